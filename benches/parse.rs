@@ -0,0 +1,128 @@
+#[macro_use] extern crate criterion;
+extern crate vobject;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, Criterion, Throughput};
+use vobject::{parse_component, parse_component_borrowed};
+
+/// Counts allocations, to show how many the borrowing parser saves.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A calendar without folded lines, where the borrowing parser does not need to copy anything.
+fn unfolded_calendar(events: usize) -> String {
+    let mut rv = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//vobject//bench//EN\r\n");
+    for i in 0..events {
+        rv.push_str(&format!("BEGIN:VEVENT\r\n\
+                              UID:event-{}@example.com\r\n\
+                              DTSTAMP:20240101T120000Z\r\n\
+                              DTSTART;TZID=Europe/Berlin:20240102T090000\r\n\
+                              DTEND;TZID=Europe/Berlin:20240102T100000\r\n\
+                              SUMMARY:Meeting number {}\r\n\
+                              LOCATION:Room 42\r\n\
+                              END:VEVENT\r\n", i, i));
+    }
+    rv.push_str("END:VCALENDAR\r\n");
+    rv
+}
+
+/// An address book of vCards, including multi-valued and grouped properties.
+fn contacts(cards: usize) -> String {
+    let mut rv = String::new();
+    for i in 0..cards {
+        rv.push_str(&format!("BEGIN:VCARD\r\n\
+                              VERSION:3.0\r\n\
+                              N:Mustermann {};Erika;;;\r\n\
+                              FN:Erika Mustermann {}\r\n\
+                              ORG:Wikipedia;Community\r\n\
+                              TEL;TYPE=CELL,VOICE:+49 170 {:07}\r\n\
+                              TEL;TYPE=HOME:+49 221 {:07}\r\n\
+                              EMAIL;TYPE=INTERNET,PREF:erika{}@example.com\r\n\
+                              item1.ADR;TYPE=HOME:;;Heidestrasse 17;Köln;;51147;Deutschland\r\n\
+                              item1.X-ABLABEL:Zuhause\r\n\
+                              UID:urn:uuid:{:08}-0000-0000-0000-000000000000\r\n\
+                              END:VCARD\r\n", i, i, i, i, i, i));
+    }
+    rv
+}
+
+/// A calendar whose long descriptions are folded many times.
+fn folded_calendar(events: usize) -> String {
+    let description = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(10);
+    let mut rv = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//vobject//bench//EN\r\n");
+    for i in 0..events {
+        let line = format!("DESCRIPTION:{}", description);
+        let folded = line.as_bytes()
+            .chunks(60)
+            .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+            .collect::<Vec<_>>()
+            .join("\r\n ");
+        rv.push_str(&format!("BEGIN:VEVENT\r\nUID:event-{}@example.com\r\n{}\r\nEND:VEVENT\r\n", i, folded));
+    }
+    rv.push_str("END:VCALENDAR\r\n");
+    rv
+}
+
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let input = unfolded_calendar(1000);
+
+    let owned = count_allocations(|| { black_box(parse_component(&input).unwrap()); });
+    let borrowed = count_allocations(|| { black_box(parse_component_borrowed(&input).unwrap()); });
+    println!("allocations for 1000 events: owned {}, borrowed {}", owned, borrowed);
+
+    let mut group = c.benchmark_group("unfolded_ics");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("parse_component", |b| b.iter(|| parse_component(black_box(&input)).unwrap()));
+    group.bench_function("parse_component_borrowed",
+                         |b| b.iter(|| parse_component_borrowed(black_box(&input)).unwrap()));
+    group.finish();
+}
+
+fn bench_vcf(c: &mut Criterion) {
+    let input = contacts(1000);
+
+    let mut group = c.benchmark_group("vcf");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("read_component", |b| b.iter(|| {
+        let mut rest = black_box(&input[..]);
+        while !rest.is_empty() {
+            rest = vobject::read_component(rest).unwrap().1;
+        }
+    }));
+    group.finish();
+}
+
+fn bench_folded(c: &mut Criterion) {
+    let input = folded_calendar(1000);
+
+    let mut group = c.benchmark_group("folded_ics");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("parse_component", |b| b.iter(|| parse_component(black_box(&input)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_vcf, bench_folded);
+criterion_main!(benches);
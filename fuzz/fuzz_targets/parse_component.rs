@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(c) = vobject::parse_component(s) {
+            // Whatever we parsed must be writable and parse to the same result
+            let written = vobject::write_component(&c);
+            assert_eq!(vobject::parse_component(&written).ok(), Some(c));
+        }
+    }
+});
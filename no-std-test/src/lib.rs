@@ -0,0 +1,44 @@
+//! Checks that vobject works without `std`, with `alloc` only:
+//!
+//! ```sh
+//! cargo test -p vobject-no-std-test
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use vobject::error::VObjectError;
+use vobject::{parse_component, write_component};
+
+/// Parse a component and write it again.
+pub fn rewrite(s: &str) -> Result<String, VObjectError> {
+    parse_component(s).map(|c| write_component(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use vobject::icalendar::ICalendar;
+
+    #[test]
+    fn test_rewrite_event() {
+        let input = "BEGIN:VCALENDAR\r\n\
+                     VERSION:2.0\r\n\
+                     PRODID:-//Example//EN\r\n\
+                     BEGIN:VEVENT\r\n\
+                     DTSTAMP:20240110T120000Z\r\n\
+                     SUMMARY:Parsed with alloc only\r\n\
+                     UID:no-std@example.com\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n";
+        let written = super::rewrite(input).unwrap();
+        assert_eq!(written, input);
+
+        let cal = ICalendar::build(&written).unwrap();
+        let event = cal.events().next().unwrap().unwrap();
+        assert_eq!(event.summary().unwrap().raw(), "Parsed with alloc only");
+        assert!(super::rewrite("BEGIN:VEVENT\r\n").is_err());
+    }
+}
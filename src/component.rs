@@ -1,12 +1,20 @@
+use std::borrow::Cow;
 use std::str::FromStr;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+#[cfg(feature = "std")] use std::io;
+use std::ops::Range;
+#[cfg(not(feature = "std"))] use std::prelude::*;
 
-use property::Property;
-use parser::{Parser, ParseErrorReason};
+use param::LIST_PARAMS;
+use property::{unescape_chars, Property, PropertyRef, TypedProperty};
+use parser::{EventState, Parser, ParseErrorReason};
+pub use parser::{ParseEvent, ParserOptions};
 
 use error::*;
+use util::{decode_bytes, Fnv1a};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Component {
     /// The name of the component, such as `VCARD` or `VEVENT`.
     pub name: String,
@@ -29,42 +37,639 @@ impl Component {
 
     /// Append the given property, preserve other same-named properties.
     pub fn push(&mut self, prop: Property) {
-        self.props.entry(prop.name.clone()).or_insert_with(Vec::new).push(prop);
+        if let Some(props) = self.props.get_mut(&normalize_name(&prop.name)[..]) {
+            props.push(prop);
+            return;
+        }
+        self.props.insert(normalize_name(&prop.name).into_owned(), vec![prop]);
     }
 
     /// Set the given property, remove other same-named properties.
     pub fn set(&mut self, prop: Property) {
-        self.props.insert(prop.name.clone(), vec![prop]);
+        self.props.insert(normalize_name(&prop.name).into_owned(), vec![prop]);
     }
 
     /// Retrieve one property by key. Returns `None` if not exactly one property was found.
+    ///
+    /// Like all lookups by name, this is case-insensitive. Property groups are ignored, so
+    /// `item1.URL` and `item2.URL` count as two properties; use `get_only_grouped` to tell them
+    /// apart.
     pub fn get_only<P: AsRef<str>>(&self, name: P) -> Option<&Property> {
-        match self.props.get(name.as_ref()) {
+        match self.props.get(&normalize_name(name.as_ref())[..]) {
             Some(x) if x.len() == 1 => Some(&x[0]),
             _ => None
         }
     }
 
     /// Retrieve properties by key. Returns an empty slice if key doesn't exist.
+    ///
+    /// Properties of all groups are returned, see `get_grouped`.
     pub fn get_all<P: AsRef<str>>(&self, name: P) -> &[Property] {
-        static EMPTY: &'static [Property] = &[];
-        match self.props.get(name.as_ref()) {
+        static EMPTY: &[Property] = &[];
+        match self.props.get(&normalize_name(name.as_ref())[..]) {
             Some(values) => &values[..],
             None => EMPTY
         }
     }
 
-    /// Remove a single property.
+    /// Mutable variant of `Component::get_only()`.
+    pub fn get_only_mut<P: AsRef<str>>(&mut self, name: P) -> Option<&mut Property> {
+        match self.props.get_mut(&normalize_name(name.as_ref())[..]) {
+            Some(x) if x.len() == 1 => Some(&mut x[0]),
+            _ => None
+        }
+    }
+
+    /// Mutable access to all properties of that name, inserting an empty list if there are none.
+    ///
+    /// Properties pushed onto the list must have a matching name, or lookups will go wrong.
+    pub fn get_all_mut<P: AsRef<str>>(&mut self, name: P) -> &mut Vec<Property> {
+        self.props.entry(normalize_name(name.as_ref()).into_owned()).or_default()
+    }
+
+    /// Retrieve one property as typed value. Returns `None` if not exactly one property was found.
+    pub fn get_typed<T: TypedProperty>(&self) -> Option<T> {
+        self.get_only(T::NAME).cloned().map(T::from)
+    }
+
+    /// Retrieve all properties of a type.
+    pub fn get_typed_all<T: TypedProperty>(&self) -> Vec<T> {
+        self.get_all(T::NAME).iter().cloned().map(T::from).collect()
+    }
+
+    /// Iterate over all properties, grouped by name in alphabetical order. Properties with the
+    /// same name are in the order they were added.
+    pub fn iter_props(&self) -> impl Iterator<Item = &Property> {
+        self.props.values().flat_map(|props| props.iter())
+    }
+
+    /// Mutable variant of `Component::iter_props()`.
+    pub fn iter_props_mut(&mut self) -> impl Iterator<Item = &mut Property> {
+        self.props.values_mut().flat_map(|props| props.iter_mut())
+    }
+
+    /// Keep only the properties for which `f` returns `true`, in this component and all
+    /// subcomponents.
+    pub fn retain_props<F: FnMut(&Property) -> bool>(&mut self, mut f: F) {
+        self.retain_props_inner(&mut f);
+    }
+
+    fn retain_props_inner<F: FnMut(&Property) -> bool>(&mut self, f: &mut F) {
+        for props in self.props.values_mut() {
+            props.retain(|p| f(p));
+        }
+        self.props.retain(|_, props| !props.is_empty());
+
+        for c in &mut self.subcomponents {
+            c.retain_props_inner(f);
+        }
+    }
+
+    /// Rewrite raw values, in this component and all subcomponents. `f` gets the name and the
+    /// raw value of each property and returns the new raw value, or `None` to keep it.
+    ///
+    /// Names, groups and parameters are left alone.
+    pub fn map_values<F: FnMut(&str, &str) -> Option<String>>(&mut self, mut f: F) {
+        self.map_values_inner(&mut f);
+    }
+
+    fn map_values_inner<F: FnMut(&str, &str) -> Option<String>>(&mut self, f: &mut F) {
+        for prop in self.iter_props_mut() {
+            if let Some(value) = f(&prop.name, &prop.raw_value) {
+                prop.raw_value = value;
+            }
+        }
+
+        for c in &mut self.subcomponents {
+            c.map_values_inner(f);
+        }
+    }
+
+    /// The number of properties, not counting those of subcomponents.
+    pub fn prop_count(&self) -> usize {
+        self.props.values().map(Vec::len).sum()
+    }
+
+    /// A hash of the names, parameters and values of all properties and subcomponents, for
+    /// detecting changes.
+    ///
+    /// The order of parameters does not matter, the order of same-named properties and of
+    /// subcomponents does. The hash does not depend on the Rust version or the platform, so it
+    /// can be stored.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        self.hash_content(&mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_content(&self, hasher: &mut Fnv1a) {
+        hasher.write_str(&self.name);
+        hasher.write(&(self.prop_count() as u64).to_le_bytes());
+        for prop in self.iter_props() {
+            hasher.write_str(&normalize_name(&prop.name));
+            hasher.write_str(prop.prop_group.as_deref().unwrap_or(""));
+            hasher.write(&(prop.params.len() as u64).to_le_bytes());
+            for (key, value) in &prop.params {
+                hasher.write_str(key);
+                hasher.write_str(value);
+            }
+            hasher.write_str(&prop.raw_value);
+        }
+
+        hasher.write(&(self.subcomponents.len() as u64).to_le_bytes());
+        for c in &self.subcomponents {
+            c.hash_content(hasher);
+        }
+    }
+
+    /// A fingerprint of the content which ignores cosmetic differences, e.g. for caching.
+    ///
+    /// Unlike `Component::content_hash()`, it is computed over this canonical form:
+    ///
+    /// * Names of components, properties, parameters and groups are uppercased.
+    /// * Parameters are sorted by name, and surrounding quotes are removed from their values.
+    /// * Values are unescaped, so `a\,b` and `a,b` are the same.
+    /// * Properties and subcomponents are sorted, their order does not matter at all.
+    ///
+    /// Folding and line endings are gone after parsing anyway. Like `content_hash()`, the
+    /// result does not depend on the Rust version or the platform.
+    pub fn fingerprint(&self) -> u64 {
+        let mut props = self.iter_props()
+            .map(|prop| {
+                let mut hasher = Fnv1a::new();
+                hasher.write_str(&normalize_name(&prop.name));
+                hasher.write_str(&normalize_name(prop.prop_group.as_deref().unwrap_or("")));
+                let params = prop.params
+                    .iter()
+                    .map(|(k, v)| (normalize_name(k), v.trim_matches('"')))
+                    .collect::<BTreeMap<_, _>>();
+                hasher.write(&(params.len() as u64).to_le_bytes());
+                for (key, value) in params {
+                    hasher.write_str(&key);
+                    hasher.write_str(value);
+                }
+                hasher.write_str(&unescape_chars(&prop.raw_value));
+                hasher.finish()
+            })
+            .collect::<Vec<_>>();
+        props.sort_unstable();
+
+        let mut subcomponents = self.subcomponents.iter().map(Component::fingerprint).collect::<Vec<_>>();
+        subcomponents.sort_unstable();
+
+        let mut hasher = Fnv1a::new();
+        hasher.write_str(&normalize_name(&self.name));
+        for hashes in &[props, subcomponents] {
+            hasher.write(&(hashes.len() as u64).to_le_bytes());
+            for hash in hashes {
+                hasher.write(&hash.to_le_bytes());
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Iterate over all extension properties, whose names start with `X-`.
+    pub fn x_properties(&self) -> impl Iterator<Item = &Property> {
+        self.props
+            .iter()
+            .filter(|&(name, _)| name.starts_with("X-"))
+            .flat_map(|(_, props)| props.iter())
+    }
+
+    /// Retrieve all properties in the given group, such as `item1` for `item1.TEL:...`.
+    ///
+    /// Group names are case-insensitive.
+    pub fn group(&self, group: &str) -> Vec<&Property> {
+        self.props
+            .values()
+            .flat_map(|props| props.iter())
+            .filter(|p| p.prop_group.as_ref().is_some_and(|g| g.eq_ignore_ascii_case(group)))
+            .collect()
+    }
+
+    /// Retrieve the properties with the given name and group, such as `item1` for
+    /// `item1.URL:...`. With `None` as group, only properties without a group are returned.
+    ///
+    /// Group names are case-insensitive.
+    pub fn get_grouped(&self, group: Option<&str>, name: &str) -> Vec<&Property> {
+        self.get_all(name)
+            .iter()
+            .filter(|p| match (p.prop_group.as_deref(), group) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                (a, b) => a == b,
+            })
+            .collect()
+    }
+
+    /// Like `get_grouped`, but returns `None` if not exactly one property was found.
+    pub fn get_only_grouped(&self, group: Option<&str>, name: &str) -> Option<&Property> {
+        match self.get_grouped(group, name)[..] {
+            [prop] => Some(prop),
+            _ => None,
+        }
+    }
+
+    /// All property groups in use, as they are spelled in the first property using them.
+    pub fn groups(&self) -> BTreeSet<&str> {
+        let mut rv = BTreeSet::new();
+        for group in self.iter_props().filter_map(|p| p.prop_group.as_deref()) {
+            if !rv.iter().any(|g: &&str| g.eq_ignore_ascii_case(group)) {
+                rv.insert(group);
+            }
+        }
+        rv
+    }
+
+    /// A group name which is not in use yet: `prefix` followed by one more than the highest
+    /// number used with it, such as `item4` if `item1` to `item3` exist. If the highest number
+    /// cannot be incremented, the lowest unused one is taken instead.
+    ///
+    /// Like all group names, `prefix` is case-insensitive.
+    pub fn next_free_group(&self, prefix: &str) -> String {
+        let used = self.iter_props()
+            .filter_map(|p| p.prop_group.as_deref())
+            .filter(|g| g.len() > prefix.len() && g.is_char_boundary(prefix.len()))
+            .filter(|g| g[..prefix.len()].eq_ignore_ascii_case(prefix))
+            .filter_map(|g| g[prefix.len()..].parse::<u64>().ok())
+            .collect::<BTreeSet<_>>();
+        let next = match used.iter().next_back() {
+            Some(highest) => highest.checked_add(1)
+                .unwrap_or_else(|| (1..).find(|n| !used.contains(n)).unwrap_or_default()),
+            None => 1,
+        };
+        format!("{}{}", prefix, next)
+    }
+
+    /// Append the given properties, all in `group`.
+    pub fn push_grouped(&mut self, group: &str, props: Vec<Property>) {
+        for mut prop in props {
+            prop.prop_group = Some(String::from(group));
+            self.push(prop);
+        }
+    }
+
+    /// Iterate over the direct subcomponents with the given name, such as `VEVENT`.
+    pub fn subcomponents_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Component> + 'a {
+        self.subcomponents.iter().filter(move |c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Mutable variant of `Component::subcomponents_named()`.
+    pub fn subcomponents_named_mut<'a>(&'a mut self, name: &'a str) -> impl Iterator<Item = &'a mut Component> + 'a {
+        self.subcomponents.iter_mut().filter(move |c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Find properties in nested subcomponents.
+    ///
+    /// The path consists of subcomponent names followed by a property name, e.g. `["VEVENT",
+    /// "VALARM", "TRIGGER"]` on a calendar returns the triggers of all alarms of all events.
+    /// A path with only a property name is the same as `Component::get_all()`.
+    pub fn find_property(&self, path: &[&str]) -> Vec<&Property> {
+        match path.split_first() {
+            None => vec![],
+            Some((name, [])) => self.get_all(name).iter().collect(),
+            Some((name, rest)) => self.subcomponents
+                .iter()
+                .filter(|c| c.name.eq_ignore_ascii_case(name))
+                .flat_map(|c| c.find_property(rest))
+                .collect(),
+        }
+    }
+
+    /// Remove a single property, the last one added of that name regardless of its group.
     pub fn pop<P: AsRef<str>>(&mut self, name: P) -> Option<Property> {
-        match self.props.get_mut(name.as_ref()) {
+        match self.props.get_mut(&normalize_name(name.as_ref())[..]) {
             Some(values) => values.pop(),
             None => None
         }
     }
 
-    /// Remove all properties
+    /// Remove all properties of that name, regardless of their groups.
     pub fn remove<P: AsRef<str>>(&mut self, name: P) -> Option<Vec<Property>> {
-        self.props.remove(name.as_ref())
+        self.props.remove(&normalize_name(name.as_ref())[..])
+    }
+
+    /// Apply `f` to all properties of that name, in order.
+    pub fn update_prop<P: AsRef<str>, F: FnMut(&mut Property)>(&mut self, name: P, f: F) {
+        if let Some(props) = self.props.get_mut(&normalize_name(name.as_ref())[..]) {
+            props.iter_mut().for_each(f);
+        }
+    }
+
+    /// Rename all properties called `from` to `to`, such as `X-ANNIVERSARY` to `ANNIVERSARY`.
+    /// They are appended to existing properties called `to`. Groups, parameters and values are
+    /// kept.
+    pub fn rename_prop<F: AsRef<str>, T: AsRef<str>>(&mut self, from: F, to: T) {
+        let to = to.as_ref();
+        let mut props = match self.props.remove(&normalize_name(from.as_ref())[..]) {
+            Some(props) => props,
+            None => return,
+        };
+        for prop in &mut props {
+            prop.name = String::from(to);
+        }
+        self.get_all_mut(to).append(&mut props);
+    }
+
+    /// Compare with another component, with relaxations configured by `options`.
+    ///
+    /// Without any options set, this is the same as `==`.
+    pub fn equivalent(&self, other: &Component, options: &EquivalenceOptions) -> bool {
+        if self.name != other.name {
+            return false;
+        }
+
+        let mut names = self.props.keys().chain(other.props.keys()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        let props_equivalent = names
+            .into_iter()
+            .filter(|n| !options.ignore_properties.iter().any(|i| i.eq_ignore_ascii_case(n)))
+            .all(|n| {
+                same_elements(self.get_all(n), other.get_all(n), options.ignore_order,
+                              |a, b| options.property_equivalent(a, b))
+            });
+
+        props_equivalent &&
+            same_elements(&self.subcomponents, &other.subcomponents, options.ignore_order,
+                          |a, b| a.equivalent(b, options))
+    }
+
+    /// Compute the changes needed to turn `self` into `other`.
+    ///
+    /// Same as `diff_ignoring` with an empty ignore list.
+    pub fn diff(&self, other: &Component) -> ComponentDiff {
+        self.diff_ignoring(other, &[])
+    }
+
+    /// Compute the changes needed to turn `self` into `other`, skipping the given properties.
+    ///
+    /// Pass `VOLATILE_PROPERTIES` to ignore timestamps which change on every save. Properties are
+    /// compared by group, parameters and raw value; parameter order does not matter.
+    /// Subcomponents are matched by name and `UID`.
+    pub fn diff_ignoring(&self, other: &Component, ignore: &[&str]) -> ComponentDiff {
+        let mut rv = ComponentDiff {
+            name: self.name.clone(),
+            uid: self.get_only("UID").map(|p| p.raw_value.clone()),
+            ..ComponentDiff::default()
+        };
+
+        let mut names = self.props.keys().chain(other.props.keys()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        for name in names.into_iter().filter(|n| !ignore.iter().any(|i| i.eq_ignore_ascii_case(n))) {
+            let mut removed = self.get_all(name).iter().collect::<Vec<_>>();
+            let mut added = vec![];
+            for prop in other.get_all(name) {
+                match removed.iter().position(|p| *p == prop) {
+                    Some(i) => { removed.remove(i); },
+                    None => added.push(prop),
+                }
+            }
+
+            let changed = removed.len().min(added.len());
+            for (old, new) in removed.drain(..changed).zip(added.drain(..changed)) {
+                rv.changed.push((old.clone(), new.clone()));
+            }
+            rv.removed.extend(removed.into_iter().cloned());
+            rv.added.extend(added.into_iter().cloned());
+        }
+
+        let uid = |c: &Component| c.get_only("UID").map(|p| p.raw_value.clone());
+        let mut unmatched = other.subcomponents.iter().collect::<Vec<_>>();
+        for sub in &self.subcomponents {
+            match unmatched.iter().position(|o| o.name == sub.name && uid(o) == uid(sub)) {
+                Some(i) => {
+                    let diff = sub.diff_ignoring(unmatched.remove(i), ignore);
+                    if !diff.is_empty() {
+                        rv.changed_components.push(diff);
+                    }
+                },
+                None => rv.removed_components.push(sub.clone()),
+            }
+        }
+        rv.added_components.extend(unmatched.into_iter().cloned());
+
+        rv
+    }
+
+    /// Render the component as an indented tree for debugging, with default `DumpOptions`.
+    pub fn dump_tree(&self) -> String {
+        self.dump_tree_with(&DumpOptions::default())
+    }
+
+    /// Render the component as an indented tree for debugging.
+    ///
+    /// Each property is written on one line as `NAME;PARAM=value: value`, sorted by name, with
+    /// the value unescaped and line breaks shown as `\n`. Subcomponents follow, indented by two
+    /// more spaces. This is not iCalendar or vCard output: nothing is folded or escaped.
+    pub fn dump_tree_with(&self, opts: &DumpOptions) -> String {
+        fn inner(buf: &mut String, c: &Component, opts: &DumpOptions, indent: usize) {
+            buf.push_str(&" ".repeat(indent));
+            buf.push_str(&c.name);
+            buf.push('\n');
+
+            for prop in c.props.values().flat_map(|props| props.iter()) {
+                buf.push_str(&" ".repeat(indent + 2));
+                if let Some(ref group) = prop.prop_group {
+                    buf.push_str(group);
+                    buf.push('.');
+                }
+                buf.push_str(&prop.name);
+                if opts.show_params {
+                    for (key, value) in &prop.params {
+                        buf.push(';');
+                        buf.push_str(key);
+                        buf.push('=');
+                        buf.push_str(value);
+                    }
+                }
+                buf.push_str(": ");
+
+                let value = prop.value_as_string().replace("\r\n", "\\n").replace('\n', "\\n");
+                match opts.max_value_len {
+                    Some(max) if value.chars().count() > max => {
+                        buf.extend(value.chars().take(max));
+                        buf.push('…');
+                    },
+                    _ => buf.push_str(&value),
+                }
+                buf.push('\n');
+            }
+
+            for sub in &c.subcomponents {
+                inner(buf, sub, opts, indent + 2);
+            }
+        }
+
+        let mut buf = String::new();
+        inner(&mut buf, self, opts, 0);
+        buf
+    }
+}
+
+/// Options for `Component::dump_tree_with`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DumpOptions {
+    /// Values longer than this many characters are cut off and end in `…`. `None` disables
+    /// truncation.
+    pub max_value_len: Option<usize>,
+
+    /// Whether to show parameters.
+    pub show_params: bool,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        DumpOptions {
+            max_value_len: Some(60),
+            show_params: true,
+        }
+    }
+}
+
+/// Property, parameter and component names are case-insensitive and stored in uppercase.
+pub(crate) fn normalize_name(name: &str) -> Cow<'_, str> {
+    if name.chars().any(char::is_lowercase) {
+        Cow::Owned(name.to_uppercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Relaxations for `Component::equivalent`.
+#[derive(Clone, Debug, Default)]
+pub struct EquivalenceOptions {
+    /// Ignore the order of same-named properties and of subcomponents.
+    pub ignore_order: bool,
+
+    /// Compare parameter names and values case-insensitively.
+    pub ignore_param_case: bool,
+
+    /// Names of properties which are not compared at all.
+    pub ignore_properties: Vec<String>,
+}
+
+impl EquivalenceOptions {
+    fn property_equivalent(&self, a: &Property, b: &Property) -> bool {
+        if !self.ignore_param_case {
+            return a == b;
+        }
+
+        let upper = |p: &Property| {
+            p.params
+                .iter()
+                .map(|(k, v)| (k.to_uppercase(), v.to_uppercase()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        a.name == b.name &&
+            a.prop_group == b.prop_group &&
+            a.raw_value == b.raw_value &&
+            upper(a) == upper(b)
+    }
+}
+
+/// Whether both slices contain equivalent elements, optionally in any order.
+fn same_elements<T, F>(a: &[T], b: &[T], ignore_order: bool, eq: F) -> bool
+    where F: Fn(&T, &T) -> bool
+{
+    if a.len() != b.len() {
+        return false;
+    }
+
+    if !ignore_order {
+        return a.iter().zip(b).all(|(x, y)| eq(x, y));
+    }
+
+    let mut unmatched = b.iter().collect::<Vec<_>>();
+    for x in a {
+        match unmatched.iter().position(|y| eq(x, y)) {
+            Some(i) => { unmatched.remove(i); },
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Properties which typically change on every save, for use with `Component::diff_ignoring`.
+pub const VOLATILE_PROPERTIES: &[&str] = &["DTSTAMP", "LAST-MODIFIED", "REV"];
+
+/// The result of `Component::diff`.
+#[derive(Clone, Debug, Default)]
+pub struct ComponentDiff {
+    /// The name of the compared component.
+    pub name: String,
+
+    /// The `UID` of the compared component, if any.
+    pub uid: Option<String>,
+
+    /// Properties only present in the other component.
+    pub added: Vec<Property>,
+
+    /// Properties only present in this component.
+    pub removed: Vec<Property>,
+
+    /// Same-named properties with different values, as pairs of old and new property.
+    pub changed: Vec<(Property, Property)>,
+
+    /// Subcomponents only present in the other component.
+    pub added_components: Vec<Component>,
+
+    /// Subcomponents only present in this component.
+    pub removed_components: Vec<Component>,
+
+    /// Subcomponents present in both, but with differences.
+    pub changed_components: Vec<ComponentDiff>,
+}
+
+impl ComponentDiff {
+    /// Whether both components are equal.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() &&
+            self.removed.is_empty() &&
+            self.changed.is_empty() &&
+            self.added_components.is_empty() &&
+            self.removed_components.is_empty() &&
+            self.changed_components.is_empty()
+    }
+
+    fn fmt_with_path(&self, f: &mut fmt::Formatter, parent: Option<&str>) -> fmt::Result {
+        let mut path = match parent {
+            Some(parent) => format!("{} > {}", parent, self.name),
+            None => self.name.clone(),
+        };
+        if let Some(ref uid) = self.uid {
+            path.push_str(&format!("[{}]", uid));
+        }
+
+        for prop in &self.removed {
+            writeln!(f, "{}: - {}", path, property_line(prop))?;
+        }
+        for prop in &self.added {
+            writeln!(f, "{}: + {}", path, property_line(prop))?;
+        }
+        for (old, new) in &self.changed {
+            writeln!(f, "{}: ~ {} -> {}", path, property_line(old), property_line(new))?;
+        }
+        for c in &self.removed_components {
+            writeln!(f, "{}: - BEGIN:{}", path, c.name)?;
+        }
+        for c in &self.added_components {
+            writeln!(f, "{}: + BEGIN:{}", path, c.name)?;
+        }
+        for diff in &self.changed_components {
+            diff.fmt_with_path(f, Some(&path))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ComponentDiff {
+    /// One line per change, e.g. `VCARD: ~ FN:Old -> FN:New`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_path(f, None)
     }
 }
 
@@ -77,11 +682,274 @@ impl FromStr for Component {
     }
 }
 
+/// A component which borrows from the parsed input where possible, see
+/// `parse_component_borrowed`.
+///
+/// Unlike `Component`, the properties are kept in the order they appeared in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentRef<'s> {
+    pub name: Cow<'s, str>,
+    pub props: Vec<PropertyRef<'s>>,
+    pub subcomponents: Vec<ComponentRef<'s>>,
+}
+
+impl<'s> ComponentRef<'s> {
+    pub fn new(name: Cow<'s, str>) -> ComponentRef<'s> {
+        ComponentRef {
+            name,
+            props: vec![],
+            subcomponents: vec![],
+        }
+    }
+
+    /// Retrieve one property by key. Returns `None` if not exactly one property was found.
+    pub fn get_only(&self, name: &str) -> Option<&PropertyRef<'s>> {
+        let mut props = self.props.iter().filter(|p| p.name.eq_ignore_ascii_case(name));
+        match (props.next(), props.next()) {
+            (Some(prop), None) => Some(prop),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the properties with the given name, ignoring case.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a PropertyRef<'s>> {
+        self.props.iter().filter(move |p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Copy into an owned `Component`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn to_owned(&self) -> Component {
+        self.clone().into_owned()
+    }
+
+    pub fn into_owned(self) -> Component {
+        let mut rv = Component::new(self.name.into_owned());
+        for prop in self.props {
+            rv.push(prop.into_owned());
+        }
+        rv.subcomponents = self.subcomponents.into_iter().map(ComponentRef::into_owned).collect();
+        rv
+    }
+}
+
+/// Parse exactly one component without copying property names, values and parameters, unless
+/// they span folded lines. Trailing data generates errors.
+pub fn parse_component_borrowed(s: &str) -> VObjectResult<ComponentRef<'_>> {
+    let mut parser = Parser::with_options(s, ParserOptions::default());
+    let rv = parser.consume_component_ref()?;
+    if !parser.eof() {
+        let at = parser.pos;
+        return Err(ParseErrorReason::TrailingData { at, data: parser.input[at..].into() }.into());
+    }
+
+    Ok(rv)
+}
+
+/// Pull-based parser that reports one component as a stream of `ParseEvent`s instead of building
+/// a tree, e.g. to report progress or to stop early once the interesting part has been seen.
+///
+/// The events are exactly what `read_component` assembles into a `Component`, and the same errors
+/// are reported. After the outermost `END` or the first error the stream ends; `remainder` returns
+/// the unparsed rest of the input.
+///
+/// ```
+/// use vobject::component::{EventParser, ParseEvent};
+///
+/// let mut events = EventParser::new("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
+/// let starts = events
+///     .by_ref()
+///     .filter(|e| matches!(e, Ok(ParseEvent::ComponentStart(_))))
+///     .count();
+/// assert_eq!(starts, 2);
+/// assert_eq!(events.remainder(), "");
+/// ```
+pub struct EventParser<'s> {
+    parser: Parser<'s>,
+    state: EventState<'s>,
+}
+
+impl<'s> EventParser<'s> {
+    pub fn new(s: &'s str) -> EventParser<'s> {
+        EventParser::with_options(s, &ParserOptions::default())
+    }
+
+    pub fn with_options(s: &'s str, opts: &ParserOptions) -> EventParser<'s> {
+        EventParser {
+            parser: Parser::with_options(s, *opts),
+            state: EventState::default(),
+        }
+    }
+
+    /// The next event, or `None` once the component is complete or an error was returned.
+    pub fn next_event(&mut self) -> Option<VObjectResult<ParseEvent<'s>>> {
+        self.parser.next_event(&mut self.state).map(|rv| rv.map_err(From::from))
+    }
+
+    /// The part of the input that has not been consumed yet.
+    pub fn remainder(&self) -> &'s str {
+        if self.parser.eof() {
+            ""
+        } else {
+            &self.parser.input[self.parser.pos..]
+        }
+    }
+}
+
+impl<'s> Iterator for EventParser<'s> {
+    type Item = VObjectResult<ParseEvent<'s>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event()
+    }
+}
+
 /// Parse exactly one component. Trailing data generates errors.
 pub fn parse_component(s: &str) -> VObjectResult<Component> {
     let (rv, new_s) = read_component(s)?;
     if !new_s.is_empty() {
-        return Err(ParseErrorReason::TrailingData(new_s.into()).into());
+        let at = s.len() - new_s.len();
+        return Err(ParseErrorReason::TrailingData { at, data: new_s.into() }.into());
+    }
+
+    Ok(rv)
+}
+
+/// Byte ranges of the properties of a parsed component in the input, in the same shape as the
+/// `Component`, see `parse_component_with_spans`.
+///
+/// A span covers all physical lines of a folded property, from the start of its group or name to
+/// the end of its value, without the final line break. Replacing it with a re-folded property
+/// keeps the rest of the input intact.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpanMap {
+    /// The spans of the properties, keyed and ordered like `Component::props`.
+    pub props: BTreeMap<String, Vec<Range<usize>>>,
+    /// The spans within the subcomponents, in the same order as `Component::subcomponents`.
+    pub subcomponents: Vec<SpanMap>,
+}
+
+impl SpanMap {
+    /// The span of the `index`th property of that name, as in `Component::get_all(name)[index]`.
+    pub fn get<P: AsRef<str>>(&self, name: P, index: usize) -> Option<Range<usize>> {
+        self.props.get(&normalize_name(name.as_ref())[..])?.get(index).cloned()
+    }
+}
+
+/// Parse exactly one component like `parse_component`, and also record where each property is
+/// in `s`, e.g. to highlight it in an editor.
+pub fn parse_component_with_spans(s: &str) -> VObjectResult<(Component, SpanMap)> {
+    let mut parser = Parser::with_options(s, ParserOptions::default());
+    let mut state = EventState::default();
+    let mut stack: Vec<(Component, SpanMap)> = Vec::new();
+
+    loop {
+        // Skipped by the parser anyway, but the span should start at the property
+        parser.skip_blank_lines();
+        let start = parser.pos;
+        let event = match parser.next_event(&mut state) {
+            Some(event) => event?,
+            None => break,
+        };
+
+        match event {
+            ParseEvent::ComponentStart(name) => stack.push((Component::new(name), SpanMap::default())),
+            ParseEvent::Property(property) => {
+                if let Some((component, spans)) = stack.last_mut() {
+                    let end = start + s[start..parser.pos].trim_end_matches(&['\r', '\n'][..]).len();
+                    spans.props
+                        .entry(normalize_name(&property.name).into_owned())
+                        .or_default()
+                        .push(start..end);
+                    component.push(property.into_owned());
+                }
+            },
+            ParseEvent::ComponentEnd(_) => {
+                let (component, spans) = match stack.pop() {
+                    Some(closed) => closed,
+                    None => break,
+                };
+                match stack.last_mut() {
+                    Some((parent, parent_spans)) => {
+                        parent.subcomponents.push(component);
+                        parent_spans.subcomponents.push(spans);
+                    },
+                    None if !parser.eof() => {
+                        let at = parser.pos;
+                        return Err(ParseErrorReason::TrailingData { at, data: s[at..].into() }.into());
+                    },
+                    None => return Ok((component, spans)),
+                }
+            },
+        }
+    }
+
+    unreachable!("the event stream ends only after the outermost END or an error")
+}
+
+/// Parse all components in `s`, such as the cards of a `.vcf` file. Blank lines between them are
+/// skipped.
+pub fn parse_components(s: &str) -> VObjectResult<Vec<Component>> {
+    let mut parser = Parser::with_options(s, ParserOptions::default());
+    let mut rv = vec![];
+    loop {
+        parser.skip_blank_lines();
+        if parser.eof() {
+            return Ok(rv);
+        }
+        rv.push(parser.consume_component()?);
+    }
+}
+
+/// Like `parse_components`, but skip broken components instead of failing, e.g. to import the
+/// rest of a large `.vcf` file with one corrupt card.
+///
+/// After an error, parsing resumes at the next line starting with `BEGIN:`. The errors are
+/// returned with the byte offset at which the broken component started. Input without errors
+/// yields the same components as `parse_components`.
+pub fn parse_components_lenient(s: &str) -> (Vec<Component>, Vec<(VObjectError, usize)>) {
+    let mut parser = Parser::with_options(s, ParserOptions::default());
+    let mut components = vec![];
+    let mut errors = vec![];
+    loop {
+        parser.skip_blank_lines();
+        if parser.eof() {
+            return (components, errors);
+        }
+
+        let start = parser.pos;
+        match parser.consume_component() {
+            Ok(c) => components.push(c),
+            Err(e) => {
+                errors.push((e.into(), start));
+                parser.pos = next_begin_line(s, start).unwrap_or(s.len());
+            },
+        }
+    }
+}
+
+/// The offset of the next line after the one at `pos` which starts with `BEGIN:`.
+fn next_begin_line(s: &str, pos: usize) -> Option<usize> {
+    s[pos..]
+        .match_indices('\n')
+        .map(|(i, _)| pos + i + 1)
+        .find(|&start| s.as_bytes()[start..].get(..6).is_some_and(|b| b.eq_ignore_ascii_case(b"BEGIN:")))
+}
+
+/// Parse exactly one component from raw bytes, see `parse_component`.
+///
+/// Besides UTF-8, UTF-16 with a byte order mark is accepted. Enable the `encoding` feature to
+/// read any other input as latin-1 instead of failing with `VObjectError::Encoding`. Positions in
+/// parse errors refer to the decoded text.
+pub fn parse_bytes(b: &[u8]) -> VObjectResult<Component> {
+    parse_component(&decode_bytes(b)?)
+}
+
+/// Parse exactly one component, with additional checks configured by `opts`.
+pub fn parse_component_with(s: &str, opts: &ParserOptions) -> VObjectResult<Component> {
+    let (rv, new_s) = read_component_with(s, opts)?;
+    if !new_s.is_empty() {
+        let at = s.len() - new_s.len();
+        return Err(ParseErrorReason::TrailingData { at, data: new_s.into() }.into());
     }
 
     Ok(rv)
@@ -89,7 +957,13 @@ pub fn parse_component(s: &str) -> VObjectResult<Component> {
 
 /// Parse one component and return the rest of the string.
 pub fn read_component(s: &str) -> VObjectResult<(Component, &str)> {
-    let mut parser = Parser::new(s);
+    read_component_with(s, &ParserOptions::default())
+}
+
+/// Parse one component with additional checks configured by `opts`, and return the rest of the
+/// string.
+pub fn read_component_with<'s>(s: &'s str, opts: &ParserOptions) -> VObjectResult<(Component, &'s str)> {
+    let mut parser = Parser::with_options(s, *opts);
     let rv = parser.consume_component()?;
     let new_s = if parser.eof() {
         ""
@@ -99,48 +973,235 @@ pub fn read_component(s: &str) -> VObjectResult<(Component, &str)> {
     Ok((rv, new_s))
 }
 
+/// Line endings for `write_component_with`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LineEnding {
+    /// `\r\n`, as required by the RFCs.
+    Crlf,
+    /// `\n`
+    Lf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Lf   => "\n",
+        }
+    }
+}
+
+/// How `WriteOptions::fold_width` is measured.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FoldMode {
+    /// Count octets of the UTF-8 encoding, as required by the RFCs.
+    Bytes,
+    /// Count characters.
+    Chars,
+}
+
+/// Options for `write_component_with`.
+///
+/// The default options produce the same output as `write_component`: CRLF line endings and lines
+/// folded at 75 octets.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WriteOptions {
+    pub line_ending: LineEnding,
+
+    /// Maximum length of a line, excluding the line ending. `None` disables folding.
+    pub fold_width: Option<usize>,
+    pub fold_mode: FoldMode,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            line_ending: LineEnding::Crlf,
+            fold_width: Some(75),
+            fold_mode: FoldMode::Bytes,
+        }
+    }
+}
+
+/// Properties which are written before all others, in this order.
+const LEADING_PROPERTIES: &[&str] = &["VERSION", "PRODID"];
+
 /// Write a component to a String.
+///
+/// The output is deterministic: `VERSION` and `PRODID` come first, then the other properties
+/// sorted by name, and properties of the same name in the order they were added. Subcomponents
+/// follow in their original order.
 pub fn write_component(c: &Component) -> String {
-    fn inner(buf: &mut String, c: &Component) {
-        buf.push_str("BEGIN:");
-        buf.push_str(&c.name);
-        buf.push_str("\r\n");
+    write_component_with(c, &WriteOptions::default())
+}
+
+/// Write a component to a String, with custom line endings and folding.
+pub fn write_component_with(c: &Component, opts: &WriteOptions) -> String {
+    let mut buf = String::new();
+    write_component_fmt(&mut buf, c, opts).expect("writing to a String does not fail");
+    buf
+}
+
+/// Like `write_component_with`, but write the output to `w` line by line, instead of building a
+/// String first. This saves memory for large calendars. Requires the `std` feature.
+///
+/// The output is written in many small pieces, so `w` should be buffered, e.g. with a
+/// `std::io::BufWriter`.
+#[cfg(feature = "std")]
+pub fn write_component_to<W: io::Write>(c: &Component, w: &mut W, opts: &WriteOptions) -> io::Result<()> {
+    struct Adapter<'a, W: 'a> {
+        inner: &'a mut W,
+        error: Option<io::Error>,
+    }
+
+    impl<'a, W: io::Write> fmt::Write for Adapter<'a, W> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.inner.write_all(s.as_bytes()).map_err(|e| {
+                self.error = Some(e);
+                fmt::Error
+            })
+        }
+    }
+
+    let mut adapter = Adapter { inner: w, error: None };
+    write_component_fmt(&mut adapter, c, opts)
+        .map_err(|_| adapter.error.take().unwrap_or_else(|| io::Error::other("formatting failed")))
+}
 
-        for (prop_name, props) in &c.props {
+fn write_component_fmt<W: fmt::Write>(w: &mut W, c: &Component, opts: &WriteOptions) -> fmt::Result {
+    fn inner<W: fmt::Write>(buf: &mut W, c: &Component, opts: &WriteOptions) -> fmt::Result {
+        let eol = opts.line_ending.as_str();
+
+        buf.write_str("BEGIN:")?;
+        buf.write_str(&normalize_name(&c.name))?;
+        buf.write_str(eol)?;
+
+        let leading = LEADING_PROPERTIES.iter().filter_map(|name| c.props.get(*name));
+        let rest = c.props
+            .iter()
+            .filter(|&(name, _)| !LEADING_PROPERTIES.contains(&&name[..]))
+            .map(|(_, props)| props);
+
+        for props in leading.chain(rest) {
             for prop in props.iter() {
-                if let Some(ref x) = prop.prop_group {
-                    buf.push_str(&x);
-                    buf.push('.');
-                };
-                buf.push_str(&prop_name);
-                for (param_key, param_value) in &prop.params {
-                    buf.push(';');
-                    buf.push_str(&param_key);
-                    buf.push('=');
-                    buf.push_str(&param_value);
-                }
-                buf.push(':');
-                buf.push_str(&fold_line(&prop.raw_value));
-                buf.push_str("\r\n");
+                buf.write_str(&prop.write_line(opts))?;
+                buf.write_str(eol)?;
             }
         }
 
         for subcomponent in &c.subcomponents {
-            inner(buf, subcomponent);
+            inner(buf, subcomponent, opts)?;
+        }
+
+        buf.write_str("END:")?;
+        buf.write_str(&normalize_name(&c.name))?;
+        buf.write_str(eol)
+    }
+
+    inner(w, c, opts)
+}
+
+/// Like `write_component`, but refuse to write data which could not be parsed again, such as
+/// names with spaces, parameter values with double quotes or values with line breaks. Such data
+/// can only come from constructing components or properties by hand.
+///
+/// Names must consist of letters, digits and dashes. Parameter values and property values must
+/// not contain control characters other than tab, and parameter values no double quotes apart
+/// from surrounding ones.
+pub fn write_component_checked(c: &Component) -> VObjectResult<String> {
+    check_component(c)?;
+    Ok(write_component(c))
+}
+
+fn check_component(c: &Component) -> VObjectResult<()> {
+    let invalid = |property: Option<&Property>, reason: String| VObjectError::InvalidData {
+        component: c.name.clone(),
+        property: property.map(|p| p.name.clone()),
+        reason,
+    };
+
+    if !is_name(&c.name) {
+        return Err(invalid(None, format!("invalid component name {:?}", c.name)));
+    }
+    for prop in c.iter_props() {
+        check_property(prop).map_err(|reason| invalid(Some(prop), reason))?;
+    }
+    c.subcomponents.iter().try_for_each(check_component)
+}
+
+fn check_property(prop: &Property) -> Result<(), String> {
+    let is_control = |c: char| c.is_control() && c != '\t';
+
+    if !is_name(&prop.name) {
+        return Err(format!("invalid property name {:?}", prop.name));
+    }
+    if let Some(ref group) = prop.prop_group {
+        if !is_name(group) {
+            return Err(format!("invalid group {:?}", group));
         }
+    }
+    for (key, value) in &prop.params {
+        if !is_name(key) {
+            return Err(format!("invalid parameter name {:?}", key));
+        }
+        let quoted = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+        let inner = if quoted { &value[1..value.len() - 1] } else { &value[..] };
+        if inner.contains('"') || inner.contains(is_control) {
+            return Err(format!("invalid value of parameter {}: {:?}", key, value));
+        }
+    }
+    if prop.raw_value.contains(is_control) {
+        return Err(format!("control character in value {:?}", prop.raw_value));
+    }
+    Ok(())
+}
+
+/// Whether `s` is a valid name of a component, property, group or parameter.
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c == '-' || c.is_ascii_alphanumeric())
+}
 
-        buf.push_str("END:");
-        buf.push_str(&c.name);
-        buf.push_str("\r\n");
+/// Write group, name and parameters of a property, up to and including the colon.
+fn push_property(buf: &mut String, prop: &Property) {
+    if let Some(ref x) = prop.prop_group {
+        buf.push_str(x);
+        buf.push('.');
+    };
+    buf.push_str(&normalize_name(&prop.name));
+    for (param_key, param_value) in &prop.params {
+        buf.push(';');
+        buf.push_str(&normalize_name(param_key));
+        // Bare vCard 2.1 parameters like `TEL;HOME:...` have no value
+        if param_value.is_empty() {
+            continue;
+        }
+        buf.push('=');
+        // Values are stored unquoted, so quote them if they contain a separator
+        let is_list = LIST_PARAMS.iter().any(|l| param_key.eq_ignore_ascii_case(l));
+        if param_value.contains([':', ';']) || (!is_list && param_value.contains(',')) {
+            buf.push('"');
+            buf.push_str(param_value);
+            buf.push('"');
+        } else {
+            buf.push_str(param_value);
+        }
     }
+    buf.push(':');
+}
 
+/// An unfolded contentline for the given property.
+pub(crate) fn property_line(prop: &Property) -> String {
     let mut buf = String::new();
-    inner(&mut buf, c);
+    push_property(&mut buf, prop);
+    buf.push_str(&prop.raw_value);
     buf
 }
 
 /// Fold contentline to 75 bytes or less. This function assumes the input
 /// to be unfolded, which means no '\n' or '\r' in it.
+///
+/// The leading space of continuation lines is not counted. The writer functions count it, see
+/// `WriteOptions::fold_width`.
 pub fn fold_line(line: &str) -> String {
     let limit = 75;
     let len = line.len();
@@ -150,7 +1211,7 @@ pub fn fold_line(line: &str) -> String {
     let mut pos = 0;
     let mut next_pos = limit;
     while bytes_remaining > limit {
-        while line.is_char_boundary(next_pos) == false {
+        while !line.is_char_boundary(next_pos) {
             next_pos -= 1;
         }
         ret.push_str(&line[pos..next_pos]);
@@ -165,10 +1226,496 @@ pub fn fold_line(line: &str) -> String {
     ret
 }
 
+/// Fold `line` so that no line is longer than `width` bytes or chars, including the leading space
+/// of continuation lines. The whole line counts, so long parameters are folded as well. Lines are
+/// only ever split on char boundaries, and never inside an escape sequence such as `\\n` in values
+/// or `^'` in parameters (RFC 6868).
+pub(crate) fn push_folded(buf: &mut String, line: &str, width: usize, mode: FoldMode, eol: &str) {
+    let measure = |s: &str| match mode {
+        FoldMode::Bytes => s.len(),
+        FoldMode::Chars => s.chars().count(),
+    };
+
+    // Every line must contain at least one unit, no matter how small the width is
+    let mut limit = width.max(1);
+    let mut start = 0;
+    let mut used = 0;
+
+    // The value starts after the first colon outside of a quoted parameter value
+    let mut in_value = false;
+    let mut quoted = false;
+
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        // A backslash in the value or a caret in parameters and the escaped char are kept together
+        let end = match (c, chars.peek()) {
+            ('\\', Some(&(j, escaped))) if in_value => {
+                chars.next();
+                j + escaped.len_utf8()
+            },
+            ('^', Some(&(j, escaped))) if !in_value => {
+                chars.next();
+                j + escaped.len_utf8()
+            },
+            ('"', _) if !in_value => {
+                quoted = !quoted;
+                i + 1
+            },
+            (':', _) if !in_value && !quoted => {
+                in_value = true;
+                i + 1
+            },
+            _ => i + c.len_utf8(),
+        };
+        let len = measure(&line[i..end]);
+
+        if used + len > limit && i > start {
+            buf.push_str(&line[start..i]);
+            buf.push_str(eol);
+            buf.push(' ');
+            start = i;
+            used = 0;
+            limit = width.saturating_sub(1).max(1);
+        }
+        used += len;
+    }
+
+    buf.push_str(&line[start..]);
+}
+
 
 #[cfg(test)]
 mod tests {
-    use component::fold_line;
+    #[cfg(not(feature = "std"))] use std::prelude::*;
+    use component::{fold_line, normalize_name, parse_component, property_line, write_component, write_component_with, Component, FoldMode, LineEnding, WriteOptions};
+    use property::Property;
+
+    #[test]
+    fn test_parse_borrowed() {
+        use std::borrow::Cow;
+        use component::parse_component_borrowed;
+
+        let input = "BEGIN:VCALENDAR\r\n\
+                     BEGIN:VEVENT\r\n\
+                     item1.summary;Language=de;X-FOO=\"a:b\":Weihnachts\r\n feier\r\n\
+                     LOCATION:Berlin\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n";
+
+        let borrowed = parse_component_borrowed(input).unwrap();
+        let event = &borrowed.subcomponents[0];
+        assert!(matches!(event.name, Cow::Borrowed("VEVENT")));
+
+        let location = event.get_only("location").unwrap();
+        assert!(matches!(location.raw_value, Cow::Borrowed("Berlin")));
+
+        let summary = event.get_only("SUMMARY").unwrap();
+        assert!(matches!(summary.name, Cow::Owned(_)));
+        assert!(matches!(summary.raw_value, Cow::Owned(_)));
+        assert_eq!(summary.raw_value, "Weihnachtsfeier");
+        assert_eq!(summary.prop_group.as_deref(), Some("item1"));
+        assert_eq!(summary.param("language"), Some("de"));
+        assert_eq!(summary.param("X-FOO"), Some("a:b"));
+        assert_eq!(event.get_all("END").count(), 0);
+
+        assert_eq!(borrowed.to_owned(), parse_component(input).unwrap());
+        assert!(parse_component_borrowed("BEGIN:A\nEND:A\nfoo").is_err());
+    }
+
+    #[test]
+    fn test_iter_props_and_content_hash() {
+        let input = "BEGIN:VCARD\r\n\
+                     VERSION:4.0\r\n\
+                     FN:Erika Mustermann\r\n\
+                     TEL;TYPE=cell;PREF=1:+49 170 1234567\r\n\
+                     TEL:+49 221 1234567\r\n\
+                     PHOTO:data:image/jpeg;base64,AAAA\r\n\
+                     END:VCARD\r\n";
+        let mut card = parse_component(input).unwrap();
+
+        assert_eq!(card.prop_count(), 5);
+        let names = card.iter_props().map(|p| &p.name[..]).collect::<Vec<_>>();
+        assert_eq!(names, vec!["FN", "PHOTO", "TEL", "TEL", "VERSION"]);
+
+        let reordered = parse_component(&input.replace("TYPE=cell;PREF=1", "PREF=1;TYPE=cell")
+                                              .replace("FN:Erika Mustermann\r\n", "")
+                                              .replace("END:VCARD", "FN:Erika Mustermann\r\nEND:VCARD"))
+            .unwrap();
+        assert_eq!(reordered.content_hash(), card.content_hash());
+
+        let hash = card.content_hash();
+        for prop in card.iter_props_mut().filter(|p| p.name == "PHOTO") {
+            prop.raw_value.clear();
+        }
+        assert_eq!(card.get_only("PHOTO").unwrap().raw_value, "");
+        assert_ne!(card.content_hash(), hash);
+
+        let mut swapped = parse_component(input).unwrap();
+        swapped.props.get_mut("TEL").unwrap().reverse();
+        assert_ne!(swapped.content_hash(), hash);
+        assert_ne!(Component::new("A").content_hash(), Component::new("B").content_hash());
+    }
+
+    #[test]
+    fn test_property_lines() {
+        let long = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor";
+        let mut prop = Property::new("DESCRIPTION", long);
+        prop.prop_group = Some("item1".to_owned());
+        prop.params.insert("LANGUAGE".into(), "en".into());
+        prop.params.insert("X-URL".into(), "http://example.com".into());
+
+        let line = prop.write_line(&WriteOptions::default());
+        assert!(line.starts_with("item1.DESCRIPTION;LANGUAGE=en;X-URL=\"http://example.com\":Lorem"));
+        assert!(line.split("\r\n").all(|l| l.len() <= 75));
+        assert!(!line.ends_with("\r\n"));
+
+        let unfolded = prop.write_line(&WriteOptions { fold_width: None, ..WriteOptions::default() });
+        assert_eq!(unfolded, line.replace("\r\n ", ""));
+
+        assert_eq!(Property::parse_line(&line).unwrap(), prop);
+        assert_eq!(Property::parse_line(&(line.clone() + "\r\n")).unwrap(), prop);
+        assert!(Property::parse_line("TEL:1\r\nTEL:2").is_err());
+        assert!(Property::parse_line(":1").is_err());
+
+        let mut c = Component::new("VCARD");
+        c.push(Property::new("VERSION", "4.0"));
+        c.push(prop.clone());
+        let expected = format!("BEGIN:VCARD\r\nVERSION:4.0\r\n{}\r\nEND:VCARD\r\n", line);
+        assert_eq!(write_component(&c), expected);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let input = "BEGIN:VCARD\r\n\
+                     VERSION:4.0\r\n\
+                     FN:Erika Mustermann\r\n\
+                     item1.TEL;TYPE=cell;PREF=1:+49 170 1234567\r\n\
+                     TEL;TYPE=home:+49 221 1234567\r\n\
+                     NOTE:Hello\\, world\r\n\
+                     END:VCARD\r\n";
+        let fingerprint = parse_component(input).unwrap().fingerprint();
+
+        let cosmetic = "begin:vcard\n\
+                        TEL;type=home:+49 221 \n 1234567\n\
+                        Note:Hello, world\n\
+                        ITEM1.tel;pref=\"1\";TYPE=cell:+49 170 1234567\n\
+                        version:4.0\n\
+                        FN:Erika Muster\n\tmann\n\
+                        END:VCARD\n";
+        assert_eq!(parse_component(cosmetic).unwrap().fingerprint(), fingerprint);
+
+        let edited = input.replace("1234567\r\nNOTE", "7654321\r\nNOTE");
+        assert_ne!(parse_component(&edited).unwrap().fingerprint(), fingerprint);
+        let moved = input.replace("TEL;TYPE=home", "TEL;TYPE=work");
+        assert_ne!(parse_component(&moved).unwrap().fingerprint(), fingerprint);
+
+        let mut a = Component::new("VCALENDAR");
+        a.subcomponents.push(Component::new("VEVENT"));
+        a.subcomponents.push(Component::new("VTODO"));
+        let mut b = a.clone();
+        b.subcomponents.reverse();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        b.subcomponents.pop();
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn test_retain_and_map() {
+        let input = "BEGIN:VCALENDAR\r\n\
+                     BEGIN:VEVENT\r\n\
+                     ATTACH;ENCODING=BASE64;VALUE=BINARY:AAAA\r\n\
+                     item1.ATTENDEE;CN=Erika:mailto:erika@example.com\r\n\
+                     SUMMARY:Lunch\r\n\
+                     BEGIN:VALARM\r\n\
+                     ATTACH;ENCODING=BASE64;VALUE=BINARY:BBBB\r\n\
+                     ACTION:AUDIO\r\n\
+                     END:VALARM\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n";
+        let mut c = parse_component(input).unwrap();
+
+        c.retain_props(|p| p.name != "ATTACH");
+        c.map_values(|name, value| if name == "ATTENDEE" { Some(value.replace("erika", "***")) } else { None });
+
+        let event = &c.subcomponents[0];
+        assert!(event.get_all("ATTACH").is_empty() && !event.props.contains_key("ATTACH"));
+        assert!(event.subcomponents[0].get_all("ATTACH").is_empty());
+        assert_eq!(event.subcomponents[0].get_only("ACTION").unwrap().raw_value, "AUDIO");
+        assert_eq!(event.get_only("SUMMARY").unwrap().raw_value, "Lunch");
+
+        let attendee = event.get_only("ATTENDEE").unwrap();
+        assert_eq!(attendee.raw_value, "mailto:***@example.com");
+        assert_eq!(attendee.params.get("CN"), Some("Erika"));
+        assert_eq!(attendee.prop_group.as_deref(), Some("item1"));
+    }
+
+    #[test]
+    fn test_parse_components() {
+        use component::parse_components;
+
+        let cards = parse_components("\r\nBEGIN:VCARD\r\nFN:a\r\nEND:VCARD\r\n\r\n\
+                                      BEGIN:VCARD\nFN:b\nEND:VCARD").unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[1].get_only("FN").unwrap().raw_value, "b");
+        assert!(parse_components("").unwrap().is_empty());
+        assert!(parse_components("BEGIN:VCARD\r\nEND:VCARD\r\nFN:a\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_components_lenient() {
+        use component::{parse_components, parse_components_lenient};
+
+        let good = "BEGIN:VCARD\r\nFN:a\r\nEND:VCARD\r\n\r\nBEGIN:VCARD\r\nFN:b\r\nEND:VCARD\r\n";
+        let (cards, errors) = parse_components_lenient(good);
+        assert_eq!(cards, parse_components(good).unwrap());
+        assert!(errors.is_empty());
+
+        let input = "BEGIN:VCARD\r\nFN:a\r\nEND:VCARD\r\n\
+                     BEGIN:VCARD\r\nFN:b\r\n;broken\r\nEND:VCARD\r\n\
+                     BEGIN:VCARD\r\nFN:c\r\nEND:VCARD\r\n\
+                     begin:VCARD\r\nFN:d\r\nEND:VCALENDAR\r\n\
+                     BEGIN:VCARD\r\nFN:e\r\nEND:VCARD\r\n";
+        let (cards, errors) = parse_components_lenient(input);
+        let names = cards.iter().map(|c| c.get_only("FN").unwrap().raw_value.clone()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "c", "e"]);
+        let offsets = errors.iter().map(|&(_, at)| at).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![input.find("BEGIN:VCARD\r\nFN:b").unwrap(), input.find("begin:").unwrap()]);
+        assert!(errors[1].0.to_string().contains("VCALENDAR"));
+
+        // Nothing to resume at
+        let (cards, errors) = parse_components_lenient("BEGIN:VCARD\r\nFN:a\r\n");
+        assert!(cards.is_empty());
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_grouped_accessors() {
+        let c = parse_component("BEGIN:VCARD\r\n\
+            item1.URL:https://example.com/home\r\n\
+            item1.X-ABLabel:_$!<HomePage>!$_\r\n\
+            ITEM2.URL:https://example.com/work\r\n\
+            URL:https://example.com\r\n\
+            END:VCARD\r\n").unwrap();
+
+        assert!(c.get_only("URL").is_none());
+        assert_eq!(c.get_all("URL").len(), 3);
+
+        let raw = |props: Vec<&Property>| props.iter().map(|p| p.raw_value.clone()).collect::<Vec<_>>();
+        assert_eq!(raw(c.get_grouped(Some("item1"), "url")), vec!["https://example.com/home"]);
+        assert_eq!(raw(c.get_grouped(Some("item2"), "URL")), vec!["https://example.com/work"]);
+        assert_eq!(raw(c.get_grouped(None, "URL")), vec!["https://example.com"]);
+        assert!(c.get_grouped(Some("item3"), "URL").is_empty());
+
+        assert_eq!(c.get_only_grouped(None, "URL").unwrap().raw_value, "https://example.com");
+        assert_eq!(c.get_only_grouped(Some("ITEM1"), "URL").unwrap().raw_value, "https://example.com/home");
+        assert!(c.get_only_grouped(Some("item1"), "FN").is_none());
+
+        assert_eq!(c.groups().into_iter().collect::<Vec<_>>(), vec!["ITEM2", "item1"]);
+    }
+
+    #[test]
+    fn test_next_free_group() {
+        let mut c = parse_component("BEGIN:VCARD\r\n\
+            item1.URL:https://example.com/home\r\n\
+            ITEM2.URL:https://example.com/work\r\n\
+            item3.TEL:+1 555 0100\r\n\
+            home.TEL:+1 555 0199\r\n\
+            END:VCARD\r\n").unwrap();
+        assert_eq!(c.next_free_group("item"), "item4");
+        assert_eq!(c.next_free_group("ITEM"), "ITEM4");
+        assert_eq!(c.next_free_group("label"), "label1");
+
+        let upper = parse_component("BEGIN:VCARD\r\n\
+            item1.URL:https://example.com/home\r\n\
+            ITEM2.URL:https://example.com/work\r\n\
+            END:VCARD\r\n").unwrap();
+        assert_eq!(upper.next_free_group("item"), "item3");
+
+        let group = c.next_free_group("item");
+        c.push_grouped(&group, vec![
+            Property::new("URL", "https://example.com/blog"),
+            Property::new("X-ABLabel", "Blog"),
+        ]);
+        assert_eq!(c.group("item4").len(), 2);
+        assert_eq!(c.get_only_grouped(Some("item4"), "X-ABLABEL").unwrap().raw_value, "Blog");
+        assert_eq!(c.next_free_group("item"), "item5");
+
+        let max = parse_component("BEGIN:VCARD\r\n\
+            item1.URL:https://example.com/home\r\n\
+            item18446744073709551615.URL:https://example.com/work\r\n\
+            END:VCARD\r\n").unwrap();
+        assert_eq!(max.next_free_group("item"), "item2");
+    }
+
+    #[test]
+    fn test_set_value() {
+        let mut card = parse_component("BEGIN:VCARD\r\n\
+            VERSION:2.1\r\n\
+            FN;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE;LANGUAGE=de:J=C3=BCrgen M=C3=BCller\r\n\
+            N;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:M=C3=BCller;J=C3=BCrgen\r\n\
+            item1.NOTE;QUOTED-PRINTABLE;HOME:Zeile=0D=0AZeile\r\n\
+            END:VCARD\r\n").unwrap();
+
+        let mut fn_ = card.pop("FN").unwrap();
+        fn_.set_value("Jürgen Müller; Köln").unwrap();
+        card.push(fn_);
+        let note = card.pop("NOTE").unwrap().with_value("Zeile\nZeile").unwrap();
+        card.push(note);
+        let mut n = card.get_only("N").unwrap().clone();
+        assert!(n.set_value("Müller;Jürgen").is_err());
+
+        let reparsed = parse_component(&write_component(&card)).unwrap();
+        let fn_ = reparsed.get_only("FN").unwrap();
+        assert_eq!(fn_.raw_value, "Jürgen Müller\\; Köln");
+        assert_eq!(fn_.value_as_string(), "Jürgen Müller; Köln");
+        assert_eq!(fn_.params.iter().collect::<Vec<_>>(), vec![(&"LANGUAGE".to_owned(), &"de".to_owned())]);
+
+        let note = reparsed.get_only("NOTE").unwrap();
+        assert_eq!(note.value_as_string(), "Zeile\nZeile");
+        assert_eq!(note.types(), vec!["HOME"]);
+        assert_eq!(note.prop_group.as_deref(), Some("item1"));
+    }
+
+    #[test]
+    fn test_duplicate_params() {
+        let card = parse_component("BEGIN:VCARD\r\n\
+            TEL;TYPE=HOME;PREF=1;type=CELL:+49 221 1234567\r\n\
+            END:VCARD\r\n").unwrap();
+        let tel = card.get_only("TEL").unwrap();
+        assert_eq!(tel.param("type"), Some("HOME,CELL"));
+        assert_eq!(tel.param_values("TYPE"), vec!["HOME", "CELL"]);
+        assert_eq!(tel.param("PREF"), Some("1"));
+        assert!(tel.param("LABEL").is_none());
+
+        let written = write_component(&card);
+        assert!(written.contains("TEL;PREF=1;TYPE=HOME,CELL:+49 221 1234567\r\n"));
+        assert_eq!(parse_component(&written).unwrap(), card);
+    }
+
+    #[test]
+    fn test_mutable_accessors() {
+        let mut c = parse_component("BEGIN:VCARD\r\n\
+            FN:Erika\r\n\
+            TEL;TYPE=HOME:123\r\n\
+            TEL:456\r\n\
+            X-ANNIVERSARY:20100101\r\n\
+            item1.X-ANNIVERSARY:20120101\r\n\
+            END:VCARD\r\n").unwrap();
+
+        c.get_only_mut("fn").unwrap().raw_value = String::from("Erika Mustermann");
+        assert_eq!(c.get_only("FN").unwrap().raw_value, "Erika Mustermann");
+        assert!(c.get_only_mut("TEL").is_none());
+
+        c.get_all_mut("TEL")[1].params.insert(String::from("TYPE"), String::from("CELL"));
+        assert_eq!(c.get_all("TEL")[1].params["TYPE"], "CELL");
+        assert!(c.get_all_mut("NOTE").is_empty());
+
+        c.update_prop("tel", |p| p.raw_value.insert(0, '+'));
+        let values: Vec<_> = c.get_all("TEL").iter().map(|p| &p.raw_value[..]).collect();
+        assert_eq!(values, vec!["+123", "+456"]);
+
+        c.rename_prop("X-ANNIVERSARY", "ANNIVERSARY");
+        c.rename_prop("X-MISSING", "NOTE");
+        assert!(c.get_all("X-ANNIVERSARY").is_empty());
+        let anniversaries = c.get_all("ANNIVERSARY");
+        assert_eq!(anniversaries.len(), 2);
+        assert_eq!(anniversaries[1].prop_group.as_deref(), Some("item1"));
+        for (key, props) in &c.props {
+            assert!(props.iter().all(|p| normalize_name(&p.name) == *key));
+        }
+        let out = write_component(&c);
+        assert!(out.contains("\r\nitem1.ANNIVERSARY:20120101\r\n"));
+        assert!(!out.contains("X-ANNIVERSARY"));
+    }
+
+    #[test]
+    fn test_parse_component_with_spans() {
+        use component::parse_component_with_spans;
+
+        let input = "BEGIN:VCALENDAR\r\n\
+                     VERSION:2.0\r\n\
+                     BEGIN:VEVENT\r\n\
+                     SUMMARY:Planning\r\n \
+                      meeting\r\n\
+                     item1.X-FOO;X-BAR=baz:a\r\n\
+                     \r\n\
+                     summary:second\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n";
+        let (c, spans) = parse_component_with_spans(input).unwrap();
+        assert_eq!(c, parse_component(input).unwrap());
+
+        assert_eq!(&input[spans.get("VERSION", 0).unwrap()], "VERSION:2.0");
+        let event = &spans.subcomponents[0];
+        assert_eq!(&input[event.get("SUMMARY", 0).unwrap()], "SUMMARY:Planning\r\n meeting");
+        assert_eq!(&input[event.get("summary", 1).unwrap()], "summary:second");
+        assert_eq!(&input[event.get("X-FOO", 0).unwrap()], "item1.X-FOO;X-BAR=baz:a");
+        assert_eq!(event.get("SUMMARY", 2), None);
+        assert_eq!(event.get("DTSTART", 0), None);
+
+        // Replacing a span with a re-folded property keeps the input parseable
+        let span = event.get("SUMMARY", 0).unwrap();
+        let replaced = format!("{}{}{}", &input[..span.start], fold_line("SUMMARY:Review"), &input[span.end..]);
+        let c = parse_component(&replaced).unwrap();
+        assert_eq!(c.subcomponents[0].get_all("SUMMARY")[0].raw_value, "Review");
+
+        assert!(parse_component_with_spans("BEGIN:VCARD\r\nEND:VCARD\r\nFOO").is_err());
+    }
+
+    #[test]
+    fn test_write_component_checked() {
+        use component::write_component_checked;
+        use error::VObjectError;
+
+        let valid = parse_component("BEGIN:VCARD\r\n\
+            item1.TEL;TYPE=\"HOME,VOICE\";X-LABEL=\"a:b\":+1 234\t5\r\n\
+            END:VCARD\r\n").unwrap();
+        assert_eq!(write_component_checked(&valid).unwrap(), write_component(&valid));
+
+        let check = |prop: Property| {
+            let mut c = Component::new("VCARD");
+            c.push(prop);
+            match write_component_checked(&c).unwrap_err() {
+                VObjectError::InvalidData { component, property, reason } => {
+                    assert_eq!(component, "VCARD");
+                    (property, reason)
+                },
+                e => panic!("unexpected error {}", e),
+            }
+        };
+        let prop = |name: &str, value: &str| Property {
+            name: name.into(),
+            params: Default::default(),
+            raw_value: value.into(),
+            prop_group: None,
+        };
+
+        let (property, reason) = check(prop("FULL NAME", "a"));
+        assert_eq!(property.as_deref(), Some("FULL NAME"));
+        assert_eq!(reason, "invalid property name \"FULL NAME\"");
+        assert!(check(prop("FN:", "a")).1.starts_with("invalid property name"));
+
+        let mut grouped = prop("URL", "a");
+        grouped.prop_group = Some("item.1".into());
+        assert_eq!(check(grouped).1, "invalid group \"item.1\"");
+
+        let mut param_key = prop("TEL", "1");
+        param_key.params.insert("TYPE;X".into(), "HOME".into());
+        assert!(check(param_key).1.starts_with("invalid parameter name"));
+
+        let mut param_value = prop("TEL", "1");
+        param_value.params.insert("X-LABEL".into(), "say \"hi\"".into());
+        assert!(check(param_value).1.starts_with("invalid value of parameter X-LABEL"));
+
+        assert!(check(prop("NOTE", "line\nbreak")).1.starts_with("control character"));
+        assert!(check(prop("NOTE", "nul\0")).1.starts_with("control character"));
+
+        let mut nested = Component::new("VCALENDAR");
+        nested.subcomponents.push(Component::new("V EVENT"));
+        let err = write_component_checked(&nested).unwrap_err();
+        assert_eq!(err.to_string(), "cannot write V EVENT: invalid component name \"V EVENT\"");
+        assert_eq!(check(prop("NOTE", "a\rb")).0.as_deref(), Some("NOTE"));
+    }
 
     #[test]
     fn test_fold() {
@@ -180,4 +1727,285 @@ mod tests {
         assert_eq!("ab", fold_line("ab"));
     }
 
+    #[test]
+    fn test_fold_counts_leading_space() {
+        let mut c = Component::new("VCARD");
+        c.push(Property::new("NOTE", "毎害止加食下組多地将写館来局必第。東証細再記得玲祉込吉宣会法授\
+                                      加食下組多地将写館来局必第。東証細再記得玲祉込吉宣会法授"));
+
+        let written = write_component(&c);
+        assert!(written.split("\r\n").all(|l| l.len() <= 75));
+        // Another char would make the continuation line 76 bytes long
+        assert_eq!(written.split("\r\n").nth(2).unwrap().len(), 73);
+        assert_eq!(parse_component(&written).unwrap(), c);
+    }
+
+    #[test]
+    fn test_fold_whole_line() {
+        let summary = "Planungstreffen für das Straßenfest, bitte Unterlagen mitbringen\\n\
+                       Ort: Gemeindesaal\\, 1. Stock; Zeit: nach Absprache – Kaffee und Kuchen \
+                       gibt es vor Ort. Rückfragen gerne per Mail. 毎害止加食下組多地将写館来局必第";
+
+        for offset in 0..75 {
+            let mut prop = Property::new("SUMMARY", "");
+            prop.raw_value = format!("{}{}", "x".repeat(offset), summary);
+            prop.params.insert("LANGUAGE".to_owned(), "de".to_owned());
+            prop.prop_group = Some("item1".to_owned());
+
+            let mut c = Component::new("VEVENT");
+            c.push(prop);
+
+            let written = write_component(&c);
+            for line in written.split("\r\n") {
+                assert!(line.len() <= 75, "line too long: {:?}", line);
+                assert!(!line.ends_with('\\') || line.ends_with("\\\\"), "split escape: {:?}", line);
+            }
+            assert_eq!(parse_component(&written).unwrap(), c);
+        }
+    }
+
+    #[test]
+    fn test_fold_caret_in_value() {
+        for offset in 60..70 {
+            let mut prop = Property::new("SUMMARY", "");
+            prop.raw_value = format!("{}^\\nxyz", "a".repeat(offset));
+
+            let mut c = Component::new("VEVENT");
+            c.push(prop);
+
+            let written = write_component(&c);
+            for line in written.split("\r\n") {
+                assert!(!line.ends_with('\\'), "split escape: {:?}", line);
+            }
+            assert_eq!(parse_component(&written).unwrap(), c);
+        }
+    }
+
+    #[test]
+    fn test_fold_long_parameters() {
+        for offset in 0..8 {
+            let mut prop = Property::new("ATTENDEE", "mailto:a@example.com");
+            prop.params.insert("CN".to_owned(), format!("{}Zoë Ångström-Łukasiewicz, Straßenfest-Komitee ^'Süd^' 毎害止", "x".repeat(offset)));
+            prop.params.insert("DELEGATED-FROM".to_owned(), "mailto:jürgen.müller.mustermann@example.com".to_owned());
+            prop.params.insert("SENT-BY".to_owned(), "mailto:assistant.of.erika.mustermann@example.com".to_owned());
+            prop.params.insert("ROLE".to_owned(), "REQ-PARTICIPANT".to_owned());
+
+            let line = property_line(&prop);
+            assert!(line.len() - prop.raw_value.len() >= 200);
+
+            let mut c = Component::new("VEVENT");
+            c.push(prop);
+            let written = write_component(&c);
+            for line in written.split("\r\n") {
+                assert!(line.len() <= 75, "line too long: {:?}", line);
+                assert!(!line.ends_with('^'), "split escape: {:?}", line);
+            }
+            assert!(written.lines().nth(1).unwrap().starts_with("ATTENDEE;CN="));
+            assert_eq!(parse_component(&written).unwrap(), c);
+        }
+    }
+
+    #[test]
+    fn test_find_property() {
+        let mut cal = parse_component("BEGIN:VCALENDAR\n\
+                                       BEGIN:VEVENT\n\
+                                       UID:first\n\
+                                       BEGIN:VALARM\n\
+                                       TRIGGER:-PT15M\n\
+                                       END:VALARM\n\
+                                       END:VEVENT\n\
+                                       BEGIN:VTODO\n\
+                                       BEGIN:VALARM\n\
+                                       TRIGGER:-PT1H\n\
+                                       END:VALARM\n\
+                                       END:VTODO\n\
+                                       BEGIN:VEVENT\n\
+                                       UID:second\n\
+                                       BEGIN:VALARM\n\
+                                       TRIGGER:-PT5M\n\
+                                       END:VALARM\n\
+                                       END:VEVENT\n\
+                                       END:VCALENDAR\n").unwrap();
+
+        let triggers = cal.find_property(&["VEVENT", "VALARM", "TRIGGER"])
+            .into_iter()
+            .map(|p| &p.raw_value[..])
+            .collect::<Vec<_>>();
+        assert_eq!(triggers, vec!["-PT15M", "-PT5M"]);
+
+        assert_eq!(cal.find_property(&["vevent", "UID"]).len(), 2);
+        assert_eq!(cal.find_property(&["*", "VALARM", "TRIGGER"]).len(), 0);
+        assert!(cal.find_property(&[]).is_empty());
+        assert_eq!(cal.subcomponents_named("VTODO").count(), 1);
+
+        for event in cal.subcomponents_named_mut("VEVENT") {
+            event.push(Property::new("SUMMARY", "Meeting"));
+        }
+        assert_eq!(cal.find_property(&["VEVENT", "SUMMARY"]).len(), 2);
+    }
+
+    #[test]
+    fn test_write_options() {
+        let mut c = Component::new("VCARD");
+        c.push(Property::new("NOTE", "äöüäöü"));
+
+        let write = |line_ending, fold_width, fold_mode| {
+            write_component_with(&c, &WriteOptions { line_ending, fold_width, fold_mode })
+        };
+
+        // Each umlaut is two bytes, "NOTE:ä" are seven
+        assert_eq!(write(LineEnding::Lf, Some(8), FoldMode::Bytes),
+                   "BEGIN:VCARD\nNOTE:ä\n öüä\n öü\nEND:VCARD\n");
+        assert_eq!(write(LineEnding::Lf, Some(8), FoldMode::Chars),
+                   "BEGIN:VCARD\nNOTE:äöü\n äöü\nEND:VCARD\n");
+        assert_eq!(write(LineEnding::Crlf, None, FoldMode::Bytes),
+                   "BEGIN:VCARD\r\nNOTE:äöüäöü\r\nEND:VCARD\r\n");
+
+        // Never split inside a char, even if the width is smaller than the char
+        for width in 2..20 {
+            let folded = write(LineEnding::Lf, Some(width), FoldMode::Bytes);
+            let mut lines = folded.lines().filter(|l| !l.contains("VCARD"));
+            assert!(lines.all(|l| l.len() <= width || l.chars().count() == 2));
+            assert_eq!(parse_component(&folded).unwrap(), c);
+        }
+    }
+
+    #[test]
+    fn test_dump_tree() {
+        let c = parse_component("BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY;LANGUAGE=de:Treffen\\, kurz\r\n\
+            DESCRIPTION:Erste Zeile\\nzweite Zeile mit sehr viel Text\r\n\
+            item1.X-FOO:bar\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n").unwrap();
+
+        assert_eq!(c.dump_tree(),
+                   "VCALENDAR\n\
+                   \x20 VERSION: 2.0\n\
+                   \x20 VEVENT\n\
+                   \x20   DESCRIPTION: Erste Zeile\\nzweite Zeile mit sehr viel Text\n\
+                   \x20   SUMMARY;LANGUAGE=de: Treffen, kurz\n\
+                   \x20   item1.X-FOO: bar\n");
+
+        let opts = super::DumpOptions { max_value_len: Some(13), show_params: false };
+        assert_eq!(c.subcomponents[0].dump_tree_with(&opts),
+                   "VEVENT\n\
+                   \x20 DESCRIPTION: Erste Zeile\\n…\n\
+                   \x20 SUMMARY: Treffen, kurz\n\
+                   \x20 item1.X-FOO: bar\n");
+    }
+
+    #[test]
+    fn test_write_order() {
+        let mut c = Component::new("VCALENDAR");
+        c.push(Property::new("X-WR-CALNAME", "Work"));
+        c.push(Property::new("CALSCALE", "GREGORIAN"));
+        c.push(Property::new("prodid", "-//Example//EN"));
+        c.push(Property::new("VERSION", "2.0"));
+        c.push(Property::new("ATTACH", "http://example.com/b"));
+        c.push(Property::new("ATTACH", "http://example.com/a"));
+        c.subcomponents.push(Component::new("VTODO"));
+        c.subcomponents.push(Component::new("VEVENT"));
+
+        let expected = "BEGIN:VCALENDAR\r\n\
+                        VERSION:2.0\r\n\
+                        PRODID:-//Example//EN\r\n\
+                        ATTACH:http://example.com/b\r\n\
+                        ATTACH:http://example.com/a\r\n\
+                        CALSCALE:GREGORIAN\r\n\
+                        X-WR-CALNAME:Work\r\n\
+                        BEGIN:VTODO\r\n\
+                        END:VTODO\r\n\
+                        BEGIN:VEVENT\r\n\
+                        END:VEVENT\r\n\
+                        END:VCALENDAR\r\n";
+        for _ in 0..100 {
+            assert_eq!(write_component(&c), expected);
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        use component::{parse_component, VOLATILE_PROPERTIES};
+
+        let local = parse_component(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            UID:a\n\
+            SUMMARY:Old\n\
+            DTSTAMP:20160101T000000Z\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:b\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+        let remote = parse_component(
+            "BEGIN:VCALENDAR\n\
+            X-WR-CALNAME:Work\n\
+            BEGIN:VEVENT\n\
+            UID:a\n\
+            SUMMARY;LANGUAGE=de:Neu\n\
+            DTSTAMP:20160202T000000Z\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+
+        assert!(local.diff(&local).is_empty());
+
+        let diff = local.diff_ignoring(&remote, VOLATILE_PROPERTIES);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.removed_components.len(), 1);
+        assert_eq!(diff.changed_components.len(), 1);
+        assert_eq!(diff.to_string(),
+                   "VCALENDAR: + X-WR-CALNAME:Work\n\
+                   VCALENDAR: - BEGIN:VEVENT\n\
+                   VCALENDAR > VEVENT[a]: ~ SUMMARY:Old -> SUMMARY;LANGUAGE=de:Neu\n");
+
+        let diff = local.diff(&remote);
+        assert_eq!(diff.changed_components[0].changed.len(), 2);
+
+        let diff = local.diff_ignoring(&remote, &["dtstamp", "x-wr-calname"]);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.changed_components[0].changed.len(), 1);
+    }
+
+    #[test]
+    fn test_equivalent() {
+        use component::{parse_component, EquivalenceOptions};
+
+        let a = parse_component(
+            "BEGIN:VCARD\n\
+            FN:Erika Mustermann\n\
+            TEL;TYPE=WORK:(0221) 9999123\n\
+            TEL;TYPE=HOME:(0221) 1234567\n\
+            REV:20140301T221110Z\n\
+            END:VCARD\n").unwrap();
+        let b = parse_component(
+            "BEGIN:VCARD\n\
+            TEL;type=home:(0221) 1234567\n\
+            REV:20160301T221110Z\n\
+            TEL;TYPE=WORK:(0221) 9999123\n\
+            FN:Erika Mustermann\n\
+            END:VCARD\n").unwrap();
+
+        assert_eq!(a, a.clone());
+        assert!(a != b);
+        assert!(a.equivalent(&a, &EquivalenceOptions::default()));
+        assert!(!a.equivalent(&b, &EquivalenceOptions::default()));
+
+        let options = EquivalenceOptions {
+            ignore_order: true,
+            ignore_param_case: true,
+            ignore_properties: vec!["REV".to_owned()],
+        };
+        assert!(a.equivalent(&b, &options));
+
+        let options = EquivalenceOptions { ignore_order: false, ..options };
+        assert!(!a.equivalent(&b, &options));
+
+        let options = EquivalenceOptions { ignore_order: true, ignore_properties: vec!["rev".to_owned()], ..options };
+        assert!(a.equivalent(&b, &options));
+    }
+
 }
@@ -0,0 +1,92 @@
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
+use component::{parse_component, Component};
+use error::*;
+use icalendar::ICalendar;
+use parser::{Parser, ParserOptions};
+use vcard::Vcard;
+
+/// The kind of object found by `detect`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ObjectKind {
+    Vcard,
+    ICalendar,
+    /// Any other component, with its uppercased name.
+    Other(String),
+}
+
+/// An object parsed by `parse_any`.
+#[derive(Debug)]
+pub enum AnyObject {
+    Vcard(Vcard),
+    ICalendar(ICalendar),
+    Other(Component),
+}
+
+/// Find out what kind of object `s` contains, reading only up to the first `BEGIN` line.
+///
+/// Leading blank lines and a byte order mark are skipped. Errors are only reported if the start
+/// of the input is broken, the rest is not looked at.
+///
+/// ```
+/// use vobject::{detect, ObjectKind};
+///
+/// assert_eq!(detect("\u{feff}BEGIN:VCARD\r\n...").unwrap(), ObjectKind::Vcard);
+/// assert_eq!(detect("BEGIN:VCALENDAR\r\n").unwrap(), ObjectKind::ICalendar);
+/// assert_eq!(detect("BEGIN:vtodo\r\n").unwrap(), ObjectKind::Other("VTODO".to_owned()));
+/// assert!(detect("FN:Erika\r\n").is_err());
+/// ```
+pub fn detect(s: &str) -> VObjectResult<ObjectKind> {
+    let name = Parser::with_options(s, ParserOptions::default()).consume_begin_name()?;
+    Ok(match &name[..] {
+        "VCARD" => ObjectKind::Vcard,
+        "VCALENDAR" => ObjectKind::ICalendar,
+        _ => ObjectKind::Other(name.into_owned()),
+    })
+}
+
+/// Parse exactly one component and wrap it according to its kind.
+///
+/// Unlike trying `Vcard::build` and then `ICalendar::build`, this parses the input only once, and
+/// syntax errors are not confused with finding the wrong kind of object.
+pub fn parse_any(s: &str) -> VObjectResult<AnyObject> {
+    let component = parse_component(s)?;
+    let rv = match Vcard::from_component(component) {
+        Ok(card) => AnyObject::Vcard(card),
+        Err(component) => match ICalendar::from_component(component) {
+            Ok(cal) => AnyObject::ICalendar(cal),
+            Err(component) => AnyObject::Other(component),
+        },
+    };
+    Ok(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("\r\n\r\nBEGIN:VCARD\r\nFN:Erika\r\n").unwrap(), ObjectKind::Vcard);
+        assert_eq!(detect("BEGIN:VCAL\r\n ENDAR\r\nbroken").unwrap(), ObjectKind::ICalendar);
+        assert!(detect("").is_err());
+        assert!(matches!(detect("FN:Erika\r\n").unwrap_err().parse_error(),
+                         Some(ParseErrorReason::ExpectedBegin)));
+    }
+
+    #[test]
+    fn test_parse_any() {
+        let card = "\u{feff}BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Erika\r\nEND:VCARD\r\n";
+        match parse_any(card).unwrap() {
+            AnyObject::Vcard(card) => assert_eq!(card.fullname()[0].raw(), "Erika"),
+            other => panic!("{:?}", other),
+        }
+
+        let cal = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:a\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert!(matches!(parse_any(cal).unwrap(), AnyObject::ICalendar(_)));
+        assert!(matches!(parse_any("BEGIN:VTODO\r\nEND:VTODO\r\n").unwrap(), AnyObject::Other(_)));
+
+        let err = parse_any("BEGIN:VCARD\r\nFN:Erika\r\n").unwrap_err();
+        assert!(err.parse_error().unwrap().is_truncated());
+    }
+}
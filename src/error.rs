@@ -1,6 +1,10 @@
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
 use thiserror::Error;
 
-use ::parser::ParseErrorReason;
+pub use ::parser::ParseErrorReason;
+use ::vcard::ValidationError;
+use ::icalendar::ValidationProblem;
 
 #[derive(Debug, Clone, Error)]
 pub enum VObjectError {
@@ -10,11 +14,51 @@ pub enum VObjectError {
         source: ParseErrorReason,
     },
 
-    #[error("Not a Vcard")]
-    NotAVCard,
+    /// `found` is the name of the component that was found instead, `prefix` the start of the
+    /// input.
+    #[error("Not a Vcard, found {}", found)]
+    NotAVCard { found: String, prefix: String },
+
+    #[error("invalid Vcard: {}", display_list(_0))]
+    InvalidVcard(Vec<ValidationError>),
+
+    /// Like `NotAVCard`.
+    #[error("Not a Icalendar, found {}", found)]
+    NotAnICalendar { found: String, prefix: String },
+
+    #[error("invalid Icalendar: {}", display_list(_0))]
+    InvalidICalendar(Vec<ValidationProblem>),
+
+    #[error("invalid GEO value: {}", _0)]
+    InvalidGeo(String),
+
+    #[error("invalid {} value: {}", _0, _1)]
+    InvalidValue(&'static str, String),
+
+    #[error("undecodable input: {}", _0)]
+    Encoding(String),
+
+    /// Returned by `write_component_checked` for data which would not survive writing.
+    /// `component` is the name of the innermost component, `property` the offending property's
+    /// name unless the component itself is at fault.
+    #[error("cannot write {}{}: {}", component, property.as_ref().map(|p| format!(", property {}", p)).unwrap_or_default(), reason)]
+    InvalidData { component: String, property: Option<String>, reason: String },
+
+    #[cfg(feature = "jcard")]
+    #[error("invalid jCard: {}", _0)]
+    InvalidJcard(String),
 
-    #[error("Not a Icalendar: {}", _0)]
-    NotAnICalendar(String),
+    #[cfg(feature = "timeconversions")]
+    #[error("unknown or unusable timezone: {}", _0)]
+    UnknownTimezone(String),
+
+    #[cfg(feature = "timeconversions")]
+    #[error("invalid recurrence rule: {}", _0)]
+    InvalidRrule(String),
+
+    #[cfg(feature = "timeconversions")]
+    #[error("invalid duration: {}", _0)]
+    InvalidDuration(String),
 
     #[cfg(feature = "timeconversions")]
     #[error("failed to parse time")]
@@ -24,4 +68,18 @@ pub enum VObjectError {
     },
 }
 
+impl VObjectError {
+    /// The reason if this is a parse error, to tell e.g. truncated files from other errors.
+    pub fn parse_error(&self) -> Option<&ParseErrorReason> {
+        match *self {
+            VObjectError::Parse { ref source } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn display_list<T: ToString>(items: &[T]) -> String {
+    items.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
 pub(crate) type VObjectResult<T> = Result<T, VObjectError>;
@@ -1,14 +1,31 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "std")] use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+#[cfg(feature = "std")] use std::io;
+use std::ops::Deref;
+use std::str::FromStr;
+#[cfg(not(feature = "std"))] use std::prelude::*;
 
 use component::Component;
+use param::Parameters;
 use component::parse_component;
+use component::write_component;
+#[cfg(feature = "std")] use component::{write_component_to, WriteOptions};
 use property::Property;
 use error::*;
+use uid::{normalize_uid, uids_equal};
+use util::{base64_decode, base64_encode, escape_unescaped, fold_text, input_prefix, join_text_list, parse_geo, split_text_list};
 
 #[cfg(feature = "timeconversions")] use chrono::NaiveDateTime;
 #[cfg(feature = "timeconversions")] use chrono::NaiveDate;
+#[cfg(feature = "timeconversions")] use chrono::Datelike;
+#[cfg(feature = "timeconversions")] use chrono::Weekday;
+
+#[cfg(feature = "timeconversions")] use rrule::{Frequency, RecurrenceRule};
 
 #[cfg(feature = "timeconversions")] use util::DATE_TIME_FMT;
+#[cfg(feature = "timeconversions")] use util::LOCAL_DATE_TIME_FMT;
 #[cfg(feature = "timeconversions")] use util::DATE_FMT;
 
 /// An ICalendar representing type
@@ -24,14 +41,49 @@ impl ICalendar {
     ///
     pub fn build(s: &str) -> VObjectResult<ICalendar> {
         let c = parse_component(s)?;
-        Self::from_component(c).map_err(|_| VObjectError::NotAnICalendar(s.to_owned()))
+        Self::from_component(c).map_err(|c| VObjectError::NotAnICalendar {
+            found: c.name,
+            prefix: input_prefix(s),
+        })
     }
 
+    /// Create a calendar without any properties
+    ///
+    /// Most servers reject calendars without `VERSION` and `PRODID`, see `ICalendar::new()`.
     pub fn empty() -> ICalendar {
         let c = Component::new("VCALENDAR");
         ICalendar(c)
     }
 
+    /// Create a calendar with `VERSION:2.0` and the given `PRODID`, such as `-//Example//EN`
+    pub fn new(prodid: &str) -> ICalendar {
+        ICalendar::empty()
+            .with_version(Version::from_raw(String::from("2.0")), None)
+            .with_prodid(Prodid::from_raw(String::from(prodid)), None)
+    }
+
+    /// Set `PRODID` to identify this crate as the producer, unless it is set already.
+    pub fn with_default_prodid(self) -> Self {
+        if self.0.get_all("PRODID").is_empty() {
+            let prodid = format!("-//rust-vobject//vobject {}//EN", env!("CARGO_PKG_VERSION"));
+            self.with_prodid(Prodid::from_raw(prodid), None)
+        } else {
+            self
+        }
+    }
+
+    /// Add an arbitrary property to the calendar itself, such as `X-WR-CALNAME`. Existing
+    /// properties of the same name are kept.
+    pub fn add_property(&mut self, property: Property) {
+        self.0.push(property);
+    }
+
+    /// Chainable variant of `ICalendar::add_property()`.
+    pub fn with_property(mut self, property: Property) -> Self {
+        self.0.push(property);
+        self
+    }
+
     /// Add an event to the calendar
     pub fn add_event(&mut self, builder: EventBuilder) {
         self.0.subcomponents.push(builder.into_component())
@@ -43,6 +95,121 @@ impl ICalendar {
         self
     }
 
+    /// Add an event to the calendar, after checking it with `EventBuilder::finish()`.
+    ///
+    /// The calendar is left untouched if the event is invalid.
+    pub fn add_event_checked(&mut self, builder: EventBuilder) -> VObjectResult<()> {
+        let event = builder.finish()?;
+        self.0.subcomponents.push(event);
+        Ok(())
+    }
+
+    /// Add a todo to the calendar
+    pub fn add_todo(&mut self, builder: TodoBuilder) {
+        self.0.subcomponents.push(builder.into_component())
+    }
+
+    /// Chainable variant of `ICalendar::add_todo()`.
+    pub fn with_todo(mut self, builder: TodoBuilder) -> Self {
+        self.0.subcomponents.push(builder.into_component());
+        self
+    }
+
+    /// Add a journal entry to the calendar
+    pub fn add_journal(&mut self, builder: JournalBuilder) {
+        self.0.subcomponents.push(builder.into_component())
+    }
+
+    /// Chainable variant of `ICalendar::add_journal()`.
+    pub fn with_journal(mut self, builder: JournalBuilder) -> Self {
+        self.0.subcomponents.push(builder.into_component());
+        self
+    }
+
+    /// Add free/busy information to the calendar
+    pub fn add_freebusy(&mut self, builder: FreeBusyBuilder) {
+        self.0.subcomponents.push(builder.into_component())
+    }
+
+    /// Chainable variant of `ICalendar::add_freebusy()`.
+    pub fn with_freebusy(mut self, builder: FreeBusyBuilder) -> Self {
+        self.0.subcomponents.push(builder.into_component());
+        self
+    }
+
+    /// Add an arbitrary subcomponent to the calendar, e.g. a `VTODO` or `VJOURNAL`.
+    pub fn add_component(&mut self, c: Component) {
+        self.0.subcomponents.push(c)
+    }
+
+    /// Chainable variant of `ICalendar::add_component()`.
+    pub fn with_component(mut self, c: Component) -> Self {
+        self.0.subcomponents.push(c);
+        self
+    }
+
+    /// Remove the first event with the given UID and return it.
+    pub fn remove_event(&mut self, uid: &str) -> Option<Component> {
+        let pos = self.0.subcomponents.iter().position(|c| {
+            c.name == "VEVENT" && c.get_only("UID").is_some_and(|p| uids_equal(&p.raw_value, uid))
+        });
+
+        pos.map(|i| self.0.subcomponents.remove(i))
+    }
+
+    /// Find the event with the given UID, compared with `uid::uids_equal()`.
+    ///
+    /// If there are several, as with overrides of a recurring event, the one without
+    /// `RECURRENCE-ID` is returned, or else the first one.
+    pub fn get_event_by_uid<'a>(&'a self, uid: &str) -> Option<Event<'a>> {
+        let mut events = self.events()
+            .filter_map(Result::ok)
+            .filter(|ev| ev.uid().is_some_and(|u| uids_equal(u.raw(), uid)))
+            .peekable();
+        let first = events.peek().map(|ev| Event(ev.0));
+        events.find(|ev| ev.0.get_only("RECURRENCE-ID").is_none()).or(first)
+    }
+
+    /// Group the event with the given UID with its overridden instances.
+    ///
+    /// Returns `None` if no event has this UID.
+    pub fn recurrence_set<'a>(&'a self, uid: &str) -> Option<RecurrenceSet<'a>> {
+        let mut set = RecurrenceSet { master: None, overrides: vec![] };
+        let events = self.events()
+            .filter_map(Result::ok)
+            .filter(|ev| ev.uid().is_some_and(|u| uids_equal(u.raw(), uid)));
+
+        for event in events {
+            if event.0.get_only("RECURRENCE-ID").is_some() {
+                set.overrides.push(event);
+            } else if set.master.is_none() {
+                set.master = Some(event);
+            }
+        }
+
+        if set.master.is_none() && set.overrides.is_empty() {
+            None
+        } else {
+            Some(set)
+        }
+    }
+
+    /// Map the UID of each subcomponent to its positions in `subcomponents`.
+    ///
+    /// An event with overrides appears once per override, so every UID maps to a list. The keys
+    /// are normalized by `uid::normalize_uid()`, so look them up with a normalized UID as well.
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn build_uid_index(&self) -> HashMap<String, Vec<usize>> {
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, c) in self.0.subcomponents.iter().enumerate() {
+            if let Some(uid) = c.get_only("UID") {
+                index.entry(normalize_uid(&uid.raw_value)).or_default().push(i);
+            }
+        }
+        index
+    }
+
     /// Wrap a Component into an ICalendar object, or don't do it if the Component is not an
     /// ICalendar.
     pub fn from_component(c: Component)-> Result<ICalendar, Component> {
@@ -83,487 +250,4099 @@ impl ICalendar {
         EventIterator::new(self.0.subcomponents.iter())
     }
 
-    make_getter_function_for_optional!(version, "VERSION", Version);
-    make_getter_function_for_optional!(prodid, "PRODID", Prodid);
-}
+    /// Get an iterator over the todos in this calendar
+    ///
+    /// Works like `ICalendar::events()`, but for `VTODO` components.
+    pub fn todos<'a>(&'a self) -> TodoIterator<'a> {
+        TodoIterator(self.0.subcomponents.iter())
+    }
 
-create_data_type!(Version);
-create_data_type!(Prodid);
+    /// Get an iterator over the todos in this calendar, allowing modification
+    ///
+    /// Works like `ICalendar::events_mut()`, but for `VTODO` components.
+    pub fn todos_mut<'a>(&'a mut self) -> TodoIterMut<'a> {
+        TodoIterMut(self.0.subcomponents.iter_mut())
+    }
 
-pub struct EventIterator<'a>(::std::slice::Iter<'a, Component>);
+    /// Get an iterator over the journal entries in this calendar
+    ///
+    /// Works like `ICalendar::events()`, but for `VJOURNAL` components.
+    pub fn journals<'a>(&'a self) -> JournalIterator<'a> {
+        JournalIterator(self.0.subcomponents.iter())
+    }
 
-impl<'a> EventIterator<'a> {
-    fn new(i: ::std::slice::Iter<'a, Component>) -> EventIterator<'a> {
-        EventIterator(i)
+    /// Get an iterator over the free/busy information in this calendar
+    ///
+    /// Works like `ICalendar::events()`, but for `VFREEBUSY` components.
+    pub fn freebusys<'a>(&'a self) -> FreeBusyIterator<'a> {
+        FreeBusyIterator(self.0.subcomponents.iter())
     }
-}
 
-impl<'a> Iterator for EventIterator<'a> {
-    type Item = Result<Event<'a>, &'a Component>;
+    /// The busy times of all `VFREEBUSY` components, sorted by start.
+    ///
+    /// Only `FREEBUSY` properties without `FBTYPE` or with `FBTYPE=BUSY` count. Periods which
+    /// overlap or touch are merged into one; those which cannot be parsed are skipped.
+    #[cfg(feature = "timeconversions")]
+    pub fn busy_periods(&self) -> Vec<Period> {
+        let mut times = self.freebusys()
+            .filter_map(Result::ok)
+            .flat_map(|fb| fb.freebusy())
+            .filter(|fb| fb.params().get("FBTYPE").is_none_or(|t| t.eq_ignore_ascii_case("BUSY")))
+            .flat_map(|fb| fb.as_periods())
+            .filter_map(|p| Some((p.start().ok()?, p.end().ok()?)))
+            .collect::<Vec<_>>();
+        times.sort();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(Event::from_component)
+        let mut merged: Vec<(NaiveDateTime, NaiveDateTime)> = vec![];
+        for (start, end) in times {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged.into_iter().map(|(start, end)| Period::new(start, end)).collect()
     }
 
-}
+    /// Get an iterator over the timezone definitions in this calendar
+    ///
+    /// Works like `ICalendar::events()`, but for `VTIMEZONE` components.
+    pub fn timezones<'a>(&'a self) -> TimezoneIterator<'a> {
+        TimezoneIterator(self.0.subcomponents.iter())
+    }
 
-#[derive(Debug, Clone)]
-pub struct Event<'a>(&'a Component);
+    /// Iterate over the subcomponents which none of the typed iterators cover, such as
+    /// `X-` components or a `VCARD` embedded by some servers.
+    pub fn other_components(&self) -> impl Iterator<Item = &Component> {
+        self.0.subcomponents
+            .iter()
+            .filter(|c| !KNOWN_COMPONENTS.contains(&&c.name[..]))
+    }
 
-impl<'a> Event<'a> {
-    fn from_component(c: &'a Component) -> Result<Event<'a>, &'a Component> {
-        if c.name == "VEVENT" {
-            Ok(Event(c))
-        } else {
-            Err(c)
-        }
+    /// Same as `Component::fingerprint()`, e.g. to detect changed calendars in a cache.
+    pub fn fingerprint(&self) -> u64 {
+        self.0.fingerprint()
     }
 
-    make_getter_function_for_optional!(dtend       , "DTEND"       , Dtend);
-    make_getter_function_for_optional!(dtstart     , "DTSTART"     , Dtstart);
-    make_getter_function_for_optional!(dtstamp     , "DTSTAMP"     , Dtstamp);
-    make_getter_function_for_optional!(uid         , "UID"         , Uid);
-    make_getter_function_for_optional!(description , "DESCRIPTION" , Description);
-    make_getter_function_for_optional!(summary     , "SUMMARY"     , Summary);
-    make_getter_function_for_optional!(url         , "URL"         , Url);
-    make_getter_function_for_optional!(location    , "LOCATION"    , Location);
-    make_getter_function_for_optional!(class       , "CLASS"       , Class);
-    make_getter_function_for_optional!(categories  , "CATEGORIES"  , Categories);
-    make_getter_function_for_optional!(transp      , "TRANSP"      , Transp);
-    make_getter_function_for_optional!(rrule       , "RRULE"       , Rrule);
+    /// Write the calendar to `w` with the default options, without building a String first.
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        write_component_to(&self.0, w, &WriteOptions::default())
+    }
 
-    pub fn build() -> EventBuilder {
-        EventBuilder(Component::new(String::from("VEVENT")))
+    /// Find the timezone definition with the given `TZID`.
+    pub fn get_timezone<'a>(&'a self, tzid: &str) -> Option<Timezone<'a>> {
+        self.timezones()
+            .filter_map(Result::ok)
+            .find(|tz| tz.tzid().is_some_and(|t| t.raw() == tzid))
     }
 
-}
+    /// The events overlapping the range from `start` (inclusive) to `end` (exclusive), sorted
+    /// like `events_sorted()`.
+    ///
+    /// Times with a `TZID` are converted to UTC using the calendar's timezones, so the range
+    /// should be given in UTC as well. Floating times, and times in unknown timezones, are taken
+    /// as they are. All-day events last from midnight to midnight. An event without `DTEND` or
+    /// `DURATION` lasts one day if it starts on a date, and is an instant otherwise. Events
+    /// without a usable `DTSTART` are left out.
+    ///
+    /// Recurring events are only included if their first instance overlaps the range.
+    // TODO: Expand RRULEs so that later instances are found as well.
+    #[cfg(feature = "timeconversions")]
+    pub fn events_in_range(&self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Event<'_>> {
+        self.events_sorted()
+            .into_iter()
+            .filter(|ev| match self.event_span(ev) {
+                Some((ev_start, ev_end)) if ev_start == ev_end => start <= ev_start && ev_start < end,
+                Some((ev_start, ev_end)) => ev_start < end && start < ev_end,
+                None => false,
+            })
+            .collect()
+    }
 
-create_data_type!(Dtend);
-create_data_type!(Dtstart);
-create_data_type!(Dtstamp);
-create_data_type!(Uid);
-create_data_type!(Description);
-create_data_type!(Summary);
-create_data_type!(Url);
-create_data_type!(Location);
-create_data_type!(Class);
-create_data_type!(Categories);
-create_data_type!(Transp);
-create_data_type!(Rrule);
+    /// Events whose `SUMMARY`, `DESCRIPTION` or `LOCATION` contains `query`, ignoring case.
+    ///
+    /// The unescaped values are searched, so `Q,A` finds `SUMMARY:Q\,A session`. `opts` selects
+    /// the properties and whether diacritics are ignored.
+    pub fn search_events(&self, query: &str, opts: SearchOptions) -> Vec<Event<'_>> {
+        let query = fold_text(query, opts.fold_diacritics);
+        let fields = [
+            ("SUMMARY", opts.summary),
+            ("DESCRIPTION", opts.description),
+            ("LOCATION", opts.location),
+        ];
 
-#[cfg(feature = "timeconversions")]
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
-pub enum Time {
-    Date(NaiveDate),
-    DateTime(NaiveDateTime),
-}
+        self.events()
+            .filter_map(Result::ok)
+            .filter(|ev| {
+                fields.iter()
+                    .filter(|&&(_, enabled)| enabled)
+                    .flat_map(|&(name, _)| ev.0.get_all(name))
+                    .any(|p| fold_text(&p.value_as_string(), opts.fold_diacritics).contains(&query[..]))
+            })
+            .collect()
+    }
 
-#[cfg(feature = "timeconversions")]
-pub trait AsDateTime {
-    fn as_datetime(&self) -> VObjectResult<Time>;
-}
+    /// All events, ordered by their start in UTC as described for `events_in_range()`, and by
+    /// `UID` if they start at the same time. Events without a usable `DTSTART` come last.
+    #[cfg(feature = "timeconversions")]
+    pub fn events_sorted(&self) -> Vec<Event<'_>> {
+        let mut events = self.events()
+            .filter_map(Result::ok)
+            .map(|ev| (self.event_span(&ev).map(|(start, _)| start), ev))
+            .collect::<Vec<_>>();
+        events.sort_by(|(a_start, a), (b_start, b)| {
+            let uid = |ev: &Event| ev.uid().map(Uid::into_raw);
+            a_start.is_none().cmp(&b_start.is_none())
+                .then(a_start.cmp(b_start))
+                .then_with(|| uid(a).cmp(&uid(b)))
+        });
+        events.into_iter().map(|(_, ev)| ev).collect()
+    }
 
-#[cfg(feature = "timeconversions")]
-impl AsDateTime for Dtend {
+    /// The start and end of an event as naive times, see `events_in_range()`.
+    #[cfg(feature = "timeconversions")]
+    fn event_span(&self, event: &Event) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let naive = |time: Time| match time {
+            Time::Date(d) => d.and_hms_opt(0, 0, 0),
+            Time::Utc(dt) | Time::Floating(dt) | Time::Local(dt, _) => Some(dt),
+        };
+        let resolve = |raw: &str, params: &Parameters| {
+            as_datetime_with_tz(raw, params, self)
+                .or_else(|_| parse_time(raw, params))
+                .ok()
+        };
 
-    fn as_datetime(&self) -> VObjectResult<Time> {
-        Ok(match NaiveDateTime::parse_from_str(&self.0, DATE_TIME_FMT) {
-            Ok(dt) => Time::DateTime(dt),
-            Err(_) => NaiveDate::parse_from_str(&self.0, DATE_FMT)
-                .map(Time::Date)?,
-        })
+        let dtstart = event.dtstart()?;
+        let start_time = resolve(dtstart.raw(), dtstart.params())?;
+        let is_date = matches!(start_time, Time::Date(_));
+        let start = naive(start_time)?;
+
+        let end = match (event.dtend(), event.duration()) {
+            (Some(dtend), _) => naive(resolve(dtend.raw(), dtend.params())?)?,
+            (None, Some(duration)) => start.checked_add_signed(duration.as_duration().ok()?)?,
+            (None, None) if is_date => start.checked_add_signed(chrono::Duration::days(1))?,
+            (None, None) => start,
+        };
+        Some((start, end.max(start)))
     }
 
-}
+    /// Get an iterator over the events in this calendar, allowing modification
+    ///
+    /// Works like `ICalendar::events()`, but creates `Ok(EventMut)` instances which offer the
+    /// same setters as `EventBuilder`.
+    pub fn events_mut<'a>(&'a mut self) -> EventIterMut<'a> {
+        EventIterMut(self.0.subcomponents.iter_mut())
+    }
 
-#[cfg(feature = "timeconversions")]
-impl AsDateTime for Dtstart {
+    make_getter_function_for_optional!(version  , "VERSION"  , Version);
+    make_getter_function_for_optional!(prodid   , "PRODID"   , Prodid);
+    make_getter_function_for_optional!(method   , "METHOD"   , Method);
+    make_getter_function_for_optional!(calscale , "CALSCALE" , Calscale);
 
-    fn as_datetime(&self) -> VObjectResult<Time> {
-        Ok(match NaiveDateTime::parse_from_str(&self.0, DATE_TIME_FMT) {
-            Ok(dt) => Time::DateTime(dt),
-            Err(_) => NaiveDate::parse_from_str(&self.0, DATE_FMT)
-                .map(Time::Date)?,
-        })
+    /// Check the calendar and all its subcomponents for violations of RFC 5545.
+    ///
+    /// This checks for required properties (`PRODID` and `VERSION` on the calendar, `UID` and
+    /// `DTSTAMP` on events, `ACTION` and `TRIGGER` on alarms), for properties which may occur
+    /// only once, for `DTEND` and `DURATION` both being present and for alarms placed outside
+    /// of an event or todo. Mismatched `END` tags are already rejected by the parser.
+    ///
+    /// Returns an empty list if no problems were found.
+    pub fn validate(&self) -> Vec<ValidationProblem> {
+        let mut problems = vec![];
+        validate_component(&self.0, None, String::from("VCALENDAR"), &mut problems);
+        problems
     }
 
-}
+    /// Move the components of `other` into this calendar.
+    ///
+    /// Components are matched by `UID`, so that an event and its overrides are kept or dropped
+    /// together; `policy` decides which side wins if both calendars have a `UID`. UIDs are
+    /// compared with `uid::uids_equal()`, ignoring case and a `urn:uuid:` prefix. Time zones
+    /// are added only if no time zone with the same `TZID` exists yet. The calendar properties of
+    /// `other`, such as `PRODID` and `X-WR-CALNAME`, are dropped.
+    pub fn merge(&mut self, other: ICalendar, policy: MergePolicy) {
+        let own = uid_stamps(&self.0.subcomponents);
+        let incoming = uid_stamps(&other.0.subcomponents);
 
-#[cfg(feature = "timeconversions")]
-impl AsDateTime for Dtstamp {
+        let mut replaced = BTreeSet::new();
+        let mut skipped = BTreeSet::new();
+        let mut renamed = BTreeMap::new();
+        for (key, &(uid, ref other_stamp)) in &incoming {
+            let own_stamp = match own.get(key) {
+                Some((_, stamp)) => stamp,
+                None => continue,
+            };
+            match policy {
+                MergePolicy::KeepSelf => { skipped.insert(key.clone()); },
+                MergePolicy::KeepOther => { replaced.insert(key.clone()); },
+                MergePolicy::KeepNewer if other_stamp > own_stamp => { replaced.insert(key.clone()); },
+                MergePolicy::KeepNewer => { skipped.insert(key.clone()); },
+                MergePolicy::KeepBoth => {
+                    let new_uid = (1..)
+                        .map(|n| format!("{}-{}", uid, n))
+                        .find(|u| {
+                            let key = normalize_uid(u);
+                            !own.contains_key(&key) && !incoming.contains_key(&key)
+                        })
+                        .unwrap();
+                    renamed.insert(key.clone(), new_uid);
+                },
+            }
+        }
 
-    fn as_datetime(&self) -> VObjectResult<Time> {
-        Ok(match NaiveDateTime::parse_from_str(&self.0, DATE_TIME_FMT) {
-            Ok(dt) => Time::DateTime(dt),
-            Err(_) => NaiveDate::parse_from_str(&self.0, DATE_FMT)
-                .map(Time::Date)?,
-        })
-    }
+        let mut tzids: BTreeSet<String> = self.0.subcomponents.iter()
+            .filter(|c| c.name == "VTIMEZONE")
+            .filter_map(|c| c.get_only("TZID"))
+            .map(|p| p.raw_value.clone())
+            .collect();
+
+        if !replaced.is_empty() {
+            self.0.subcomponents.retain(|c| !c.get_only("UID").is_some_and(|p| replaced.contains(&normalize_uid(&p.raw_value))));
+        }
 
+        for mut c in other.0.subcomponents {
+            if c.name == "VTIMEZONE" {
+                if let Some(tzid) = c.get_only("TZID") {
+                    if !tzids.insert(tzid.raw_value.clone()) {
+                        continue;
+                    }
+                }
+            } else if let Some(uid) = c.get_only("UID").map(|p| normalize_uid(&p.raw_value)) {
+                if skipped.contains(&uid) {
+                    continue;
+                }
+                if let Some(new_uid) = renamed.get(&uid) {
+                    c.set(Property::new("UID", new_uid));
+                }
+            }
+            self.0.subcomponents.push(c);
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct EventBuilder(Component);
+/// What `ICalendar::search_events()` looks at.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SearchOptions {
+    /// Search `SUMMARY`. On by default.
+    pub summary: bool,
 
-macro_rules! make_setter_function_for {
-    ($fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
-        pub fn $fnname(&mut self, value: $type, params: Option<BTreeMap<String, String>>) {
-            let property = Property {
-                name:       String::from($name),
-                params:     params.unwrap_or_else(|| BTreeMap::new()),
-                raw_value:  $tostring(value),
-                prop_group: None,
-            };
+    /// Search `DESCRIPTION`. On by default.
+    pub description: bool,
 
-            self.0.set(property);
+    /// Search `LOCATION`. On by default.
+    pub location: bool,
+
+    /// Ignore accents on Latin letters, so that `cafe` finds `Café`. Off by default.
+    pub fold_diacritics: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            summary: true,
+            description: true,
+            location: true,
+            fold_diacritics: false,
         }
-    };
+    }
 }
 
-macro_rules! make_function_for {
-    ($fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
-        pub fn $fnname(mut self, value: $type, params: Option<BTreeMap<String, String>>) -> Self {
-            let property = Property {
-                name:       String::from($name),
-                params:     params.unwrap_or_else(|| BTreeMap::new()),
-                raw_value:  $tostring(value),
-                prop_group: None,
-            };
+/// How `ICalendar::merge()` handles a `UID` found in both calendars.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MergePolicy {
+    /// Keep the components of the calendar merged into.
+    KeepSelf,
 
-            self.0.push(property);
-            self
+    /// Replace them with the incoming components.
+    KeepOther,
+
+    /// Keep the side which was modified last, by `LAST-MODIFIED`, or else `DTSTAMP`. On a tie,
+    /// or if both are missing, the calendar merged into wins.
+    KeepNewer,
+
+    /// Keep both, appending `-1`, `-2`, ... to the incoming `UID` until it is unique.
+    KeepBoth,
+}
+
+/// Map each normalized `UID` to its first spelling and the latest `LAST-MODIFIED`, or else
+/// `DTSTAMP`, of the components using it.
+///
+/// Both are UTC timestamps. Once the separators of the extended form (`2024-01-05T10:00:00Z`)
+/// are removed, comparing them as strings orders them by time.
+fn uid_stamps(components: &[Component]) -> BTreeMap<String, (&str, Option<String>)> {
+    let mut stamps: BTreeMap<String, (&str, Option<String>)> = BTreeMap::new();
+    for c in components.iter().filter(|c| c.name != "VTIMEZONE") {
+        let uid = match c.get_only("UID") {
+            Some(uid) => &uid.raw_value[..],
+            None => continue,
+        };
+        let stamp = c.get_only("LAST-MODIFIED")
+            .or_else(|| c.get_only("DTSTAMP"))
+            .map(|p| p.raw_value.trim().replace(['-', ':'], ""));
+        let entry = stamps.entry(normalize_uid(uid)).or_insert((uid, None));
+        if stamp > entry.1 {
+            entry.1 = stamp;
         }
-    };
+    }
+    stamps
 }
 
-impl EventBuilder {
+fn validate_component(c: &Component,
+                      parent: Option<&str>,
+                      path: String,
+                      problems: &mut Vec<ValidationProblem>) {
+    let (required, singletons): (&[&str], &[&str]) = match &c.name[..] {
+        "VCALENDAR" => (&["PRODID", "VERSION"], &["CALSCALE", "METHOD", "PRODID", "VERSION"]),
+        "VEVENT"    => (&["DTSTAMP", "UID"], &[
+            "CLASS", "CREATED", "DESCRIPTION", "DTEND", "DTSTAMP", "DTSTART", "DURATION", "GEO",
+            "LAST-MODIFIED", "LOCATION", "ORGANIZER", "PRIORITY", "RECURRENCE-ID", "SEQUENCE",
+            "STATUS", "SUMMARY", "TRANSP", "UID", "URL",
+        ]),
+        "VALARM"    => (&["ACTION", "TRIGGER"], &["ACTION", "DURATION", "REPEAT", "TRIGGER"]),
+        _           => (&[], &[]),
+    };
 
-    /// Private function for adding event to calendar
-    fn into_component(self) -> Component {
-        self.0
+    for name in required {
+        if c.get_all(name).is_empty() {
+            problems.push(ValidationProblem::new(&path, Some(name), ProblemKind::MissingProperty));
+        }
     }
 
-    /// Setter for "DTEND" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_dtend, "DTEND", Dtend, Dtend::into_raw);
+    for name in singletons {
+        if c.get_all(name).len() > 1 {
+            problems.push(ValidationProblem::new(&path, Some(name), ProblemKind::DuplicateProperty));
+        }
+    }
 
-    /// Setter for "DTSTART" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+    if c.name == "VEVENT" && !c.get_all("DTEND").is_empty() && !c.get_all("DURATION").is_empty() {
+        problems.push(ValidationProblem::new(&path, Some("DURATION"), ProblemKind::ConflictingProperties));
+    }
 
-    /// Setter for "DTSTAMP" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
+    if c.name == "VALARM" && parent != Some("VEVENT") && parent != Some("VTODO") {
+        problems.push(ValidationProblem::new(&path, None, ProblemKind::UnexpectedComponent));
+    }
 
-    /// Setter for "UID" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_uid, "UID", Uid, Uid::into_raw);
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for sub in &c.subcomponents {
+        let index = counts.entry(&sub.name).or_insert(0);
+        let subpath = format!("{} > {}[{}]", path, sub.name, index);
+        *index += 1;
+        validate_component(sub, Some(&c.name), subpath, problems);
+    }
+}
 
-    /// Setter for "DESCRIPTION" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_description, "DESCRIPTION", Description, Description::into_raw);
+/// The kind of a problem found by `ICalendar::validate()`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ProblemKind {
+    /// A required property is missing.
+    MissingProperty,
 
-    /// Setter for "SUMMARY" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_summary, "SUMMARY", Summary, Summary::into_raw);
+    /// A property which may occur at most once was found multiple times.
+    DuplicateProperty,
 
-    /// Setter for "URL" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_url, "URL", Url, Url::into_raw);
+    /// The property must not be used together with another one, e.g. `DURATION` and `DTEND`.
+    ConflictingProperties,
 
-    /// Setter for "LOCATION" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_location, "LOCATION", Location, Location::into_raw);
+    /// The component is not allowed at this place, e.g. a `VALARM` directly inside the calendar.
+    UnexpectedComponent,
+}
 
-    /// Setter for "CLASS" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_class, "CLASS", Class, Class::into_raw);
+/// How severe a `ProblemKind` is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    /// The data is usable, but other implementations might reject it.
+    Warning,
 
-    /// Setter for "CATEGORIES" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_categories, "CATEGORIES", Categories, Categories::into_raw);
+    /// The data violates RFC 5545.
+    Error,
+}
 
-    /// Setter for "TRANSP" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_transp, "TRANSP", Transp, Transp::into_raw);
+impl ProblemKind {
+    pub fn severity(&self) -> Severity {
+        match *self {
+            ProblemKind::UnexpectedComponent => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
 
-    /// Setter for "RRULE" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_rrule, "RRULE", Rrule, Rrule::into_raw);
+/// A problem found by `ICalendar::validate()`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ValidationProblem {
+    /// The path to the offending component, e.g. `VCALENDAR > VEVENT[2]`. Indices count
+    /// same-named siblings and start at zero.
+    pub path: String,
 
-    //
-    // chainable builders
-    //
+    /// The offending property, if the problem is about a single property.
+    pub property: Option<String>,
 
-    /// Chainable setter for "DTEND" property.
+    /// What is wrong.
+    pub kind: ProblemKind,
+}
+
+impl ValidationProblem {
+    fn new(path: &str, property: Option<&str>, kind: ProblemKind) -> ValidationProblem {
+        ValidationProblem {
+            path: String::from(path),
+            property: property.map(String::from),
+            kind,
+        }
+    }
+
+    /// Shortcut for `self.kind.severity()`.
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for ValidationProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let what = match self.kind {
+            ProblemKind::MissingProperty       => "missing required property",
+            ProblemKind::DuplicateProperty     => "duplicate property",
+            ProblemKind::ConflictingProperties => "conflicting property",
+            ProblemKind::UnexpectedComponent   => "unexpected component",
+        };
+
+        match self.property {
+            Some(ref name) => write!(f, "{}: {} {}", self.path, what, name),
+            None => write!(f, "{}: {}", self.path, what),
+        }
+    }
+}
+
+impl Deref for ICalendar {
+    type Target = Component;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromStr for ICalendar {
+    type Err = VObjectError;
+
+    /// Same as `ICalendar::build`
+    fn from_str(s: &str) -> VObjectResult<ICalendar> {
+        ICalendar::build(s)
+    }
+}
+
+impl fmt::Display for ICalendar {
+    /// Same as `vobject::write_component` on the inner component.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&write_component(&self.0))
+    }
+}
+
+impl TryFrom<Component> for ICalendar {
+    type Error = Component;
+
+    /// Same as `ICalendar::from_component`
+    fn try_from(c: Component) -> Result<ICalendar, Component> {
+        ICalendar::from_component(c)
+    }
+}
+
+impl From<ICalendar> for Component {
+    fn from(item: ICalendar) -> Component {
+        item.0
+    }
+}
+
+/// A calendar with an index from UIDs to subcomponents, for repeated lookups. UIDs are
+/// compared with `uid::uids_equal()`.
+///
+/// The index is kept up to date by the methods of this type, which is why the calendar can only
+/// be modified through them.
+#[derive(Debug)]
+pub struct IndexedCalendar {
+    calendar: ICalendar,
+    index: BTreeMap<String, Vec<usize>>,
+}
+
+impl IndexedCalendar {
+    pub fn new(calendar: ICalendar) -> IndexedCalendar {
+        let mut rv = IndexedCalendar { calendar, index: BTreeMap::new() };
+        rv.reindex();
+        rv
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, c) in self.calendar.0.subcomponents.iter().enumerate() {
+            if let Some(uid) = c.get_only("UID") {
+                self.index.entry(normalize_uid(&uid.raw_value)).or_default().push(i);
+            }
+        }
+    }
+
+    /// All events with the given UID, i.e. a recurring event and its overrides.
+    pub fn events_by_uid<'a>(&'a self, uid: &str) -> Vec<Event<'a>> {
+        self.index
+            .get(&normalize_uid(uid))
+            .map(|positions| {
+                positions.iter()
+                    .filter_map(|&i| Event::from_component(&self.calendar.0.subcomponents[i]).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Add an event to the calendar and the index.
+    pub fn add_event(&mut self, builder: EventBuilder) {
+        let event = builder.into_component();
+        if let Some(uid) = event.get_only("UID") {
+            let position = self.calendar.0.subcomponents.len();
+            self.index.entry(normalize_uid(&uid.raw_value)).or_default().push(position);
+        }
+        self.calendar.0.subcomponents.push(event);
+    }
+
+    /// Remove all events with the given UID, including overrides, and return them.
+    pub fn remove_event(&mut self, uid: &str) -> Vec<Component> {
+        let positions = match self.index.get(&normalize_uid(uid)) {
+            Some(positions) => positions.clone(),
+            None => return vec![],
+        };
+
+        let mut removed = vec![];
+        for &i in positions.iter().rev() {
+            if self.calendar.0.subcomponents[i].name == "VEVENT" {
+                removed.push(self.calendar.0.subcomponents.remove(i));
+            }
+        }
+        removed.reverse();
+
+        if !removed.is_empty() {
+            self.reindex();
+        }
+        removed
+    }
+
+    pub fn into_inner(self) -> ICalendar {
+        self.calendar
+    }
+}
+
+impl Deref for IndexedCalendar {
+    type Target = ICalendar;
+
+    fn deref(&self) -> &Self::Target {
+        &self.calendar
+    }
+}
+
+/// The subcomponents of a calendar with a typed wrapper.
+const KNOWN_COMPONENTS: &[&str] = &["VEVENT", "VTODO", "VJOURNAL", "VFREEBUSY", "VTIMEZONE"];
+
+create_data_type!(Version, "VERSION");
+create_data_type!(Prodid, "PRODID");
+create_data_type!(Method, "METHOD");
+create_data_type!(Calscale, "CALSCALE");
+
+pub struct EventIterator<'a>(::std::slice::Iter<'a, Component>);
+
+impl<'a> EventIterator<'a> {
+    fn new(i: ::std::slice::Iter<'a, Component>) -> EventIterator<'a> {
+        EventIterator(i)
+    }
+}
+
+impl<'a> Iterator for EventIterator<'a> {
+    type Item = Result<Event<'a>, &'a Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Event::from_component)
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct Event<'a>(&'a Component);
+
+impl<'a> Event<'a> {
+    fn from_component(c: &'a Component) -> Result<Event<'a>, &'a Component> {
+        if c.name == "VEVENT" {
+            Ok(Event(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(dtend       , "DTEND"       , Dtend);
+    make_getter_function_for_optional!(dtstart     , "DTSTART"     , Dtstart);
+    make_getter_function_for_optional!(dtstamp     , "DTSTAMP"     , Dtstamp);
+    make_getter_function_for_optional!(uid         , "UID"         , Uid);
+    make_getter_function_for_optional!(description , "DESCRIPTION" , Description);
+    make_getter_function_for_optional!(summary     , "SUMMARY"     , Summary);
+    make_getter_function_for_optional!(url         , "URL"         , Url);
+    make_getter_function_for_optional!(location    , "LOCATION"    , Location);
+    make_getter_function_for_optional!(class       , "CLASS"       , Class);
+    make_getter_function_for_optional!(categories  , "CATEGORIES"  , Categories);
+    make_getter_function_for_optional!(transp      , "TRANSP"      , Transp);
+    make_getter_function_for_optional!(rrule       , "RRULE"       , Rrule);
+    make_getter_function_for_optional!(duration    , "DURATION"    , Duration);
+    make_getter_function_for_optional!(geo         , "GEO"         , Geo);
+    make_getter_function_for_optional!(status      , "STATUS"      , Status);
+    make_getter_function_for_optional!(organizer   , "ORGANIZER"   , Organizer);
+    make_getter_function_for_values!(attendees     , "ATTENDEE"    , Attendee);
+    make_getter_function_for_optional!(recurrence_id, "RECURRENCE-ID", RecurrenceId);
+    make_getter_function_for_values!(exdate        , "EXDATE"      , Exdate);
+    make_getter_function_for_optional!(last_modified, "LAST-MODIFIED", LastModified);
+    make_getter_function_for_optional!(created     , "CREATED"     , Created);
+    make_getter_function_for_values!(attachments   , "ATTACH"      , Attach);
+
+    make_getter_function_for_language!(summary_for_lang     , "SUMMARY"     , Summary);
+    make_getter_function_for_language!(description_for_lang , "DESCRIPTION" , Description);
+
+    /// All excluded instances, from all `EXDATE` properties.
+    #[cfg(feature = "timeconversions")]
+    pub fn exdates(&self) -> VObjectResult<Vec<Time>> {
+        let mut rv = vec![];
+        for exdate in self.exdate() {
+            rv.extend(exdate.as_datetimes()?);
+        }
+        Ok(rv)
+    }
+
+    /// All excluded instances, from all `EXDATE` properties.
+    #[cfg(not(feature = "timeconversions"))]
+    pub fn exdates(&self) -> Vec<String> {
+        self.exdate().iter().flat_map(Exdate::values).collect()
+    }
+
+    /// The end of the event: `DTEND` if present, otherwise `DTSTART` plus `DURATION`.
     ///
-    /// # Notice
+    /// Returns `None` if neither `DTEND` nor both `DTSTART` and `DURATION` are set. Adding a
+    /// duration of whole days to a date yields a date, other durations turn dates into floating
+    /// times.
+    #[cfg(feature = "timeconversions")]
+    pub fn effective_end(&self) -> VObjectResult<Option<Time>> {
+        if let Some(dtend) = self.dtend() {
+            return dtend.as_datetime().map(Some);
+        }
+
+        let (start, raw_duration) = match (self.dtstart(), self.duration()) {
+            (Some(start), Some(duration)) => (start.as_datetime()?, duration),
+            _ => return Ok(None),
+        };
+        let duration = raw_duration.as_duration()?;
+
+        let end = match start {
+            Time::Date(d) if duration.num_seconds() % 86400 == 0 => {
+                d.checked_add_signed(duration).map(Time::Date)
+            }
+            Time::Date(d) => d.and_hms_opt(0, 0, 0).unwrap_or_default()
+                .checked_add_signed(duration)
+                .map(Time::Floating),
+            Time::Utc(dt) => dt.checked_add_signed(duration).map(Time::Utc),
+            Time::Floating(dt) => dt.checked_add_signed(duration).map(Time::Floating),
+            Time::Local(dt, tzid) => dt.checked_add_signed(duration).map(|dt| Time::Local(dt, tzid)),
+        };
+        end.map(Some).ok_or_else(|| VObjectError::InvalidValue("DURATION", raw_duration.raw().clone()))
+    }
+
+    /// Get an iterator over the alarms of this event
     ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_dtend, "DTEND", Dtend, Dtend::into_raw);
+    /// Works like `ICalendar::events()`, but for the `VALARM` subcomponents of the event.
+    pub fn alarms(&self) -> AlarmIterator<'a> {
+        AlarmIterator(self.0.subcomponents.iter())
+    }
+
+    /// Copy the event out of the calendar, e.g. to send it to another thread.
+    #[allow(clippy::should_implement_trait)]
+    pub fn to_owned(&self) -> OwnedEvent {
+        OwnedEvent(self.0.clone())
+    }
+
+    pub fn build() -> EventBuilder {
+        EventBuilder(Component::new(String::from("VEVENT")))
+    }
+
+}
+
+/// An event which owns its component, unlike `Event` which borrows from the calendar.
+///
+/// Created with `Event::to_owned()`. Use `as_event()` to access the properties.
+#[derive(Debug, Clone)]
+pub struct OwnedEvent(Component);
+
+impl OwnedEvent {
+    pub fn as_event(&self) -> Event<'_> {
+        Event(&self.0)
+    }
+}
+
+impl Deref for OwnedEvent {
+    type Target = Component;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<OwnedEvent> for Component {
+    fn from(event: OwnedEvent) -> Component {
+        event.0
+    }
+}
+
+/// A recurring event together with the instances that override some of its occurrences.
+///
+/// Overrides are the events sharing the UID of the master event and carrying a
+/// `RECURRENCE-ID`, which names the occurrence they replace.
+#[derive(Debug, Clone)]
+pub struct RecurrenceSet<'a> {
+    /// The event holding the recurrence rule. Missing if only overrides were sent, as happens
+    /// with invitations to single occurrences.
+    pub master: Option<Event<'a>>,
+    pub overrides: Vec<Event<'a>>,
+}
+
+create_data_type!(Dtend, "DTEND");
+create_data_type!(Dtstart, "DTSTART");
+create_data_type!(Dtstamp, "DTSTAMP");
+create_data_type!(Uid, "UID");
+create_data_type!(Description, "DESCRIPTION");
+create_data_type!(Summary, "SUMMARY");
+create_data_type!(Url, "URL");
+create_data_type!(Location, "LOCATION");
+create_data_type!(Class, "CLASS");
+create_data_type!(Categories, "CATEGORIES");
+create_data_type!(Transp, "TRANSP");
+create_data_type!(Rrule, "RRULE");
+create_data_type!(Geo, "GEO");
+create_data_type!(RecurrenceId, "RECURRENCE-ID");
+create_data_type!(Exdate, "EXDATE");
+create_data_type!(LastModified, "LAST-MODIFIED");
+create_data_type!(Created, "CREATED");
+create_data_type!(Attach, "ATTACH");
+
+impl Exdate {
+    /// The raw values of this property, which may hold several comma-separated dates.
+    pub fn values(&self) -> Vec<String> {
+        split_text_list(&self.0)
+            .into_iter()
+            .map(|v| String::from(v.trim()))
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+
+    /// Parse all dates of this property.
+    #[cfg(feature = "timeconversions")]
+    pub fn as_datetimes(&self) -> VObjectResult<Vec<Time>> {
+        self.values().iter().map(|v| parse_time(v, &self.1)).collect()
+    }
+}
+
+impl Categories {
+    /// Create a comma-separated list, escaping commas within the entries.
+    pub fn from_values(values: Vec<String>) -> Categories {
+        Categories::from_raw(join_text_list(&values))
+    }
+
+    /// The unescaped entries of the list.
+    pub fn values(&self) -> Vec<String> {
+        split_text_list(&self.0)
+    }
+}
+
+create_value_enum!(
+    /// The status of an event, from the `STATUS` property.
+    EventStatus {
+        Tentative => "TENTATIVE",
+        Confirmed => "CONFIRMED",
+        Cancelled => "CANCELLED",
+    });
+
+create_value_enum!(
+    /// Whether an event blocks time, from the `TRANSP` property.
+    Transparency {
+        Opaque      => "OPAQUE",
+        Transparent => "TRANSPARENT",
+    });
+
+create_value_enum!(
+    /// The access classification, from the `CLASS` property.
+    Classification {
+        Public       => "PUBLIC",
+        Private      => "PRIVATE",
+        Confidential => "CONFIDENTIAL",
+    });
+
+impl Class {
+    pub fn parsed(&self) -> Classification {
+        Classification::from_ical_str(&self.0)
+    }
+}
+
+impl From<Classification> for Class {
+    fn from(c: Classification) -> Class {
+        Class::from_raw(c.as_ical_str().to_owned())
+    }
+}
+
+impl Transp {
+    pub fn parsed(&self) -> Transparency {
+        Transparency::from_ical_str(&self.0)
+    }
+}
+
+impl From<Transparency> for Transp {
+    fn from(t: Transparency) -> Transp {
+        Transp::from_raw(t.as_ical_str().to_owned())
+    }
+}
+
+impl Geo {
+    /// Create a value like `37.386013;-122.082932`.
+    pub fn from_coords(lat: f64, lon: f64) -> Geo {
+        Geo::from_raw(format!("{};{}", lat, lon))
+    }
+
+    /// Latitude and longitude. Also accepts the `geo:` URI form used by vCard 4.0.
+    pub fn coordinates(&self) -> VObjectResult<(f64, f64)> {
+        parse_geo(&self.0)
+    }
+}
+
+#[cfg(feature = "timeconversions")]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub enum Time {
+    /// A date without time, e.g. `20160325`.
+    Date(NaiveDate),
+
+    /// A date-time in UTC, e.g. `20160325T120000Z`.
+    Utc(NaiveDateTime),
+
+    /// A date-time without timezone information, e.g. `20160325T120000`.
+    Floating(NaiveDateTime),
+
+    /// A date-time local to the timezone given by the `TZID` parameter, e.g.
+    /// `DTSTART;TZID=Europe/Berlin:20160325T120000`.
+    Local(NaiveDateTime, String),
+}
+
+#[cfg(feature = "timeconversions")]
+pub trait AsDateTime {
+    fn as_datetime(&self) -> VObjectResult<Time>;
+}
+
+/// Parse a date or date-time value, taking the trailing `Z` and the `TZID` parameter into account.
+#[cfg(feature = "timeconversions")]
+pub(crate) fn parse_time(raw: &str, params: &::param::Parameters) -> VObjectResult<Time> {
+    if let Ok(dt) = NaiveDateTime::parse_from_str(raw, DATE_TIME_FMT) {
+        return Ok(Time::Utc(dt));
+    }
+
+    Ok(match NaiveDateTime::parse_from_str(raw, LOCAL_DATE_TIME_FMT) {
+        Ok(dt) => match params.get("TZID") {
+            Some(tzid) => Time::Local(dt, tzid.to_owned()),
+            None => Time::Floating(dt),
+        },
+        Err(_) => NaiveDate::parse_from_str(raw, DATE_FMT)
+            .map(Time::Date)?,
+    })
+}
+
+macro_rules! make_as_datetime_impl {
+    ($type:ty) => {
+        #[cfg(feature = "timeconversions")]
+        impl AsDateTime for $type {
+
+            fn as_datetime(&self) -> VObjectResult<Time> {
+                parse_time(&self.0, &self.1)
+            }
+
+        }
+    };
+}
+
+make_as_datetime_impl!(Dtend);
+make_as_datetime_impl!(Dtstart);
+make_as_datetime_impl!(Dtstamp);
+make_as_datetime_impl!(RecurrenceId);
+
+make_chrono_conversions!(Dtend);
+make_chrono_conversions!(Dtstart);
+make_chrono_conversions!(Dtstamp);
+make_chrono_conversions!(RecurrenceId);
+make_chrono_conversions!(LastModified);
+make_chrono_conversions!(Created);
+
+make_timestamp_conversions!(LastModified);
+make_timestamp_conversions!(Created);
+
+#[cfg(feature = "timeconversions")]
+impl Rrule {
+    /// Parse the raw value into a structured `RecurrenceRule`.
+    pub fn parse(&self) -> VObjectResult<RecurrenceRule> {
+        self.0.parse()
+    }
+}
+
+#[cfg(feature = "timeconversions")]
+impl From<RecurrenceRule> for Rrule {
+    fn from(rule: RecurrenceRule) -> Rrule {
+        Rrule::from_raw(rule.to_rrule_string())
+    }
+}
+
+/// The value of an `ATTACH` property, see `Attach::kind()`.
+#[derive(Clone, Debug)]
+pub enum AttachKind {
+    /// A reference to the attachment, such as `https://example.com/report.pdf`.
+    Uri(String),
+
+    /// The attachment itself, with its media type from the `FMTTYPE` parameter. Decoding fails
+    /// if the value is not valid base64.
+    Binary { mime: Option<String>, data: VObjectResult<Vec<u8>> },
+}
+
+impl Attach {
+    /// Whether the attachment is inline (`VALUE=BINARY` or `ENCODING=BASE64`) or a URI.
+    pub fn kind(&self) -> AttachKind {
+        let is = |param: &str, value: &str| self.1.get(param).is_some_and(|v| v.eq_ignore_ascii_case(value));
+        if is("VALUE", "BINARY") || is("ENCODING", "BASE64") {
+            AttachKind::Binary {
+                mime: self.1.get("FMTTYPE").map(String::from),
+                data: base64_decode(&self.0).ok_or_else(|| VObjectError::InvalidValue("BINARY", self.0.clone())),
+            }
+        } else {
+            AttachKind::Uri(self.0.clone())
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EventBuilder(Component);
+
+/// Text properties whose values are escaped by the builders.
+///
+/// Structured and list values like `GEO` or `CATEGORIES` use `;` and `,` as separators and are
+/// passed through unchanged.
+const ESCAPED_PROPERTIES: &[&str] = &[
+    "ACTION", "CALSCALE", "CLASS", "COMMENT", "CONTACT", "DESCRIPTION", "LOCATION", "METHOD",
+    "PRODID", "STATUS", "SUMMARY", "TRANSP", "TZID", "UID", "VERSION",
+];
+
+/// The value of a property set through a builder, escaped if it is text.
+///
+/// Escaping leaves existing escape sequences alone, so both values from `from_raw` and from
+/// `new_unescaped` end up correctly escaped.
+fn builder_raw_value(name: &str, raw: String) -> String {
+    if ESCAPED_PROPERTIES.contains(&name) {
+        escape_unescaped(&raw)
+    } else {
+        raw
+    }
+}
+
+macro_rules! make_setter_function_for {
+    ($(#[$attr:meta])* $fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
+        $(#[$attr])*
+        pub fn $fnname(&mut self, value: $type, params: Option<Parameters>) {
+            let params = params.unwrap_or_else(|| value.params().clone());
+            let property = Property {
+                name:       String::from($name),
+                params,
+                raw_value:  builder_raw_value($name, $tostring(value)),
+                prop_group: None,
+            };
+
+            self.0.set(property);
+        }
+    };
+}
+
+macro_rules! make_function_for {
+    ($(#[$attr:meta])* $fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
+        $(#[$attr])*
+        pub fn $fnname(mut self, value: $type, params: Option<Parameters>) -> Self {
+            let params = params.unwrap_or_else(|| value.params().clone());
+            let property = Property {
+                name:       String::from($name),
+                params,
+                raw_value:  builder_raw_value($name, $tostring(value)),
+                prop_group: None,
+            };
+
+            self.0.push(property);
+            self
+        }
+    };
+}
+
+impl ICalendar {
+
+    make_setter_function_for!(
+        /// Setter for "VERSION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_version, "VERSION", Version, Version::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "PRODID" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_prodid, "PRODID", Prodid, Prodid::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "METHOD" property, such as `PUBLISH` or `REQUEST`
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_method, "METHOD", Method, Method::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "CALSCALE" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_calscale, "CALSCALE", Calscale, Calscale::into_raw);
+
+    /// Chainable variant of `ICalendar::set_version()`.
+    pub fn with_version(mut self, value: Version, params: Option<Parameters>) -> Self {
+        self.set_version(value, params);
+        self
+    }
+
+    /// Chainable variant of `ICalendar::set_prodid()`.
+    pub fn with_prodid(mut self, value: Prodid, params: Option<Parameters>) -> Self {
+        self.set_prodid(value, params);
+        self
+    }
+
+    /// Chainable variant of `ICalendar::set_method()`.
+    pub fn with_method(mut self, value: Method, params: Option<Parameters>) -> Self {
+        self.set_method(value, params);
+        self
+    }
+
+    /// Chainable variant of `ICalendar::set_calscale()`.
+    pub fn with_calscale(mut self, value: Calscale, params: Option<Parameters>) -> Self {
+        self.set_calscale(value, params);
+        self
+    }
+}
+
+impl EventBuilder {
+
+    /// Private function for adding event to calendar
+    fn into_component(self) -> Component {
+        self.0
+    }
+
+    /// Check the event and return it as component.
+    ///
+    /// Fails if required properties (`UID`, `DTSTAMP`) are missing, if both `DTEND` and
+    /// `DURATION` are set, or if the event has any other error-level problem as reported by
+    /// `ICalendar::validate()`.
+    pub fn finish(self) -> VObjectResult<Component> {
+        let mut problems = vec![];
+        validate_component(&self.0, Some("VCALENDAR"), String::from("VEVENT"), &mut problems);
+        problems.retain(|p| p.severity() == Severity::Error);
+
+        if problems.is_empty() {
+            Ok(self.0)
+        } else {
+            Err(VObjectError::InvalidICalendar(problems))
+        }
+    }
+
+    /// Set an arbitrary property, such as `X-` properties which have no dedicated setter.
+    ///
+    /// # Notice
+    ///
+    /// Internally, the property is overridden. Old values are dropped silently:
+    pub fn set_property(&mut self, property: Property) {
+        self.0.set(property);
+    }
+
+    /// Chainable variant of `EventBuilder::set_property()`.
+    pub fn with_property(mut self, property: Property) -> Self {
+        self.0.set(property);
+        self
+    }
+
+    /// Set `DTSTAMP` to the current time.
+    #[cfg(feature = "timeconversions")]
+    pub fn touch_dtstamp(mut self) -> Self {
+        self.set_dtstamp(Dtstamp::from_datetime_utc(::chrono::Utc::now()), None);
+        self
+    }
+
+    /// Add an `ATTACH` property referring to the attachment by URI.
+    pub fn with_attach_uri(mut self, uri: &str) -> Self {
+        self.0.push(Property {
+            name: String::from("ATTACH"),
+            params: Parameters::new(),
+            raw_value: String::from(uri),
+            prop_group: None,
+        });
+        self
+    }
+
+    /// Add an `ATTACH` property with the attachment inline, with `mime` being its media type,
+    /// such as `application/pdf`.
+    pub fn with_attach_binary(mut self, bytes: &[u8], mime: &str) -> Self {
+        self.0.push(Property {
+            name: String::from("ATTACH"),
+            params: parameters!("VALUE" => "BINARY", "ENCODING" => "BASE64", "FMTTYPE" => mime),
+            raw_value: base64_encode(bytes),
+            prop_group: None,
+        });
+        self
+    }
+
+    /// Add an alarm to the event
+    pub fn add_alarm(&mut self, builder: AlarmBuilder) {
+        self.0.subcomponents.push(builder.into_component())
+    }
+
+    /// Chainable variant of `EventBuilder::add_alarm()`.
+    pub fn with_alarm(mut self, builder: AlarmBuilder) -> Self {
+        self.0.subcomponents.push(builder.into_component());
+        self
+    }
+
+    make_setter_function_for!(
+        /// Setter for "DTEND" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtend, "DTEND", Dtend, Dtend::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DTSTART" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DTSTAMP" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "UID" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_uid, "UID", Uid, Uid::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DESCRIPTION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "SUMMARY" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "URL" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_url, "URL", Url, Url::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "LOCATION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_location, "LOCATION", Location, Location::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "CLASS" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_class, "CLASS", Class, Class::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "CATEGORIES" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_categories, "CATEGORIES", Categories, Categories::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "TRANSP" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_transp, "TRANSP", Transp, Transp::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "RRULE" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_rrule, "RRULE", Rrule, Rrule::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DURATION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_duration, "DURATION", Duration, Duration::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "GEO" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_geo, "GEO", Geo, Geo::into_raw);
+
+    //
+    // chainable builders
+    //
+
+    make_function_for!(
+        /// Chainable setter for "DTEND" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtend, "DTEND", Dtend, Dtend::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DTSTART" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DTSTAMP" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "UID" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_uid, "UID", Uid, Uid::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DESCRIPTION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "SUMMARY" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "URL" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_url, "URL", Url, Url::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "LOCATION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_location, "LOCATION", Location, Location::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "CLASS" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_class, "CLASS", Class, Class::into_raw);
+
+    /// Chainable setter for "CATEGORIES" property, from the unescaped entries.
+    ///
+    /// # Notice
+    ///
+    /// Internally, the property is added, not overridden.
+    pub fn with_categories(mut self, categories: Vec<String>) -> Self {
+        self.0.push(Property {
+            name:       String::from("CATEGORIES"),
+            params:     Parameters::new(),
+            raw_value:  join_text_list(&categories),
+            prop_group: None,
+        });
+        self
+    }
+
+    make_function_for!(
+        /// Chainable setter for "TRANSP" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_transp, "TRANSP", Transp, Transp::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "RRULE" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_rrule, "RRULE", Rrule, Rrule::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DURATION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_duration, "DURATION", Duration, Duration::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "GEO" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_geo, "GEO", Geo, Geo::into_raw);
+
+    /// Setter for "STATUS" property
+    ///
+    /// # Notice
+    ///
+    /// Internally, the property is overridden. Old values are dropped silently:
+    pub fn set_status(&mut self, status: EventStatus) {
+        self.0.set(Property::new("STATUS", status.as_ical_str()));
+    }
+
+    /// Chainable variant of `EventBuilder::set_status()`.
+    pub fn with_status(mut self, status: EventStatus) -> Self {
+        self.set_status(status);
+        self
+    }
+
+    make_setter_function_for!(
+        /// Setter for "ORGANIZER" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_organizer, "ORGANIZER", Organizer, Organizer::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "ORGANIZER" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_organizer, "ORGANIZER", Organizer, Organizer::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "ATTENDEE" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden, so this can be called once per
+        /// attendee.
+        with_attendee, "ATTENDEE", Attendee, Attendee::into_raw);
+
+}
+
+pub struct EventIterMut<'a>(::std::slice::IterMut<'a, Component>);
+
+impl<'a> Iterator for EventIterMut<'a> {
+    type Item = Result<EventMut<'a>, &'a mut Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(EventMut::from_component)
+    }
+
+}
+
+/// A mutable view on an event inside an `ICalendar`.
+///
+/// The setters work like the ones of `EventBuilder`: The property is overridden, old values are
+/// dropped silently.
+#[derive(Debug)]
+pub struct EventMut<'a>(&'a mut Component);
+
+impl<'a> EventMut<'a> {
+    fn from_component(c: &'a mut Component) -> Result<EventMut<'a>, &'a mut Component> {
+        if c.name == "VEVENT" {
+            Ok(EventMut(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(dtend       , "DTEND"       , Dtend);
+    make_getter_function_for_optional!(dtstart     , "DTSTART"     , Dtstart);
+    make_getter_function_for_optional!(dtstamp     , "DTSTAMP"     , Dtstamp);
+    make_getter_function_for_optional!(uid         , "UID"         , Uid);
+    make_getter_function_for_optional!(description , "DESCRIPTION" , Description);
+    make_getter_function_for_optional!(summary     , "SUMMARY"     , Summary);
+    make_getter_function_for_optional!(url         , "URL"         , Url);
+    make_getter_function_for_optional!(location    , "LOCATION"    , Location);
+    make_getter_function_for_optional!(class       , "CLASS"       , Class);
+    make_getter_function_for_optional!(categories  , "CATEGORIES"  , Categories);
+    make_getter_function_for_optional!(transp      , "TRANSP"      , Transp);
+    make_getter_function_for_optional!(rrule       , "RRULE"       , Rrule);
+    make_getter_function_for_optional!(duration    , "DURATION"    , Duration);
+    make_getter_function_for_optional!(geo         , "GEO"         , Geo);
+    make_getter_function_for_optional!(last_modified, "LAST-MODIFIED", LastModified);
+    make_getter_function_for_optional!(created     , "CREATED"     , Created);
+
+    /// Set `DTSTAMP` and `LAST-MODIFIED` to the current time, e.g. after modifying the event.
+    #[cfg(feature = "timeconversions")]
+    pub fn touch(&mut self) {
+        let now = ::chrono::Utc::now();
+        self.set_dtstamp(Dtstamp::from_datetime_utc(now), None);
+        self.set_last_modified(LastModified::from_datetime_utc(now), None);
+    }
+
+    make_setter_function_for!(set_dtend, "DTEND", Dtend, Dtend::into_raw);
+    make_setter_function_for!(set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+    make_setter_function_for!(set_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
+    make_setter_function_for!(set_uid, "UID", Uid, Uid::into_raw);
+    make_setter_function_for!(set_description, "DESCRIPTION", Description, Description::into_raw);
+    make_setter_function_for!(set_summary, "SUMMARY", Summary, Summary::into_raw);
+    make_setter_function_for!(set_url, "URL", Url, Url::into_raw);
+    make_setter_function_for!(set_location, "LOCATION", Location, Location::into_raw);
+    make_setter_function_for!(set_class, "CLASS", Class, Class::into_raw);
+    make_setter_function_for!(set_categories, "CATEGORIES", Categories, Categories::into_raw);
+    make_setter_function_for!(set_transp, "TRANSP", Transp, Transp::into_raw);
+    make_setter_function_for!(set_rrule, "RRULE", Rrule, Rrule::into_raw);
+    make_setter_function_for!(set_duration, "DURATION", Duration, Duration::into_raw);
+    make_setter_function_for!(set_geo, "GEO", Geo, Geo::into_raw);
+    make_setter_function_for!(set_last_modified, "LAST-MODIFIED", LastModified, LastModified::into_raw);
+
+}
+
+pub struct TodoIterator<'a>(::std::slice::Iter<'a, Component>);
+
+impl<'a> Iterator for TodoIterator<'a> {
+    type Item = Result<Todo<'a>, &'a Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Todo::from_component)
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct Todo<'a>(&'a Component);
+
+impl<'a> Todo<'a> {
+    fn from_component(c: &'a Component) -> Result<Todo<'a>, &'a Component> {
+        if c.name == "VTODO" {
+            Ok(Todo(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(dtstart          , "DTSTART"          , Dtstart);
+    make_getter_function_for_optional!(due              , "DUE"              , Due);
+    make_getter_function_for_optional!(completed        , "COMPLETED"        , Completed);
+    make_getter_function_for_optional!(uid              , "UID"              , Uid);
+    make_getter_function_for_optional!(description      , "DESCRIPTION"      , Description);
+    make_getter_function_for_optional!(summary          , "SUMMARY"          , Summary);
+    make_getter_function_for_optional!(percent_complete , "PERCENT-COMPLETE" , PercentComplete);
+    make_getter_function_for_optional!(priority         , "PRIORITY"         , Priority);
+    make_getter_function_for_optional!(status           , "STATUS"           , Status);
+    make_getter_function_for_optional!(rrule            , "RRULE"            , Rrule);
+    make_getter_function_for_optional!(duration         , "DURATION"         , Duration);
+
+    /// Whether the todo is done, by `STATUS:COMPLETED` or a `COMPLETED` timestamp.
+    pub fn is_completed(&self) -> bool {
+        todo_is_completed(self.0)
+    }
+
+    /// `PERCENT-COMPLETE`, clamped to 0 to 100. Values which are not integers are errors.
+    pub fn percent(&self) -> VObjectResult<Option<u8>> {
+        todo_percent(self.0)
+    }
+
+    pub fn build() -> TodoBuilder {
+        TodoBuilder(Component::new(String::from("VTODO")))
+    }
+
+}
+
+pub struct TodoIterMut<'a>(::std::slice::IterMut<'a, Component>);
+
+impl<'a> Iterator for TodoIterMut<'a> {
+    type Item = Result<TodoMut<'a>, &'a mut Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(TodoMut::from_component)
+    }
+
+}
+
+/// A mutable view on a todo inside an `ICalendar`, see `EventMut`.
+#[derive(Debug)]
+pub struct TodoMut<'a>(&'a mut Component);
+
+impl<'a> TodoMut<'a> {
+    fn from_component(c: &'a mut Component) -> Result<TodoMut<'a>, &'a mut Component> {
+        if c.name == "VTODO" {
+            Ok(TodoMut(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(due              , "DUE"              , Due);
+    make_getter_function_for_optional!(completed        , "COMPLETED"        , Completed);
+    make_getter_function_for_optional!(uid              , "UID"              , Uid);
+    make_getter_function_for_optional!(summary          , "SUMMARY"          , Summary);
+    make_getter_function_for_optional!(percent_complete , "PERCENT-COMPLETE" , PercentComplete);
+    make_getter_function_for_optional!(status           , "STATUS"           , Status);
+
+    /// See `Todo::is_completed()`.
+    pub fn is_completed(&self) -> bool {
+        todo_is_completed(self.0)
+    }
+
+    /// See `Todo::percent()`.
+    pub fn percent(&self) -> VObjectResult<Option<u8>> {
+        todo_percent(self.0)
+    }
+
+    /// See `TodoBuilder::mark_completed()`.
+    #[cfg(feature = "timeconversions")]
+    pub fn mark_completed(&mut self, at: Option<NaiveDateTime>) {
+        todo_mark_completed(self.0, at)
+    }
+
+    /// See `TodoBuilder::reopen()`.
+    pub fn reopen(&mut self) {
+        todo_reopen(self.0)
+    }
+
+    make_setter_function_for!(set_due, "DUE", Due, Due::into_raw);
+    make_setter_function_for!(set_summary, "SUMMARY", Summary, Summary::into_raw);
+    make_setter_function_for!(set_status, "STATUS", Status, Status::into_raw);
+    make_setter_function_for!(set_percent_complete, "PERCENT-COMPLETE", PercentComplete, PercentComplete::into_raw);
+}
+
+fn todo_is_completed(c: &Component) -> bool {
+    c.get_only("COMPLETED").is_some() ||
+        c.get_only("STATUS").is_some_and(|s| s.raw_value.trim().eq_ignore_ascii_case("COMPLETED"))
+}
+
+fn todo_percent(c: &Component) -> VObjectResult<Option<u8>> {
+    let raw = match c.get_only("PERCENT-COMPLETE") {
+        Some(p) => &p.raw_value,
+        None => return Ok(None),
+    };
+    let percent: i64 = raw.trim()
+        .parse()
+        .map_err(|_| VObjectError::InvalidValue("PERCENT-COMPLETE", raw.clone()))?;
+    Ok(Some(percent.clamp(0, 100) as u8))
+}
+
+#[cfg(feature = "timeconversions")]
+fn todo_mark_completed(c: &mut Component, at: Option<NaiveDateTime>) {
+    c.set(Property::new("STATUS", "COMPLETED"));
+    c.set(Property::new("PERCENT-COMPLETE", "100"));
+    match at {
+        Some(at) => c.set(Property::new("COMPLETED", at.format(DATE_TIME_FMT).to_string())),
+        None => {
+            c.remove("COMPLETED");
+        },
+    }
+}
+
+fn todo_reopen(c: &mut Component) {
+    c.set(Property::new("STATUS", "NEEDS-ACTION"));
+    c.remove("COMPLETED");
+    c.remove("PERCENT-COMPLETE");
+}
+
+create_data_type!(Due, "DUE");
+create_data_type!(Completed, "COMPLETED");
+create_data_type!(PercentComplete, "PERCENT-COMPLETE");
+create_data_type!(Priority, "PRIORITY");
+create_data_type!(Status, "STATUS");
+
+impl Status {
+    /// The status of an event. Values for to-dos and journals, such as `COMPLETED`, end up in
+    /// `EventStatus::Other`.
+    pub fn parsed(&self) -> EventStatus {
+        EventStatus::from_ical_str(&self.0)
+    }
+}
+
+impl From<EventStatus> for Status {
+    fn from(s: EventStatus) -> Status {
+        Status::from_raw(s.as_ical_str().to_owned())
+    }
+}
+
+make_as_datetime_impl!(Due);
+make_as_datetime_impl!(Completed);
+
+#[derive(Clone, Debug)]
+pub struct TodoBuilder(Component);
+
+impl TodoBuilder {
+
+    /// Private function for adding todo to calendar
+    fn into_component(self) -> Component {
+        self.0
+    }
+
+    /// Mark the todo as done: `STATUS:COMPLETED`, `PERCENT-COMPLETE:100` and `COMPLETED` set to
+    /// `at`, which is in UTC. Without `at`, an existing `COMPLETED` is removed.
+    #[cfg(feature = "timeconversions")]
+    pub fn mark_completed(&mut self, at: Option<NaiveDateTime>) {
+        todo_mark_completed(&mut self.0, at)
+    }
+
+    /// Undo `mark_completed()`: `STATUS:NEEDS-ACTION`, without `COMPLETED` and
+    /// `PERCENT-COMPLETE`.
+    pub fn reopen(&mut self) {
+        todo_reopen(&mut self.0)
+    }
+
+    make_setter_function_for!(
+        /// Setter for "DTSTART" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DUE" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_due, "DUE", Due, Due::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "COMPLETED" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_completed, "COMPLETED", Completed, Completed::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "UID" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_uid, "UID", Uid, Uid::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DESCRIPTION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "SUMMARY" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "PERCENT-COMPLETE" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_percent_complete, "PERCENT-COMPLETE", PercentComplete, PercentComplete::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "PRIORITY" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_priority, "PRIORITY", Priority, Priority::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "STATUS" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_status, "STATUS", Status, Status::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "RRULE" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_rrule, "RRULE", Rrule, Rrule::into_raw);
+
+    //
+    // chainable builders
+    //
+
+    make_function_for!(
+        /// Chainable setter for "DTSTART" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DUE" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_due, "DUE", Due, Due::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "COMPLETED" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_completed, "COMPLETED", Completed, Completed::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "UID" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_uid, "UID", Uid, Uid::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DESCRIPTION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "SUMMARY" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "PERCENT-COMPLETE" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_percent_complete, "PERCENT-COMPLETE", PercentComplete, PercentComplete::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "PRIORITY" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_priority, "PRIORITY", Priority, Priority::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "STATUS" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_status, "STATUS", Status, Status::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "RRULE" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_rrule, "RRULE", Rrule, Rrule::into_raw);
+
+}
+
+pub struct JournalIterator<'a>(::std::slice::Iter<'a, Component>);
+
+impl<'a> Iterator for JournalIterator<'a> {
+    type Item = Result<Journal<'a>, &'a Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Journal::from_component)
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct Journal<'a>(&'a Component);
+
+impl<'a> Journal<'a> {
+    fn from_component(c: &'a Component) -> Result<Journal<'a>, &'a Component> {
+        if c.name == "VJOURNAL" {
+            Ok(Journal(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(uid         , "UID"         , Uid);
+    make_getter_function_for_optional!(dtstart     , "DTSTART"     , Dtstart);
+    make_getter_function_for_optional!(summary     , "SUMMARY"     , Summary);
+    make_getter_function_for_optional!(description , "DESCRIPTION" , Description);
+    make_getter_function_for_optional!(categories  , "CATEGORIES"  , Categories);
+
+    pub fn build() -> JournalBuilder {
+        JournalBuilder(Component::new(String::from("VJOURNAL")))
+    }
+
+}
+
+#[derive(Clone, Debug)]
+pub struct JournalBuilder(Component);
+
+impl JournalBuilder {
+
+    /// Private function for adding journal entry to calendar
+    fn into_component(self) -> Component {
+        self.0
+    }
+
+    make_setter_function_for!(
+        /// Setter for "UID" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_uid, "UID", Uid, Uid::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DTSTART" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "SUMMARY" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DESCRIPTION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "CATEGORIES" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_categories, "CATEGORIES", Categories, Categories::into_raw);
+
+    //
+    // chainable builders
+    //
+
+    make_function_for!(
+        /// Chainable setter for "UID" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_uid, "UID", Uid, Uid::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DTSTART" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "SUMMARY" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DESCRIPTION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "CATEGORIES" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_categories, "CATEGORIES", Categories, Categories::into_raw);
+
+}
+
+pub struct FreeBusyIterator<'a>(::std::slice::Iter<'a, Component>);
+
+impl<'a> Iterator for FreeBusyIterator<'a> {
+    type Item = Result<FreeBusy<'a>, &'a Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(FreeBusy::from_component)
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct FreeBusy<'a>(&'a Component);
+
+impl<'a> FreeBusy<'a> {
+    fn from_component(c: &'a Component) -> Result<FreeBusy<'a>, &'a Component> {
+        if c.name == "VFREEBUSY" {
+            Ok(FreeBusy(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(uid       , "UID"       , Uid);
+    make_getter_function_for_optional!(dtstart   , "DTSTART"   , Dtstart);
+    make_getter_function_for_optional!(dtend     , "DTEND"     , Dtend);
+    make_getter_function_for_optional!(organizer , "ORGANIZER" , Organizer);
+    make_getter_function_for_values!(attendee    , "ATTENDEE"  , Attendee);
+    make_getter_function_for_values!(freebusy    , "FREEBUSY"  , FreeBusyTime);
+
+    /// All periods of all `FREEBUSY` properties, as pairs of start and end (or duration).
+    pub fn periods(&self) -> Vec<(String, String)> {
+        self.freebusy().iter().flat_map(FreeBusyTime::periods).collect()
+    }
+
+    pub fn build() -> FreeBusyBuilder {
+        FreeBusyBuilder(Component::new(String::from("VFREEBUSY")))
+    }
+
+}
+
+create_data_type!(Organizer, "ORGANIZER");
+create_data_type!(Attendee, "ATTENDEE");
+
+/// The address of a calendar user, without a `mailto:` scheme.
+fn calendar_user_email(raw: &str) -> &str {
+    if raw.len() >= 7 && raw[..7].eq_ignore_ascii_case("mailto:") {
+        &raw[7..]
+    } else {
+        raw
+    }
+}
+
+/// A parameter value with surrounding quotes removed.
+fn unquoted_param(params: &Parameters, name: &str) -> Option<String> {
+    params.get(name).map(|value| value.trim_matches('"').to_owned())
+}
+
+/// A parameter value as stored: unquoted, as quotes are added on write where necessary, and
+/// without double quotes, which cannot be represented.
+fn param_value(value: &str) -> String {
+    value.replace('"', "")
+}
+
+impl Organizer {
+    /// Create an organizer with a `mailto:` URI.
+    pub fn from_email(email: &str) -> Organizer {
+        Organizer::from_raw(format!("mailto:{}", email))
+    }
+
+    /// Set the `CN` parameter.
+    pub fn with_common_name(mut self, cn: &str) -> Self {
+        self.1.insert(String::from("CN"), param_value(cn));
+        self
+    }
+
+    /// The address, with the `mailto:` scheme stripped.
+    pub fn email(&self) -> &str {
+        calendar_user_email(&self.0)
+    }
+
+    /// The `CN` parameter.
+    pub fn common_name(&self) -> Option<String> {
+        unquoted_param(&self.1, "CN")
+    }
+}
+
+create_value_enum!(
+    /// The participation status of an attendee, from the `PARTSTAT` parameter.
+    Partstat {
+        NeedsAction => "NEEDS-ACTION",
+        Accepted    => "ACCEPTED",
+        Declined    => "DECLINED",
+        Tentative   => "TENTATIVE",
+        Delegated   => "DELEGATED",
+    });
+
+impl Attendee {
+    /// Create an attendee with a `mailto:` URI.
+    pub fn from_email(email: &str) -> Attendee {
+        Attendee::from_raw(format!("mailto:{}", email))
+    }
+
+    /// Set the `CN` parameter.
+    pub fn with_common_name(mut self, cn: &str) -> Self {
+        self.1.insert(String::from("CN"), param_value(cn));
+        self
+    }
+
+    /// Set the `PARTSTAT` parameter.
+    pub fn with_partstat(mut self, partstat: Partstat) -> Self {
+        self.1.insert(String::from("PARTSTAT"), partstat.as_ical_str().to_owned());
+        self
+    }
+
+    /// Set the `ROLE` parameter, e.g. `REQ-PARTICIPANT` or `CHAIR`.
+    pub fn with_role(mut self, role: &str) -> Self {
+        self.1.insert(String::from("ROLE"), role.to_owned());
+        self
+    }
+
+    /// Set the `RSVP` parameter.
+    pub fn with_rsvp(mut self, rsvp: bool) -> Self {
+        self.1.insert(String::from("RSVP"), String::from(if rsvp { "TRUE" } else { "FALSE" }));
+        self
+    }
+
+    /// The address, with the `mailto:` scheme stripped.
+    pub fn email(&self) -> &str {
+        calendar_user_email(&self.0)
+    }
+
+    /// The `CN` parameter.
+    pub fn common_name(&self) -> Option<String> {
+        unquoted_param(&self.1, "CN")
+    }
+
+    /// The `PARTSTAT` parameter, which defaults to `NEEDS-ACTION`.
+    pub fn partstat(&self) -> Partstat {
+        self.1.get("PARTSTAT").map_or(Partstat::NeedsAction, Partstat::from_ical_str)
+    }
+
+    /// The `ROLE` parameter.
+    pub fn role(&self) -> Option<&str> {
+        self.1.get("ROLE")
+    }
+
+    /// The `CUTYPE` parameter, e.g. `INDIVIDUAL` or `ROOM`.
+    pub fn cutype(&self) -> Option<&str> {
+        self.1.get("CUTYPE")
+    }
+
+    /// Whether a reply is requested, from the `RSVP` parameter.
+    pub fn rsvp(&self) -> bool {
+        self.1.get("RSVP").is_some_and(|r| r.eq_ignore_ascii_case("TRUE"))
+    }
+
+    /// The addresses of the `DELEGATED-TO` parameter, with the `mailto:` scheme stripped.
+    pub fn delegated_to(&self) -> Vec<String> {
+        self.1
+            .get("DELEGATED-TO")
+            .map(|value| {
+                value.split(',')
+                    .map(|addr| calendar_user_email(addr.trim().trim_matches('"')).to_owned())
+                    .filter(|addr| !addr.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+create_data_type!(FreeBusyTime, "FREEBUSY");
+
+impl FreeBusyTime {
+    /// Split the comma-separated list of periods into pairs of start and end (or duration).
+    ///
+    /// Periods without a `/` are skipped.
+    pub fn periods(&self) -> Vec<(String, String)> {
+        split_text_list(&self.0)
+            .into_iter()
+            .filter_map(|period| {
+                let mut parts = period.splitn(2, '/');
+                match (parts.next(), parts.next()) {
+                    (Some(start), Some(end)) => Some((String::from(start), String::from(end))),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Like `periods()`, but as `Period` values. Periods without a `/` are skipped.
+    pub fn as_periods(&self) -> Vec<Period> {
+        self.periods()
+            .into_iter()
+            .map(|(start, end)| Period { start, end })
+            .collect()
+    }
+}
+
+/// A period of time, as used in `FREEBUSY`: a start and either an end or a duration, such as
+/// `19980314T233000Z/19980315T003000Z` or `19980314T233000Z/PT1H`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Period {
+    start: String,
+    end: String,
+}
+
+impl Period {
+    /// Parse a period of the form `start/end` or `start/duration`.
+    pub fn from_raw(raw: &str) -> VObjectResult<Period> {
+        match raw.trim().split_once('/') {
+            Some((start, end)) if !start.is_empty() && !end.is_empty() => Ok(Period {
+                start: String::from(start),
+                end: String::from(end),
+            }),
+            _ => Err(VObjectError::InvalidValue("PERIOD", String::from(raw))),
+        }
+    }
+
+    /// A period from `start` to `end`, both in UTC.
+    #[cfg(feature = "timeconversions")]
+    pub fn new(start: NaiveDateTime, end: NaiveDateTime) -> Period {
+        Period {
+            start: start.format(DATE_TIME_FMT).to_string(),
+            end: end.format(DATE_TIME_FMT).to_string(),
+        }
+    }
+
+    /// The start as written.
+    pub fn raw_start(&self) -> &str {
+        &self.start
+    }
+
+    /// The end or the duration as written.
+    pub fn raw_end(&self) -> &str {
+        &self.end
+    }
+
+    /// Whether the period is given as start and duration.
+    pub fn has_duration(&self) -> bool {
+        self.end.trim_start_matches(['+', '-']).starts_with('P')
+    }
+
+    /// The start, which must be in UTC.
+    #[cfg(feature = "timeconversions")]
+    pub fn start(&self) -> VObjectResult<NaiveDateTime> {
+        Ok(NaiveDateTime::parse_from_str(&self.start, DATE_TIME_FMT)?)
+    }
+
+    /// The end, which must be in UTC, or else the start plus the duration.
+    #[cfg(feature = "timeconversions")]
+    pub fn end(&self) -> VObjectResult<NaiveDateTime> {
+        if self.has_duration() {
+            let duration = Duration::from_raw(self.end.clone()).as_duration()?;
+            self.start()?
+                .checked_add_signed(duration)
+                .ok_or_else(|| VObjectError::InvalidValue("PERIOD", self.to_string()))
+        } else {
+            Ok(NaiveDateTime::parse_from_str(&self.end, DATE_TIME_FMT)?)
+        }
+    }
+
+    /// Whether the periods share any time. Periods which only touch, where one ends when the
+    /// other starts, do not overlap.
+    #[cfg(feature = "timeconversions")]
+    pub fn overlaps(&self, other: &Period) -> VObjectResult<bool> {
+        Ok(self.start()? < other.end()? && other.start()? < self.end()?)
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.start, self.end)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FreeBusyBuilder(Component);
+
+impl FreeBusyBuilder {
+
+    /// Private function for adding free/busy information to calendar
+    fn into_component(self) -> Component {
+        self.0
+    }
+
+    make_setter_function_for!(
+        /// Setter for "UID" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_uid, "UID", Uid, Uid::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DTSTART" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DTEND" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtend, "DTEND", Dtend, Dtend::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "ORGANIZER" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_organizer, "ORGANIZER", Organizer, Organizer::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "ATTENDEE" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_attendee, "ATTENDEE", Attendee, Attendee::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "FREEBUSY" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_freebusy, "FREEBUSY", FreeBusyTime, FreeBusyTime::into_raw);
+
+    //
+    // chainable builders
+    //
+
+    make_function_for!(
+        /// Chainable setter for "UID" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_uid, "UID", Uid, Uid::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DTSTART" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DTEND" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtend, "DTEND", Dtend, Dtend::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "ORGANIZER" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_organizer, "ORGANIZER", Organizer, Organizer::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "ATTENDEE" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_attendee, "ATTENDEE", Attendee, Attendee::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "FREEBUSY" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_freebusy, "FREEBUSY", FreeBusyTime, FreeBusyTime::into_raw);
+
+}
+
+pub struct AlarmIterator<'a>(::std::slice::Iter<'a, Component>);
+
+impl<'a> Iterator for AlarmIterator<'a> {
+    type Item = Result<Alarm<'a>, &'a Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Alarm::from_component)
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct Alarm<'a>(&'a Component);
+
+impl<'a> Alarm<'a> {
+    fn from_component(c: &'a Component) -> Result<Alarm<'a>, &'a Component> {
+        if c.name == "VALARM" {
+            Ok(Alarm(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(action      , "ACTION"      , Action);
+    make_getter_function_for_optional!(trigger     , "TRIGGER"     , Trigger);
+    make_getter_function_for_optional!(description , "DESCRIPTION" , Description);
+    make_getter_function_for_optional!(repeat      , "REPEAT"      , Repeat);
+    make_getter_function_for_optional!(duration    , "DURATION"    , Duration);
+
+    pub fn build() -> AlarmBuilder {
+        AlarmBuilder(Component::new(String::from("VALARM")))
+    }
+
+}
+
+create_data_type!(Action, "ACTION");
+create_data_type!(Trigger, "TRIGGER");
+create_data_type!(Repeat, "REPEAT");
+create_data_type!(Duration, "DURATION");
+
+#[cfg(feature = "timeconversions")]
+impl Duration {
+
+    /// Parse an ISO 8601 duration like `PT1H30M`, `-PT15M` or `P2W`.
+    pub fn as_duration(&self) -> VObjectResult<chrono::Duration> {
+        let invalid = || VObjectError::InvalidDuration(self.0.clone());
+
+        let (sign, rest) = match self.0.chars().next() {
+            Some('-') => (-1, &self.0[1..]),
+            Some('+') => (1, &self.0[1..]),
+            _ => (1, &self.0[..]),
+        };
+
+        if !rest.starts_with('P') || rest.len() < 2 {
+            return Err(invalid());
+        }
+
+        let mut seconds: i64 = 0;
+        let mut in_time = false;
+        let mut number = String::new();
+        for c in rest[1..].chars() {
+            match c {
+                '0'..='9' => { number.push(c); continue; },
+                'T' if !in_time && number.is_empty() => { in_time = true; continue; },
+                _ => (),
+            }
+
+            let factor = match (c, in_time) {
+                ('W', false) => 7 * 24 * 3600,
+                ('D', false) => 24 * 3600,
+                ('H', true) => 3600,
+                ('M', true) => 60,
+                ('S', true) => 1,
+                _ => return Err(invalid()),
+            };
+            let n: i64 = number.parse().map_err(|_| invalid())?;
+            seconds = n.checked_mul(factor)
+                .and_then(|n| seconds.checked_add(n))
+                .ok_or_else(invalid)?;
+            number.clear();
+        }
+
+        if !number.is_empty() || rest.ends_with('T') {
+            return Err(invalid());
+        }
+
+        chrono::Duration::try_seconds(sign * seconds).ok_or_else(invalid)
+    }
+
+    /// Format a `chrono::Duration` canonically, e.g. `P2W`, `P1DT2H` or `-PT15M`.
+    ///
+    /// Fractions of seconds are dropped.
+    pub fn from_chrono(d: chrono::Duration) -> Duration {
+        let mut total = d.num_seconds();
+        let mut rv = String::new();
+        if total < 0 {
+            rv.push('-');
+            total = -total;
+        }
+        rv.push('P');
+
+        if total != 0 && total % (7 * 24 * 3600) == 0 {
+            rv.push_str(&format!("{}W", total / (7 * 24 * 3600)));
+            return Duration::from_raw(rv);
+        }
+
+        let (days, hours, minutes, seconds) =
+            (total / 86400, total % 86400 / 3600, total % 3600 / 60, total % 60);
+        if days > 0 {
+            rv.push_str(&format!("{}D", days));
+        }
+        if hours > 0 || minutes > 0 || seconds > 0 || days == 0 {
+            rv.push('T');
+            if hours > 0 {
+                rv.push_str(&format!("{}H", hours));
+            }
+            if minutes > 0 {
+                rv.push_str(&format!("{}M", minutes));
+            }
+            if seconds > 0 || (hours == 0 && minutes == 0) {
+                rv.push_str(&format!("{}S", seconds));
+            }
+        }
+
+        Duration::from_raw(rv)
+    }
+
+}
+
+/// Whether a `Trigger` is relative to the event or an absolute point in time.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TriggerKind {
+    /// A duration like `-PT15M`, relative to the start or end of the event.
+    Duration,
+
+    /// An absolute UTC date-time like `19980101T050000Z`.
+    DateTime,
+}
+
+impl Trigger {
+    /// Determine the kind of trigger from the `VALUE` parameter, which defaults to `DURATION`.
+    pub fn kind(&self) -> TriggerKind {
+        match self.1.get("VALUE") {
+            Some(v) if v.eq_ignore_ascii_case("DATE-TIME") => TriggerKind::DateTime,
+            _ => TriggerKind::Duration,
+        }
+    }
+
+    /// The `RELATED` parameter, which defaults to `START`. Only meaningful for durations.
+    pub fn related(&self) -> &str {
+        self.1.get("RELATED").unwrap_or("START")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AlarmBuilder(Component);
+
+impl AlarmBuilder {
+
+    /// Private function for adding alarm to calendar
+    fn into_component(self) -> Component {
+        self.0
+    }
+
+    make_setter_function_for!(
+        /// Setter for "ACTION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_action, "ACTION", Action, Action::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "TRIGGER" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_trigger, "TRIGGER", Trigger, Trigger::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DESCRIPTION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "REPEAT" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_repeat, "REPEAT", Repeat, Repeat::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DURATION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_duration, "DURATION", Duration, Duration::into_raw);
+
+    //
+    // chainable builders
+    //
+
+    make_function_for!(
+        /// Chainable setter for "ACTION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_action, "ACTION", Action, Action::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "TRIGGER" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_trigger, "TRIGGER", Trigger, Trigger::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DESCRIPTION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "REPEAT" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_repeat, "REPEAT", Repeat, Repeat::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DURATION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_duration, "DURATION", Duration, Duration::into_raw);
+
+}
+
+pub struct TimezoneIterator<'a>(::std::slice::Iter<'a, Component>);
+
+impl<'a> Iterator for TimezoneIterator<'a> {
+    type Item = Result<Timezone<'a>, &'a Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Timezone::from_component)
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct Timezone<'a>(&'a Component);
+
+impl<'a> Timezone<'a> {
+    fn from_component(c: &'a Component) -> Result<Timezone<'a>, &'a Component> {
+        if c.name == "VTIMEZONE" {
+            Ok(Timezone(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    make_getter_function_for_optional!(tzid , "TZID" , Tzid);
+
+    /// Get an iterator over the `STANDARD` and `DAYLIGHT` observances of this timezone
+    pub fn observances(&self) -> ObservanceIterator<'a> {
+        ObservanceIterator(self.0.subcomponents.iter())
+    }
+
+}
+
+create_data_type!(Tzid, "TZID");
+create_data_type!(TzOffsetFrom, "TZOFFSETFROM");
+create_data_type!(TzOffsetTo, "TZOFFSETTO");
+create_data_type!(TzName, "TZNAME");
+
+make_utc_offset_conversions!(TzOffsetFrom, parse_utc_offset);
+make_utc_offset_conversions!(TzOffsetTo, parse_utc_offset);
+
+pub struct ObservanceIterator<'a>(::std::slice::Iter<'a, Component>);
+
+impl<'a> Iterator for ObservanceIterator<'a> {
+    type Item = Result<Observance<'a>, &'a Component>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Observance::from_component)
+    }
+
+}
+
+/// Whether an `Observance` describes standard or daylight saving time.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ObservanceKind {
+    Standard,
+    Daylight,
+}
+
+/// A `STANDARD` or `DAYLIGHT` subcomponent of a `VTIMEZONE`.
+#[derive(Debug, Clone)]
+pub struct Observance<'a>(&'a Component);
+
+impl<'a> Observance<'a> {
+    fn from_component(c: &'a Component) -> Result<Observance<'a>, &'a Component> {
+        if c.name == "STANDARD" || c.name == "DAYLIGHT" {
+            Ok(Observance(c))
+        } else {
+            Err(c)
+        }
+    }
+
+    pub fn kind(&self) -> ObservanceKind {
+        if self.0.name == "DAYLIGHT" {
+            ObservanceKind::Daylight
+        } else {
+            ObservanceKind::Standard
+        }
+    }
+
+    make_getter_function_for_optional!(dtstart      , "DTSTART"      , Dtstart);
+    make_getter_function_for_optional!(tzoffsetfrom , "TZOFFSETFROM" , TzOffsetFrom);
+    make_getter_function_for_optional!(tzoffsetto   , "TZOFFSETTO"   , TzOffsetTo);
+    make_getter_function_for_values!(tzname         , "TZNAME"       , TzName);
+    make_getter_function_for_optional!(rrule        , "RRULE"        , Rrule);
+
+}
+
+#[cfg(feature = "timeconversions")]
+impl<'a> Timezone<'a> {
+
+    /// Find the UTC offset in seconds which applies at the given local time.
+    ///
+    /// This picks the observance with the latest onset before `local`. Yearly `RRULE`s using
+    /// `BYMONTH` and `BYDAY` (such as `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`) are expanded, other
+    /// recurrence rules and `RDATE`s are ignored. If `local` lies before all onsets, the
+    /// `TZOFFSETFROM` of the earliest observance is used.
+    pub fn utc_offset_at(&self, local: &NaiveDateTime) -> Option<i32> {
+        let mut latest: Option<(NaiveDateTime, Observance)> = None;
+        let mut earliest: Option<(NaiveDateTime, Observance)> = None;
+
+        for obs in self.observances().filter_map(Result::ok) {
+            for onset in obs.onsets_around(local.year()) {
+                if onset <= *local && latest.as_ref().is_none_or(|&(t, _)| onset > t) {
+                    latest = Some((onset, obs.clone()));
+                }
+                if earliest.as_ref().is_none_or(|&(t, _)| onset < t) {
+                    earliest = Some((onset, obs.clone()));
+                }
+            }
+        }
+
+        match (latest, earliest) {
+            (Some((_, obs)), _) => obs.tzoffsetto().and_then(|o| o.utc_offset()),
+            (None, Some((_, obs))) => obs.tzoffsetfrom().and_then(|o| o.utc_offset()),
+            (None, None) => None,
+        }
+    }
+
+}
+
+#[cfg(feature = "timeconversions")]
+impl<'a> Observance<'a> {
+
+    /// Onsets of this observance in the given and the previous year, plus the `DTSTART` itself.
+    fn onsets_around(&self, year: i32) -> Vec<NaiveDateTime> {
+        let start = match self.dtstart()
+            .and_then(|d| NaiveDateTime::parse_from_str(d.raw(), LOCAL_DATE_TIME_FMT).ok()) {
+            Some(start) => start,
+            None => return vec![],
+        };
+
+        let mut rv = vec![start];
+        let rule = match self.rrule().and_then(|r| r.parse().ok()) {
+            Some(rule) => rule,
+            None => return rv,
+        };
+
+        if rule.freq != Frequency::Yearly {
+            return rv;
+        }
+
+        let until = rule.until.map(|u| match u {
+            Time::Date(d) => d.and_hms_opt(0, 0, 0).unwrap_or(start),
+            Time::Utc(dt) | Time::Floating(dt) | Time::Local(dt, _) => dt,
+        });
+        let month = rule.by_month.first().map(|m| u32::from(*m)).unwrap_or_else(|| start.month());
+
+        for y in &[year - 1, year] {
+            let date = match rule.by_day.first() {
+                Some(&(n, weekday)) => nth_weekday_of_month(*y, month, n.unwrap_or(1), weekday),
+                None => NaiveDate::from_ymd_opt(*y, month, start.day()),
+            };
+
+            if let Some(onset) = date.map(|d| d.and_time(start.time())) {
+                if onset > start && until.is_none_or(|u| onset <= u) {
+                    rv.push(onset);
+                }
+            }
+        }
+
+        rv
+    }
+
+}
+
+/// Find the `n`th given weekday within the given month, counting from the end if `n` is negative.
+#[cfg(feature = "timeconversions")]
+fn nth_weekday_of_month(year: i32, month: u32, n: i8, weekday: Weekday) -> Option<NaiveDate> {
+    if n > 0 {
+        NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+    } else if n < 0 {
+        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        let mut date = NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()?;
+        while date.weekday() != weekday {
+            date = date.pred_opt()?;
+        }
+        let date = date - chrono::Duration::weeks(-i64::from(n) - 1);
+        if date.month() == month { Some(date) } else { None }
+    } else {
+        None
+    }
+}
+
+/// Convert a date or date-time value to UTC, using the `VTIMEZONE` referenced by its `TZID`
+/// parameter.
+///
+/// Dates and floating times are returned unchanged.
+#[cfg(feature = "timeconversions")]
+fn as_datetime_with_tz(raw: &str,
+                       params: &::param::Parameters,
+                       cal: &ICalendar) -> VObjectResult<Time> {
+    match parse_time(raw, params)? {
+        Time::Local(local, tzid) => {
+            let offset = cal.get_timezone(&tzid)
+                .and_then(|tz| tz.utc_offset_at(&local))
+                .ok_or(VObjectError::UnknownTimezone(tzid))?;
+
+            Ok(Time::Utc(local - chrono::Duration::seconds(i64::from(offset))))
+        },
+        other => Ok(other),
+    }
+}
+
+#[cfg(feature = "timeconversions")]
+impl Dtstart {
+    /// Convert to UTC, resolving the `TZID` parameter against the timezones of `cal`.
+    pub fn as_datetime_with_tz(&self, cal: &ICalendar) -> VObjectResult<Time> {
+        as_datetime_with_tz(&self.0, &self.1, cal)
+    }
+}
+
+#[cfg(feature = "timeconversions")]
+impl Dtend {
+    /// Convert to UTC, resolving the `TZID` parameter against the timezones of `cal`.
+    pub fn as_datetime_with_tz(&self, cal: &ICalendar) -> VObjectResult<Time> {
+        as_datetime_with_tz(&self.0, &self.1, cal)
+    }
+}
+
+#[cfg(all(test, feature = "timeconversions"))]
+mod tests {
+    use chrono::NaiveDate;
+    use chrono::NaiveDateTime;
+    use util::*;
+    use super::ICalendar;
+
+    use super::*;
+
+    const TEST_ENTRY : &str =
+            "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:http://www.example.com/calendarapplication/\n\
+            METHOD:PUBLISH\n\
+            BEGIN:VEVENT\n\
+            UID:461092315540@example.com\n\
+            ORGANIZER;CN=\"Alice Balder, Example Inc.\":MAILTO:alice@example.com\n\
+            LOCATION:Somewhere\n\
+            SUMMARY:Eine Kurzinfo\n\
+            DESCRIPTION:Beschreibung des Termines\n\
+            CLASS:PUBLIC\n\
+            DTSTART:20060910T220000Z\n\
+            DTEND:20060919T215900Z\n\
+            DTSTAMP:20060812T125900Z\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n";
+
+    const TEST_ENTRY_OC : &str = // Lets see how owncloud foo works here
+        "BEGIN:VCALENDAR\n\
+        VERSION:2.0\n\
+        PRODID:ownCloud Calendar\n\
+        CALSCALE:GREGORIAN\n\
+        BEGIN:VEVENT\n\
+        UID:ff411055a5\n\
+        DTSTAMP:20160128T223013Z\n\
+        CREATED:20160128T223013Z\n\
+        LAST-MODIFIED:20160128T223013Z\n\
+        SUMMARY:Amon Amarth - Jomsviking\n\
+        DTSTART;VALUE=DATE:20160325\n\
+        DTEND;VALUE=DATE:20160326\n\
+        LOCATION:\n\
+        DESCRIPTION:\n\
+        CATEGORIES:\n\
+        END:VEVENT\n\
+        END:VCALENDAR\n\
+        ";
+
+    #[test]
+    fn test_parse() {
+        let cal = ICalendar::build(TEST_ENTRY);
+        assert!(cal.is_ok(), "Not okay: {:?}\n in '{}'", cal, TEST_ENTRY);
+    }
+
+    #[test]
+    fn test_iter() {
+        let ical = ICalendar::build(TEST_ENTRY).unwrap();
+        assert_eq!(ical.events().count(), 1);
+    }
+
+    #[test]
+    fn test_icalendar_attributes() {
+        let ical = ICalendar::build(TEST_ENTRY).unwrap();
+        assert_eq!(ical.version().unwrap().raw(), "2.0");
+        assert_eq!(ical.prodid().unwrap().raw(), "http://www.example.com/calendarapplication/");
+    }
+
+    #[test]
+    fn test_event_attributes() {
+        let ical = ICalendar::build(TEST_ENTRY).unwrap();
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.dtend().map(|e| e.raw().clone())       , Some("20060919T215900Z".to_owned()));
+        assert_eq!(ev.dtstart().map(|e| e.raw().clone())     , Some("20060910T220000Z".to_owned()));
+        assert_eq!(ev.dtstamp().map(|e| e.raw().clone())     , Some("20060812T125900Z".to_owned()));
+        assert_eq!(ev.uid().map(|e| e.raw().clone())         , Some("461092315540@example.com".to_owned()));
+        assert_eq!(ev.description().map(|e| e.raw().clone()) , Some("Beschreibung des Termines".to_owned()));
+        assert_eq!(ev.summary().map(|e| e.raw().clone())     , Some("Eine Kurzinfo".to_owned()));
+        assert_eq!(ev.url()                                  , None);
+        assert_eq!(ev.location().map(|e| e.raw().clone())    , Some("Somewhere".to_owned()));
+        assert_eq!(ev.class().map(|e| e.raw().clone())       , Some("PUBLIC".to_owned()));
+        assert_eq!(ev.categories()                           , None);
+        assert_eq!(ev.transp()                               , None);
+        assert_eq!(ev.rrule()                                , None);
+    }
+
+    #[test]
+    fn test_event_attributes_oc() {
+        let ical = ICalendar::build(TEST_ENTRY_OC).unwrap();
+        assert_eq!(ical.version().unwrap().raw(), "2.0");
+        assert_eq!(ical.prodid().unwrap().raw(), "ownCloud Calendar");
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.dtend().map(|e| e.raw().clone())       , Some("20160326".to_owned()));
+        assert_eq!(ev.dtstart().map(|e| e.raw().clone())     , Some("20160325".to_owned()));
+        assert_eq!(ev.dtstamp().map(|e| e.raw().clone())     , Some("20160128T223013Z".to_owned()));
+        assert_eq!(ev.uid().map(|e| e.raw().clone())         , Some("ff411055a5".to_owned()));
+        assert_eq!(ev.description().map(|e| e.raw().clone()) , Some("".to_owned()));
+        assert_eq!(ev.summary().map(|e| e.raw().clone())     , Some("Amon Amarth - Jomsviking".to_owned()));
+        assert_eq!(ev.url()                                  , None);
+        assert_eq!(ev.location().map(|e| e.raw().clone())    , Some("".to_owned()));
+        assert_eq!(ev.class().map(|e| e.raw().clone())       , None);
+        assert_eq!(ev.categories().map(|e| e.raw().clone())  , Some("".to_owned()));
+        assert_eq!(ev.transp()                               , None);
+        assert_eq!(ev.rrule()                                , None);
+    }
+
+    #[cfg(feature = "timeconversions")]
+    #[test]
+    fn test_event_attributes_with_conversions() {
+        let ical = ICalendar::build(TEST_ENTRY).unwrap();
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.dtend().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Utc(NaiveDateTime::parse_from_str("20060919T215900Z", DATE_TIME_FMT).unwrap()));
+        assert_eq!(ev.dtstart().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Utc(NaiveDateTime::parse_from_str("20060910T220000Z", DATE_TIME_FMT).unwrap()));
+        assert_eq!(ev.dtstamp().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Utc(NaiveDateTime::parse_from_str("20060812T125900Z", DATE_TIME_FMT).unwrap()));
+    }
+
+    #[cfg(feature = "timeconversions")]
+    #[test]
+    fn test_event_attributes_oc_with_conversions() {
+        let ical = ICalendar::build(TEST_ENTRY_OC).unwrap();
+        assert_eq!(ical.version().unwrap().raw(), "2.0");
+        assert_eq!(ical.prodid().unwrap().raw(), "ownCloud Calendar");
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.dtend().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Date(NaiveDate::parse_from_str("20160326", DATE_FMT).unwrap()));
+        assert_eq!(ev.dtstart().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Date(NaiveDate::parse_from_str("20160325", DATE_FMT).unwrap()));
+        assert_eq!(ev.dtstamp().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Utc(NaiveDateTime::parse_from_str("20160128T223013Z", DATE_TIME_FMT).unwrap()));
+    }
+
+    #[test]
+    fn test_build_event() {
+        let mut ical = ICalendar::empty();
+        let mut builder = Event::build();
+
+        let desc = Description::new(String::from("test"), BTreeMap::new());
+        builder.set_description(desc, None);
+
+        let uid = Uid::new(String::from("testuid"), BTreeMap::new());
+        builder.set_uid(uid, None);
+
+        let summary = Summary::new(String::from("summary"), BTreeMap::new());
+        builder.set_summary(summary, None);
+
+        ical.add_event(builder);
+
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.uid().map(|e| e.raw().clone())         , Some("testuid".to_owned()));
+        assert_eq!(ev.description().map(|e| e.raw().clone()) , Some("test".to_owned()));
+        assert_eq!(ev.summary().map(|e| e.raw().clone())     , Some("summary".to_owned()));
+
+    }
+
+    #[test]
+    fn test_new_calendar() {
+        use component::write_component;
+
+        let ical = ICalendar::new("-//me//EN");
+        assert!(ical.validate().is_empty());
+        assert_eq!(write_component(&ical),
+                   "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//me//EN\r\nEND:VCALENDAR\r\n");
+
+        let mut ical = ical.with_method(Method::from_raw("PUBLISH".into()), None);
+        ical.set_calscale(Calscale::from_raw("GREGORIAN".into()), None);
+        ical.set_prodid(Prodid::from_raw("-//you//EN".into()), None);
+
+        let ical = ICalendar::build(&write_component(&ical)).unwrap();
+        assert_eq!(ical.method().unwrap().raw(), "PUBLISH");
+        assert_eq!(ical.calscale().unwrap().raw(), "GREGORIAN");
+        assert_eq!(ical.prodid().unwrap().raw(), "-//you//EN");
+        assert_eq!(ical.version().unwrap().raw(), "2.0");
+    }
+
+    #[test]
+    fn test_build_event_checked() {
+        let mut ical = ICalendar::empty();
+
+        let builder = Event::build()
+            .with_dtstamp(Dtstamp::from_raw("20060812T125900Z".into()), None)
+            .with_summary(Summary::from_raw("No UID".into()), None);
+        match ical.add_event_checked(builder.clone()) {
+            Err(VObjectError::InvalidICalendar(problems)) => {
+                assert_eq!(problems.len(), 1);
+                assert_eq!(problems[0].to_string(), "VEVENT: missing required property UID");
+            },
+            x => panic!("unexpected result: {:?}", x),
+        }
+        assert_eq!(ical.events().count(), 0);
+
+        let conflicting = builder.clone()
+            .with_uid(Uid::from_raw("checked".into()), None)
+            .with_dtend(Dtend::from_raw("20060919T215900Z".into()), None)
+            .with_duration(Duration::from_raw("PT1H".into()), None);
+        assert!(conflicting.finish().is_err());
+
+        let mut x_prop = Property::new("X-MY-PROP", "some value");
+        x_prop.params.insert("X-PARAM".into(), "1".into());
+        let builder = builder
+            .with_uid(Uid::from_raw("checked".into()), None)
+            .with_property(x_prop.clone());
+        ical.add_event_checked(builder).unwrap();
+
+        let reparsed = ICalendar::build(&::component::write_component(&ical)).unwrap();
+        let ev = reparsed.events().next().unwrap().unwrap();
+        assert_eq!(ev.0.get_only("X-MY-PROP"), Some(&x_prop));
+    }
+
+    #[test]
+    fn test_edit_events() {
+        use component::write_component;
+
+        let mut ical = ICalendar::empty()
+            .with_event(Event::build().with_uid(Uid::from_raw("first".into()), None))
+            .with_event(Event::build().with_uid(Uid::from_raw("second".into()), None));
+
+        for mut ev in ical.events_mut().filter_map(Result::ok) {
+            if ev.uid().map(|u| u.into_raw()) == Some("first".to_owned()) {
+                ev.set_summary(Summary::from_raw("Meeting".into()), None);
+                ev.set_location(Location::from_raw("Office".into()), None);
+            }
+        }
+
+        assert!(ical.remove_event("second").is_some());
+        assert!(ical.remove_event("second").is_none());
+
+        assert_eq!(write_component(&ical),
+            "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            LOCATION:Office\r\n\
+            SUMMARY:Meeting\r\n\
+            UID:first\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n");
+    }
+
+    #[test]
+    fn test_todo_roundtrip() {
+        use component::write_component;
+
+        let ical = ICalendar::empty()
+            .with_todo(Todo::build()
+                       .with_uid(Uid::from_raw("date".into()), None)
+                       .with_due(Due::from_raw("20160325".into()), Some(parameters!("VALUE" => "DATE"))))
+            .with_todo(Todo::build()
+                       .with_uid(Uid::from_raw("datetime".into()), None)
+                       .with_due(Due::from_raw("20160325T120000Z".into()), None)
+                       .with_status(Status::from_raw("NEEDS-ACTION".into()), None));
+
+        let ical = ICalendar::build(&write_component(&ical)).unwrap();
+        let mut todos = ical.todos().filter_map(Result::ok);
+
+        let todo = todos.next().unwrap();
+        assert_eq!(todo.uid().unwrap().raw(), "date");
+        assert_eq!(todo.due().unwrap().params().get("VALUE").unwrap(), "DATE");
+        assert_eq!(todo.due().unwrap().as_datetime().unwrap(),
+                   Time::Date(NaiveDate::from_ymd_opt(2016, 3, 25).unwrap()));
+
+        let todo = todos.next().unwrap();
+        assert_eq!(todo.uid().unwrap().raw(), "datetime");
+        assert_eq!(todo.status().unwrap().raw(), "NEEDS-ACTION");
+        assert_eq!(todo.due().unwrap().as_datetime().unwrap(),
+                   Time::Utc(NaiveDate::from_ymd_opt(2016, 3, 25).unwrap().and_hms_opt(12, 0, 0).unwrap()));
+
+        assert!(todos.next().is_none());
+        assert_eq!(ical.events().filter_map(Result::ok).count(), 0);
+    }
+
+    #[test]
+    fn test_journal_and_freebusy() {
+        let ical = ICalendar::build(
+            "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            BEGIN:VJOURNAL\n\
+            UID:journal@example.com\n\
+            SUMMARY:Staff meeting minutes\n\
+            END:VJOURNAL\n\
+            BEGIN:VFREEBUSY\n\
+            UID:freebusy@example.com\n\
+            ORGANIZER:mailto:jane_doe@example.com\n\
+            ATTENDEE:mailto:john_public@example.com\n\
+            FREEBUSY:19980314T233000Z/19980315T003000Z,19980316T153000Z/PT1H\n\
+            FREEBUSY;FBTYPE=BUSY-TENTATIVE:19980318T033000Z/19980318T043000Z\n\
+            END:VFREEBUSY\n\
+            END:VCALENDAR\n").unwrap();
+
+        let journal = ical.journals().filter_map(Result::ok).next().unwrap();
+        assert_eq!(journal.uid().unwrap().raw(), "journal@example.com");
+        assert_eq!(journal.summary().unwrap().raw(), "Staff meeting minutes");
+
+        let fb = ical.freebusys().filter_map(Result::ok).next().unwrap();
+        assert_eq!(fb.organizer().unwrap().raw(), "mailto:jane_doe@example.com");
+        assert_eq!(fb.attendee().len(), 1);
+        assert_eq!(fb.freebusy().len(), 2);
+        assert_eq!(fb.periods(), vec![
+            ("19980314T233000Z".to_owned(), "19980315T003000Z".to_owned()),
+            ("19980316T153000Z".to_owned(), "PT1H".to_owned()),
+            ("19980318T033000Z".to_owned(), "19980318T043000Z".to_owned()),
+        ]);
+
+        let built = ICalendar::empty()
+            .with_journal(Journal::build().with_uid(Uid::from_raw("j".into()), None))
+            .with_freebusy(FreeBusy::build().with_uid(Uid::from_raw("f".into()), None));
+        assert_eq!(built.journals().filter_map(Result::ok).count(), 1);
+        assert_eq!(built.freebusys().filter_map(Result::ok).count(), 1);
+    }
+
+    #[test]
+    fn test_alarms() {
+        use component::write_component;
+
+        let ical = ICalendar::empty()
+            .with_event(Event::build()
+                        .with_uid(Uid::from_raw("alarmed".into()), None)
+                        .with_alarm(Alarm::build()
+                                    .with_action(Action::from_raw("DISPLAY".into()), None)
+                                    .with_trigger(Trigger::from_raw("-PT15M".into()), None)
+                                    .with_description(Description::from_raw("Reminder".into()), None))
+                        .with_alarm(Alarm::build()
+                                    .with_action(Action::from_raw("AUDIO".into()), None)
+                                    .with_trigger(Trigger::from_raw("19980101T050000Z".into()),
+                                                  Some(parameters!("VALUE" => "DATE-TIME")))));
+
+        let written = write_component(&ical);
+        assert!(written.contains("UID:alarmed\r\nBEGIN:VALARM\r\n"));
+
+        let ical = ICalendar::build(&written).unwrap();
+        let ev = ical.events().next().unwrap().unwrap();
+        let alarms = ev.alarms().filter_map(Result::ok).collect::<Vec<_>>();
+        assert_eq!(alarms.len(), 2);
+
+        assert_eq!(alarms[0].action().unwrap().raw(), "DISPLAY");
+        assert_eq!(alarms[0].description().unwrap().raw(), "Reminder");
+        assert_eq!(alarms[0].trigger().unwrap().raw(), "-PT15M");
+        assert_eq!(alarms[0].trigger().unwrap().kind(), TriggerKind::Duration);
+        assert_eq!(alarms[0].trigger().unwrap().related(), "START");
+
+        assert_eq!(alarms[1].trigger().unwrap().kind(), TriggerKind::DateTime);
+    }
+
+    const TEST_ENTRY_TZ : &str =
+        "BEGIN:VCALENDAR\n\
+        VERSION:2.0\n\
+        PRODID:Microsoft Exchange Server 2010\n\
+        BEGIN:VTIMEZONE\n\
+        TZID:W. Europe Standard Time\n\
+        BEGIN:STANDARD\n\
+        DTSTART:16010101T030000\n\
+        TZOFFSETFROM:+0200\n\
+        TZOFFSETTO:+0100\n\
+        RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=10\n\
+        END:STANDARD\n\
+        BEGIN:DAYLIGHT\n\
+        DTSTART:16010101T020000\n\
+        TZOFFSETFROM:+0100\n\
+        TZOFFSETTO:+0200\n\
+        RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=3\n\
+        END:DAYLIGHT\n\
+        END:VTIMEZONE\n\
+        BEGIN:VEVENT\n\
+        UID:summer\n\
+        DTSTART;TZID=W. Europe Standard Time:20160701T100000\n\
+        DTEND;TZID=W. Europe Standard Time:20161201T100000\n\
+        END:VEVENT\n\
+        END:VCALENDAR\n";
+
+    #[test]
+    fn test_timezones() {
+        let ical = ICalendar::build(TEST_ENTRY_TZ).unwrap();
+        assert_eq!(ical.timezones().filter_map(Result::ok).count(), 1);
+        assert!(ical.get_timezone("Pacific Standard Time").is_none());
+
+        let tz = ical.get_timezone("W. Europe Standard Time").unwrap();
+        let kinds = tz.observances()
+            .filter_map(Result::ok)
+            .map(|o| (o.kind(), o.tzoffsetto().unwrap().into_raw()))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![(ObservanceKind::Standard, "+0100".to_owned()),
+                               (ObservanceKind::Daylight, "+0200".to_owned())]);
+
+        let ev = ical.events().filter_map(Result::ok).next().unwrap();
+        assert_eq!(ev.dtstart().unwrap().as_datetime_with_tz(&ical).unwrap(),
+                   Time::Utc(NaiveDate::from_ymd_opt(2016, 7, 1).unwrap().and_hms_opt(8, 0, 0).unwrap()));
+        assert_eq!(ev.dtend().unwrap().as_datetime_with_tz(&ical).unwrap(),
+                   Time::Utc(NaiveDate::from_ymd_opt(2016, 12, 1).unwrap().and_hms_opt(9, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_unknown_timezone() {
+        let ical = ICalendar::empty();
+        let dtstart = Dtstart::new("20160701T100000".into(), parameters!("TZID" => "Nowhere"));
+        assert!(dtstart.as_datetime_with_tz(&ical).is_err());
+
+        let dtstart = Dtstart::from_raw("20160701T100000Z".into());
+        assert_eq!(dtstart.as_datetime_with_tz(&ical).unwrap(),
+                   Time::Utc(NaiveDate::from_ymd_opt(2016, 7, 1).unwrap().and_hms_opt(10, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_as_datetime_forms() {
+        let date = Dtstart::new("20230405".into(), parameters!("VALUE" => "DATE"));
+        assert_eq!(date.as_datetime().unwrap(),
+                   Time::Date(NaiveDate::from_ymd_opt(2023, 4, 5).unwrap()));
+
+        let dt = NaiveDate::from_ymd_opt(2023, 4, 5).unwrap().and_hms_opt(9, 30, 0).unwrap();
+
+        let floating = Dtstart::from_raw("20230405T093000".into());
+        assert_eq!(floating.as_datetime().unwrap(), Time::Floating(dt));
+
+        let zulu = Dtstart::from_raw("20230405T093000Z".into());
+        assert_eq!(zulu.as_datetime().unwrap(), Time::Utc(dt));
+
+        let local = Dtstart::new("20230405T093000".into(), parameters!("TZID" => "Europe/Berlin"));
+        assert_eq!(local.as_datetime().unwrap(), Time::Local(dt, "Europe/Berlin".to_owned()));
+    }
+
+    #[test]
+    fn test_chrono_constructors() {
+        use chrono::{TimeZone, Utc};
+        use component::write_component;
+
+        let mut builder = Event::build();
+        builder.set_dtstart(Dtstart::from(NaiveDate::from_ymd_opt(2016, 3, 25).unwrap()), None);
+        builder.set_dtstamp(Dtstamp::from_datetime_utc(Utc.with_ymd_and_hms(2016, 1, 28, 22, 30, 13).unwrap()), None);
+        let dt = NaiveDate::from_ymd_opt(2016, 3, 26).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        builder.set_dtend(Dtend::from_naive_floating(dt), None);
+
+        let ical = ICalendar::empty().with_event(builder);
+        assert_eq!(write_component(&ical),
+            "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            DTEND:20160326T120000\r\n\
+            DTSTAMP:20160128T223013Z\r\n\
+            DTSTART;VALUE=DATE:20160325\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n");
+    }
+
+    #[test]
+    fn test_rrule_roundtrip() {
+        use rrule::Frequency;
+
+        let mut builder = Event::build();
+        let rrule = Rrule::from_raw("FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20250101T000000Z".into());
+        let mut rule = rrule.parse().unwrap();
+        assert_eq!(rule.freq, Frequency::Monthly);
+
+        rule.interval = Some(2);
+        builder.set_rrule(Rrule::from(rule), None);
+
+        let ical = ICalendar::empty().with_event(builder);
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.rrule().unwrap().raw(), "FREQ=MONTHLY;UNTIL=20250101T000000Z;INTERVAL=2;BYDAY=-1FR");
+    }
+
+    #[test]
+    fn test_duration() {
+        let parse = |s: &str| Duration::from_raw(s.into()).as_duration();
+        assert_eq!(parse("PT1H30M").unwrap(), chrono::Duration::minutes(90));
+        assert_eq!(parse("-PT15M").unwrap(), chrono::Duration::minutes(-15));
+        assert_eq!(parse("P2W").unwrap(), chrono::Duration::weeks(2));
+        assert_eq!(parse("P1DT2H3M4S").unwrap(), chrono::Duration::seconds(93784));
+        assert_eq!(parse("+P1D").unwrap(), chrono::Duration::days(1));
+        assert!(parse("PT").is_err());
+        assert!(parse("P1H").is_err());
+        assert!(parse("1D").is_err());
+        assert!(parse("P1").is_err());
+
+        let format = |d: chrono::Duration| Duration::from_chrono(d).into_raw();
+        assert_eq!(format(chrono::Duration::minutes(90)), "PT1H30M");
+        assert_eq!(format(chrono::Duration::minutes(-15)), "-PT15M");
+        assert_eq!(format(chrono::Duration::weeks(2)), "P2W");
+        assert_eq!(format(chrono::Duration::seconds(93784)), "P1DT2H3M4S");
+        assert_eq!(format(chrono::Duration::days(1)), "P1D");
+        assert_eq!(format(chrono::Duration::zero()), "PT0S");
+    }
+
+    #[test]
+    fn test_effective_end() {
+        let ical = ICalendar::empty()
+            .with_event(Event::build()
+                        .with_dtstart(Dtstart::from_raw("20160325T100000Z".into()), None)
+                        .with_duration(Duration::from_chrono(chrono::Duration::minutes(90)), None))
+            .with_event(Event::build()
+                        .with_dtstart(Dtstart::from_raw("20160325T100000Z".into()), None)
+                        .with_dtend(Dtend::from_raw("20160325T120000Z".into()), None))
+            .with_event(Event::build()
+                        .with_dtstart(Dtstart::from_raw("20160325T100000Z".into()), None));
+
+        let ends = ical.events()
+            .filter_map(Result::ok)
+            .map(|ev| ev.effective_end().unwrap())
+            .collect::<Vec<_>>();
+        let at = |h, m| Time::Utc(NaiveDate::from_ymd_opt(2016, 3, 25).unwrap().and_hms_opt(h, m, 0).unwrap());
+        assert_eq!(ends, vec![Some(at(11, 30)), Some(at(12, 0)), None]);
+
+        let overflow = Event::build()
+            .with_dtstart(Dtstart::from_raw("20160325T100000Z".into()), None)
+            .with_duration(Duration::from_raw("P15000000W".into()), None);
+        let ical = ICalendar::empty().with_event(overflow);
+        assert!(ical.events().next().unwrap().unwrap().effective_end().is_err());
+    }
+
+    #[test]
+    fn test_event_geo() {
+        let ical = ICalendar::empty()
+            .with_event(Event::build().with_geo(Geo::from_coords(37.386013, -122.082932), None));
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.geo().unwrap().raw(), "37.386013;-122.082932");
+        assert_eq!(ev.geo().unwrap().coordinates().unwrap(), (37.386013, -122.082932));
+    }
+
+    #[test]
+    fn test_ical_string_conversions() {
+        use std::convert::TryFrom;
+
+        let ical: ICalendar = TEST_ENTRY.parse().unwrap();
+        let reparsed: ICalendar = ical.to_string().parse().unwrap();
+        assert_eq!(*reparsed, *ical);
+        assert!("BEGIN:VCARD\r\nEND:VCARD\r\n".parse::<ICalendar>().is_err());
+
+        let component = Component::from(ical);
+        assert_eq!(component.name, "VCALENDAR");
+        assert!(ICalendar::try_from(component).is_ok());
+        assert_eq!(ICalendar::try_from(Component::new("VCARD")).unwrap_err().name, "VCARD");
+    }
+
+    #[test]
+    fn test_unescaped_value() {
+        let ical = ICalendar::build("BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            DESCRIPTION:First line\\nSecond line\\, with comma\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+        let description = ical.events().next().unwrap().unwrap().description().unwrap();
+
+        assert_eq!(description.raw(), "First line\\nSecond line\\, with comma");
+        assert_eq!(description.value(), "First line\nSecond line, with comma");
+        assert_eq!(description.into_value(), "First line\nSecond line, with comma");
+    }
+
+    #[test]
+    fn test_builder_escaping() {
+        use component::write_component;
+
+        let text = "semi;colon, comma\nnewline \\ backslash";
+        let ical = ICalendar::empty()
+            .with_event(Event::build()
+                        .with_description(Description::new_unescaped(text, parameters!()), None)
+                        .with_summary(Summary::new_unescaped(text, parameters!()), None)
+                        .with_location(Location::from_raw("already\\, escaped".into()), None)
+                        .with_geo(Geo::from_coords(1.5, 2.5), None));
+
+        let written = write_component(&ical);
+        assert!(written.contains("LOCATION:already\\, escaped\r\n"));
+        assert!(written.contains("GEO:1.5;2.5\r\n"));
+
+        let ical = ICalendar::build(&written).unwrap();
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.description().unwrap().value(), text);
+        assert_eq!(ev.summary().unwrap().value(), text);
+        assert_eq!(ev.location().unwrap().value(), "already, escaped");
+        assert_eq!(ev.geo().unwrap().coordinates().unwrap(), (1.5, 2.5));
+    }
+
+    #[test]
+    fn test_builder_raw_value() {
+        use component::write_component;
+
+        let raw = "semi\\;colon\\, comma\\nnewline \\\\ backslash";
+        let ical = ICalendar::empty()
+            .with_event(Event::build()
+                        .with_description(Description::from_raw(raw.into()), None));
+
+        let written = write_component(&ical);
+        assert!(written.contains(&format!("DESCRIPTION:{}\r\n", raw)));
+
+        let ical = ICalendar::build(&written).unwrap();
+        let ev = ical.events().next().unwrap().unwrap();
+        assert_eq!(ev.description().unwrap().raw(), raw);
+        assert_eq!(ev.description().unwrap().value(), "semi;colon, comma\nnewline \\ backslash");
+    }
+
+    #[test]
+    fn test_not_a_calendar() {
+        let input = "BEGIN:VCARD\nVERSION:4.0\nFN:Erika\nEND:VCARD\n";
+        match ICalendar::build(input) {
+            Err(VObjectError::NotAnICalendar { found, prefix }) => {
+                assert_eq!(found, "VCARD");
+                assert_eq!(prefix, input);
+            },
+            x => panic!("unexpected result: {:?}", x),
+        }
+    }
 
-    /// Chainable setter for "DTSTART" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+    #[test]
+    fn test_other_components() {
+        let input = "BEGIN:VCALENDAR\r\n\
+                     VERSION:2.0\r\n\
+                     PRODID:-//test//EN\r\n\
+                     BEGIN:VEVENT\r\n\
+                     UID:event\r\n\
+                     END:VEVENT\r\n\
+                     BEGIN:VCARD\r\n\
+                     VERSION:4.0\r\n\
+                     FN:Organizer\r\n\
+                     END:VCARD\r\n\
+                     BEGIN:X-SERVER-INFO\r\n\
+                     X-QUOTA:100\r\n\
+                     END:X-SERVER-INFO\r\n\
+                     END:VCALENDAR\r\n";
 
-    /// Chainable setter for "DTSTAMP" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
+        let mut ical = ICalendar::build(input).unwrap();
+        let others = ical.other_components().map(|c| &c.name[..]).collect::<Vec<_>>();
+        assert_eq!(others, vec!["VCARD", "X-SERVER-INFO"]);
+        assert_eq!(ical.to_string(), input);
 
-    /// Chainable setter for "UID" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_uid, "UID", Uid, Uid::into_raw);
+        let mut extra = Component::new("X-EXTRA");
+        extra.push(Property::new("X-FOO", "bar"));
+        ical.add_component(extra);
+        let reparsed = ICalendar::build(&ical.to_string()).unwrap();
+        assert_eq!(reparsed.other_components().count(), 3);
+        assert_eq!(reparsed.other_components().last().unwrap().get_only("X-FOO").unwrap().raw_value, "bar");
+        assert_eq!(reparsed.events().filter_map(Result::ok).count(), 1);
+    }
 
-    /// Chainable setter for "DESCRIPTION" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_description, "DESCRIPTION", Description, Description::into_raw);
+    #[test]
+    fn test_event_languages() {
+        let cal = ICalendar::build("BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:1\r\n\
+            SUMMARY:Meeting\r\n\
+            SUMMARY;LANGUAGE=de:Besprechung\r\n\
+            DESCRIPTION;LANGUAGE=fr-CA:Réunion\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n").unwrap();
+        let event = cal.events().next().unwrap().unwrap();
 
-    /// Chainable setter for "SUMMARY" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_summary, "SUMMARY", Summary, Summary::into_raw);
+        assert!(event.summary().is_none());
+        assert_eq!(event.summary_for_lang("de").unwrap().value(), "Besprechung");
+        assert_eq!(event.summary_for_lang("en").unwrap().value(), "Meeting");
+        assert_eq!(event.description_for_lang("fr").unwrap().language(), Some("fr-CA"));
+        assert!(event.description_for_lang("en").is_none());
+    }
 
-    /// Chainable setter for "URL" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_url, "URL", Url, Url::into_raw);
+    #[test]
+    fn test_event_timestamps() {
+        use chrono::{TimeZone, Utc};
 
-    /// Chainable setter for "LOCATION" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_location, "LOCATION", Location, Location::into_raw);
+        let mut cal = ICalendar::build("BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:1\r\n\
+            DTSTAMP:20240110T120000Z\r\n\
+            CREATED:20240101T080000Z\r\n\
+            LAST-MODIFIED:2024-01-10T12:00:00Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n").unwrap();
+        {
+            let event = cal.events().next().unwrap().unwrap();
+            assert_eq!(event.created().unwrap().as_datetime().unwrap(),
+                       Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap());
+            assert_eq!(event.last_modified().unwrap().as_datetime().unwrap(),
+                       Utc.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap());
+        }
 
-    /// Chainable setter for "CLASS" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_class, "CLASS", Class, Class::into_raw);
+        let before = Utc::now() - chrono::Duration::seconds(1);
+        cal.events_mut().next().unwrap().unwrap().touch();
+        let event = cal.events().next().unwrap().unwrap();
+        let last_modified = event.last_modified().unwrap();
+        assert!(last_modified.as_datetime().unwrap() >= before);
+        assert_eq!(event.dtstamp().unwrap().raw(), last_modified.raw());
+        assert!(NaiveDateTime::parse_from_str(last_modified.raw(), DATE_TIME_FMT).is_ok());
 
-    /// Chainable setter for "CATEGORIES" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_categories, "CATEGORIES", Categories, Categories::into_raw);
+        let event = Event::build().touch_dtstamp().touch_dtstamp();
+        assert_eq!(event.0.get_all("DTSTAMP").len(), 1);
+        assert!(event.0.get_only("DTSTAMP").unwrap().raw_value.ends_with('Z'));
+    }
 
-    /// Chainable setter for "TRANSP" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_transp, "TRANSP", Transp, Transp::into_raw);
+    #[test]
+    fn test_event_attachments() {
+        let cal = ICalendar::build("BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:1\r\n\
+            ATTACH:https://tickets.example.com/42/log.txt\r\n\
+            ATTACH;VALUE=BINARY;ENCODING=BASE64;FMTTYPE=application/pdf:JVBERi0x\r\n \
+            LjQK\r\n\
+            ATTACH;ENCODING=BASE64:not base64!\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n").unwrap();
+        let event = cal.events().next().unwrap().unwrap();
+        let attachments = event.attachments().iter().map(Attach::kind).collect::<Vec<_>>();
 
-    /// Chainable setter for "RRULE" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_rrule, "RRULE", Rrule, Rrule::into_raw);
+        match attachments[0] {
+            AttachKind::Uri(ref uri) => assert_eq!(uri, "https://tickets.example.com/42/log.txt"),
+            ref other => panic!("{:?}", other),
+        }
+        match attachments[1] {
+            AttachKind::Binary { ref mime, data: Ok(ref data) } => {
+                assert_eq!(mime.as_deref(), Some("application/pdf"));
+                assert_eq!(data, b"%PDF-1.4\n");
+            },
+            ref other => panic!("{:?}", other),
+        }
+        assert!(matches!(attachments[2], AttachKind::Binary { mime: None, data: Err(_) }));
 
-}
+        let bytes = (0..5000u32).map(|i| (i * 7 % 256) as u8).collect::<Vec<_>>();
+        let mut cal = ICalendar::empty();
+        cal.add_event(Event::build()
+            .with_attach_uri("https://example.com/a.pdf")
+            .with_attach_binary(&bytes, "application/octet-stream"));
+        let written = cal.to_string();
+        assert!(written.lines().all(|l| l.len() <= 75));
 
-#[cfg(all(test, feature = "timeconversions"))]
-mod tests {
-    use chrono::NaiveDate;
-    use chrono::NaiveDateTime;
-    use util::*;
-    use super::ICalendar;
+        let cal = ICalendar::build(&written).unwrap();
+        let event = cal.events().next().unwrap().unwrap();
+        let attachments = event.attachments();
+        assert!(matches!(attachments[0].kind(), AttachKind::Uri(_)));
+        match attachments[1].kind() {
+            AttachKind::Binary { mime, data } => {
+                assert_eq!(mime.as_deref(), Some("application/octet-stream"));
+                assert_eq!(data.unwrap(), bytes);
+            },
+            other => panic!("{:?}", other),
+        }
+    }
 
-    use super::*;
+    #[test]
+    fn test_events_in_range() {
+        let cal = ICalendar::build("BEGIN:VCALENDAR\r\n\
+            BEGIN:VTIMEZONE\r\n\
+            TZID:Europe/Berlin\r\n\
+            BEGIN:STANDARD\r\n\
+            DTSTART:19701025T030000\r\n\
+            TZOFFSETFROM:+0200\r\n\
+            TZOFFSETTO:+0100\r\n\
+            END:STANDARD\r\n\
+            END:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\nUID:late\r\nDTSTART:20240115T160000Z\r\nDTEND:20240115T170000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:b-allday\r\nDTSTART;VALUE=DATE:20240115\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:a-allday\r\nDTSTART;VALUE=DATE:20240115\r\nDTEND;VALUE=DATE:20240116\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:berlin\r\nDTSTART;TZID=Europe/Berlin:20240115T100000\r\nDURATION:PT2H\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:reminder\r\nDTSTART:20240115T120000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:yesterday\r\nDTSTART:20240114T100000Z\r\nDTEND:20240114T110000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:nostart\r\nSUMMARY:x\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:overflow\r\nDTSTART:20240115T100000Z\r\nDURATION:P15000000W\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n").unwrap();
 
-    const TEST_ENTRY : &'static str =
-            "BEGIN:VCALENDAR\n\
+        let uids = |events: Vec<Event>| {
+            events.iter().map(|ev| ev.uid().map(Uid::into_raw).unwrap_or_default()).collect::<Vec<_>>()
+        };
+        let at = |h, m| NaiveDate::from_ymd_opt(2024, 1, 15).unwrap().and_hms_opt(h, m, 0).unwrap();
+
+        assert_eq!(uids(cal.events_sorted()),
+                   vec!["yesterday", "a-allday", "b-allday", "berlin", "reminder", "late", "nostart", "overflow"]);
+
+        // The Berlin event is 09:00 to 11:00 UTC.
+        assert_eq!(uids(cal.events_in_range(at(10, 30), at(12, 0))), vec!["a-allday", "b-allday", "berlin"]);
+        assert_eq!(uids(cal.events_in_range(at(11, 0), at(12, 1))), vec!["a-allday", "b-allday", "reminder"]);
+        assert_eq!(uids(cal.events_in_range(at(17, 0), at(23, 0))), vec!["a-allday", "b-allday"]);
+        assert!(cal.events_in_range(at(0, 0) + chrono::Duration::days(1), at(12, 0) + chrono::Duration::days(1)).is_empty());
+        assert_eq!(uids(cal.events_in_range(at(10, 59) - chrono::Duration::days(1), at(0, 0))), vec!["yesterday"]);
+    }
+
+    #[test]
+    fn test_calendar_properties() {
+        let cal = ICalendar::empty()
+            .with_version(Version::from_raw("2.0".into()), None)
+            .with_default_prodid()
+            .with_property(Property::new("X-WR-CALNAME", "Team"))
+            .with_event(Event::build()
+                .with_uid(Uid::from_raw("1".into()), None)
+                .with_dtstamp(Dtstamp::from_raw("20240110T120000Z".into()), None)
+                .with_property(Property::new("X-APPLE-TRAVEL-ADVISORY-BEHAVIOR", "AUTOMATIC")));
+        assert!(cal.validate().is_empty());
+        assert!(cal.prodid().unwrap().raw().starts_with("-//rust-vobject//vobject "));
+
+        let cal = ICalendar::build(&cal.to_string()).unwrap();
+        assert_eq!(cal.get_only("X-WR-CALNAME").unwrap().raw_value, "Team");
+        let event = &cal.subcomponents[0];
+        assert!(event.get_only("X-WR-CALNAME").is_none());
+        assert!(event.get_only("X-APPLE-TRAVEL-ADVISORY-BEHAVIOR").is_some());
+
+        let cal = ICalendar::new("-//Example//EN").with_default_prodid();
+        assert_eq!(cal.prodid().unwrap().raw(), "-//Example//EN");
+    }
+
+    #[test]
+    fn test_todo_completion() {
+        let mut cal = ICalendar::build("BEGIN:VCALENDAR\n\
             VERSION:2.0\n\
-            PRODID:http://www.example.com/calendarapplication/\n\
-            METHOD:PUBLISH\n\
+            PRODID:-//test//EN\n\
+            BEGIN:VTODO\n\
+            UID:todo\n\
+            STATUS:IN-PROCESS\n\
+            PERCENT-COMPLETE:50\n\
+            END:VTODO\n\
+            BEGIN:VTODO\n\
+            UID:broken\n\
+            PERCENT-COMPLETE:half\n\
+            END:VTODO\n\
+            BEGIN:VTODO\n\
+            UID:overachiever\n\
+            PERCENT-COMPLETE:150\n\
+            COMPLETED:20240110T120000Z\n\
+            END:VTODO\n\
+            END:VCALENDAR\n").unwrap();
+
+        {
+            let todos = cal.todos().collect::<Result<Vec<_>, _>>().unwrap();
+            assert!(!todos[0].is_completed());
+            assert_eq!(todos[0].percent().unwrap(), Some(50));
+            assert!(todos[1].percent().is_err());
+            assert!(todos[2].is_completed());
+            assert_eq!(todos[2].percent().unwrap(), Some(100));
+        }
+
+        let at = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap().and_hms_opt(9, 30, 0).unwrap();
+        let mut todo = cal.todos_mut().next().unwrap().unwrap();
+        todo.mark_completed(Some(at));
+        assert!(todo.is_completed());
+        assert_eq!(todo.status().unwrap().raw(), "COMPLETED");
+        assert_eq!(todo.completed().unwrap().raw(), "20240115T093000Z");
+        assert_eq!(todo.percent().unwrap(), Some(100));
+
+        todo.reopen();
+        assert!(!todo.is_completed());
+        assert_eq!(todo.status().unwrap().raw(), "NEEDS-ACTION");
+        assert!(todo.completed().is_none());
+        assert_eq!(todo.percent().unwrap(), None);
+
+        let mut builder = Todo::build();
+        builder.mark_completed(None);
+        let cal = ICalendar::empty().with_todo(builder);
+        let todo = cal.todos().next().unwrap().unwrap();
+        assert!(todo.is_completed());
+        assert!(todo.completed().is_none());
+    }
+
+    #[test]
+    fn test_merge() {
+        let feed_a = "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:-//a//EN\n\
+            X-WR-CALNAME:A\n\
+            BEGIN:VTIMEZONE\n\
+            TZID:Europe/Vienna\n\
+            END:VTIMEZONE\n\
             BEGIN:VEVENT\n\
-            UID:461092315540@example.com\n\
-            ORGANIZER;CN=\"Alice Balder, Example Inc.\":MAILTO:alice@example.com\n\
-            LOCATION:Somewhere\n\
-            SUMMARY:Eine Kurzinfo\n\
-            DESCRIPTION:Beschreibung des Termines\n\
-            CLASS:PUBLIC\n\
-            DTSTART:20060910T220000Z\n\
-            DTEND:20060919T215900Z\n\
-            DTSTAMP:20060812T125900Z\n\
+            UID:shared\n\
+            DTSTAMP:20240101T000000Z\n\
+            LAST-MODIFIED:20240105T000000Z\n\
+            SUMMARY:From A\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:only-a\n\
+            DTSTAMP:20240101T000000Z\n\
+            SUMMARY:Only A\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n";
+        let feed_b = "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:-//b//EN\n\
+            X-WR-CALNAME:B\n\
+            BEGIN:VTIMEZONE\n\
+            TZID:Europe/Vienna\n\
+            END:VTIMEZONE\n\
+            BEGIN:VTIMEZONE\n\
+            TZID:Europe/Berlin\n\
+            END:VTIMEZONE\n\
+            BEGIN:VEVENT\n\
+            UID:shared\n\
+            DTSTAMP:20240110T000000Z\n\
+            SUMMARY:From B\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:shared\n\
+            DTSTAMP:20240110T000000Z\n\
+            RECURRENCE-ID:20240201T100000Z\n\
+            SUMMARY:From B, moved\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:only-b\n\
+            DTSTAMP:20240101T000000Z\n\
+            SUMMARY:Only B\n\
             END:VEVENT\n\
             END:VCALENDAR\n";
 
-    const TEST_ENTRY_OC : &'static str = // Lets see how owncloud foo works here
-        "BEGIN:VCALENDAR\n\
-        VERSION:2.0\n\
-        PRODID:ownCloud Calendar\n\
-        CALSCALE:GREGORIAN\n\
-        BEGIN:VEVENT\n\
-        UID:ff411055a5\n\
-        DTSTAMP:20160128T223013Z\n\
-        CREATED:20160128T223013Z\n\
-        LAST-MODIFIED:20160128T223013Z\n\
-        SUMMARY:Amon Amarth - Jomsviking\n\
-        DTSTART;VALUE=DATE:20160325\n\
-        DTEND;VALUE=DATE:20160326\n\
-        LOCATION:\n\
-        DESCRIPTION:\n\
-        CATEGORIES:\n\
-        END:VEVENT\n\
-        END:VCALENDAR\n\
-        ";
+        let merged = |policy| {
+            let mut cal = ICalendar::build(feed_a).unwrap();
+            cal.merge(ICalendar::build(feed_b).unwrap(), policy);
+            assert_eq!(cal.timezones().filter_map(Result::ok).count(), 2);
+            assert_eq!(cal.get_only("PRODID").unwrap().raw_value, "-//a//EN");
+            assert_eq!(cal.get_only("X-WR-CALNAME").unwrap().raw_value, "A");
+            cal
+        };
+        let summaries = |cal: &ICalendar, uid: &str| {
+            cal.events()
+                .filter_map(Result::ok)
+                .filter(|ev| ev.uid().unwrap().raw() == uid)
+                .map(|ev| ev.summary().unwrap().raw().clone())
+                .collect::<Vec<_>>()
+        };
 
-    #[test]
-    fn test_parse() {
-        let cal = ICalendar::build(TEST_ENTRY);
-        assert!(cal.is_ok(), "Not okay: {:?}\n in '{}'", cal, TEST_ENTRY);
+        let cal = merged(MergePolicy::KeepSelf);
+        assert_eq!(cal.events().filter_map(Result::ok).count(), 3);
+        assert_eq!(summaries(&cal, "shared"), vec!["From A"]);
+
+        let cal = merged(MergePolicy::KeepOther);
+        assert_eq!(cal.events().filter_map(Result::ok).count(), 4);
+        assert_eq!(summaries(&cal, "shared"), vec!["From B", "From B, moved"]);
+
+        // A's LAST-MODIFIED is older than B's DTSTAMP
+        let cal = merged(MergePolicy::KeepNewer);
+        assert_eq!(cal.events().filter_map(Result::ok).count(), 4);
+        assert_eq!(summaries(&cal, "shared"), vec!["From B", "From B, moved"]);
+
+        let cal = merged(MergePolicy::KeepBoth);
+        assert_eq!(cal.events().filter_map(Result::ok).count(), 5);
+        assert_eq!(summaries(&cal, "shared"), vec!["From A"]);
+        assert_eq!(summaries(&cal, "shared-1"), vec!["From B", "From B, moved"]);
+        assert_eq!(summaries(&cal, "only-b"), vec!["Only B"]);
+
+        let mut newer_self = ICalendar::build(&feed_a.replace("20240105", "20240120")).unwrap();
+        newer_self.merge(ICalendar::build(feed_b).unwrap(), MergePolicy::KeepNewer);
+        assert_eq!(summaries(&newer_self, "shared"), vec!["From A"]);
+
+        let extended = feed_a.replace("LAST-MODIFIED:20240105T000000Z", "LAST-MODIFIED:2024-01-20T00:00:00Z");
+        let mut newer_self = ICalendar::build(&extended).unwrap();
+        newer_self.merge(ICalendar::build(feed_b).unwrap(), MergePolicy::KeepNewer);
+        assert_eq!(summaries(&newer_self, "shared"), vec!["From A"]);
     }
 
     #[test]
-    fn test_iter() {
-        let ical = ICalendar::build(TEST_ENTRY).unwrap();
-        assert_eq!(ical.events().count(), 1);
+    fn test_busy_periods() {
+        let cal = ICalendar::build("BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:-//test//EN\n\
+            BEGIN:VFREEBUSY\n\
+            UID:fb\n\
+            FREEBUSY:19980314T233000Z/PT1H,19980315T003000Z/19980315T010000Z\n\
+            FREEBUSY;FBTYPE=BUSY:19980314T230000Z/PT45M\n\
+            FREEBUSY;FBTYPE=FREE:19980315T020000Z/PT1H\n\
+            FREEBUSY;FBTYPE=busy:19980315T030000Z/PT30M\n\
+            FREEBUSY:garbage\n\
+            FREEBUSY:19980314T233000Z/P15000000W\n\
+            END:VFREEBUSY\n\
+            END:VCALENDAR\n").unwrap();
+
+        let periods = cal.busy_periods();
+        let raw = periods.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(raw, vec![
+            "19980314T230000Z/19980315T010000Z",
+            "19980315T030000Z/19980315T033000Z",
+        ]);
+
+        let dt = |s| NaiveDateTime::parse_from_str(s, DATE_TIME_FMT).unwrap();
+        let free = Period::new(dt("19980315T013000Z"), dt("19980315T023000Z"));
+        let touching = Period::new(dt("19980315T010000Z"), dt("19980315T020000Z"));
+        assert!(!periods.iter().any(|p| p.overlaps(&free).unwrap()));
+        assert!(!periods[0].overlaps(&touching).unwrap());
+        assert!(periods[1].overlaps(&Period::from_raw("19980315T031500Z/PT1H").unwrap()).unwrap());
+
+        let period = Period::from_raw("19980314T233000Z/PT1H").unwrap();
+        assert!(period.has_duration());
+        assert_eq!(period.raw_start(), "19980314T233000Z");
+        assert_eq!(period.raw_end(), "PT1H");
+        assert_eq!(period.end().unwrap(), dt("19980315T003000Z"));
+        assert!(Period::from_raw("19980314T233000Z").is_err());
+        assert!(Period::from_raw("19980314T233000Z/").is_err());
+        assert!(Period::from_raw("19980314T233000Z/P15000000W").unwrap().end().is_err());
     }
 
     #[test]
-    fn test_icalendar_attributes() {
-        let ical = ICalendar::build(TEST_ENTRY).unwrap();
-        assert_eq!(ical.version().unwrap().raw(), "2.0");
-        assert_eq!(ical.prodid().unwrap().raw(), "http://www.example.com/calendarapplication/");
+    fn test_search_events() {
+        let cal = ICalendar::build("BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:-//test//EN\n\
+            BEGIN:VEVENT\n\
+            UID:qa\n\
+            SUMMARY:Q\\,A session\n\
+            DESCRIPTION:Bring questions\\; answers are optional\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:standup\n\
+            SUMMARY:Daily Standup\n\
+            LOCATION:Café Müller\n\
+            ATTACH;ENCODING=BASE64;VALUE=BINARY:c3RhbmR1cCBjYWZl\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+
+        let uids = |events: Vec<Event>| events.iter().map(|ev| ev.uid().unwrap().raw().clone()).collect::<Vec<_>>();
+        let defaults = SearchOptions::default();
+
+        assert_eq!(uids(cal.search_events("Q,A", defaults)), vec!["qa"]);
+        assert_eq!(uids(cal.search_events("questions; answers", defaults)), vec!["qa"]);
+        assert!(cal.search_events("Q\\,A", defaults).is_empty());
+        assert_eq!(uids(cal.search_events("STANDUP", defaults)), vec!["standup"]);
+        // Binary attachments are not searched
+        assert!(cal.search_events("c3RhbmR1", defaults).is_empty());
+
+        assert_eq!(uids(cal.search_events("café", defaults)), vec!["standup"]);
+        assert!(cal.search_events("cafe muller", defaults).is_empty());
+        let folding = SearchOptions { fold_diacritics: true, ..defaults };
+        assert_eq!(uids(cal.search_events("cafe muller", folding)), vec!["standup"]);
+        assert_eq!(uids(cal.search_events("CAFÉ", folding)), vec!["standup"]);
+        assert_eq!(uids(cal.search_events("cafe\u{301}", folding)), vec!["standup"]);
+
+        let no_location = SearchOptions { location: false, ..folding };
+        assert!(cal.search_events("cafe", no_location).is_empty());
+        let only_description = SearchOptions { summary: false, location: false, ..defaults };
+        assert!(cal.search_events("Q,A", only_description).is_empty());
+        assert_eq!(uids(cal.search_events("optional", only_description)), vec!["qa"]);
     }
 
     #[test]
-    fn test_event_attributes() {
-        let ical = ICalendar::build(TEST_ENTRY).unwrap();
-        let ev = ical.events().next().unwrap().unwrap();
-        assert_eq!(ev.dtend().map(|e| e.raw().clone())       , Some("20060919T215900Z".to_owned()));
-        assert_eq!(ev.dtstart().map(|e| e.raw().clone())     , Some("20060910T220000Z".to_owned()));
-        assert_eq!(ev.dtstamp().map(|e| e.raw().clone())     , Some("20060812T125900Z".to_owned()));
-        assert_eq!(ev.uid().map(|e| e.raw().clone())         , Some("461092315540@example.com".to_owned()));
-        assert_eq!(ev.description().map(|e| e.raw().clone()) , Some("Beschreibung des Termines".to_owned()));
-        assert_eq!(ev.summary().map(|e| e.raw().clone())     , Some("Eine Kurzinfo".to_owned()));
-        assert_eq!(ev.url()                                  , None);
-        assert_eq!(ev.location().map(|e| e.raw().clone())    , Some("Somewhere".to_owned()));
-        assert_eq!(ev.class().map(|e| e.raw().clone())       , Some("PUBLIC".to_owned()));
-        assert_eq!(ev.categories()                           , None);
-        assert_eq!(ev.transp()                               , None);
-        assert_eq!(ev.rrule()                                , None);
+    fn test_uid_normalization() {
+        let feed = |uid: &str, summary: &str| ICalendar::build(&format!("BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:-//test//EN\n\
+            BEGIN:VEVENT\n\
+            UID:{}\n\
+            SUMMARY:{}\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n", uid, summary)).unwrap();
+
+        let mut cal = feed("urn:uuid:abc-123", "Mine");
+        assert!(cal.get_event_by_uid("URN:UUID:ABC-123").is_some());
+        assert!(cal.get_event_by_uid("abc-123").is_some());
+        assert!(cal.recurrence_set("ABC-123").is_some());
+
+        cal.merge(feed("URN:UUID:ABC-123", "Theirs"), MergePolicy::KeepSelf);
+        assert_eq!(cal.events().count(), 1);
+        cal.merge(feed("URN:UUID:ABC-123", "Theirs"), MergePolicy::KeepBoth);
+        let uids = cal.events().map(|ev| ev.unwrap().uid().unwrap().raw().clone()).collect::<Vec<_>>();
+        assert_eq!(uids, vec!["urn:uuid:abc-123", "URN:UUID:ABC-123-1"]);
+
+        let indexed = IndexedCalendar::new(feed("URN:UUID:ABC-123", "Indexed"));
+        assert_eq!(indexed.events_by_uid("urn:uuid:abc-123").len(), 1);
+        assert_eq!(indexed.events_by_uid("abc-123").len(), 1);
+
+        let mut cal = feed("ABC-123", "Removed");
+        assert!(cal.remove_event("urn:uuid:abc-123").is_some());
     }
 
     #[test]
-    fn test_event_attributes_oc() {
-        let ical = ICalendar::build(TEST_ENTRY_OC).unwrap();
-        assert_eq!(ical.version().unwrap().raw(), "2.0");
-        assert_eq!(ical.prodid().unwrap().raw(), "ownCloud Calendar");
-        let ev = ical.events().next().unwrap().unwrap();
-        assert_eq!(ev.dtend().map(|e| e.raw().clone())       , Some("20160326".to_owned()));
-        assert_eq!(ev.dtstart().map(|e| e.raw().clone())     , Some("20160325".to_owned()));
-        assert_eq!(ev.dtstamp().map(|e| e.raw().clone())     , Some("20160128T223013Z".to_owned()));
-        assert_eq!(ev.uid().map(|e| e.raw().clone())         , Some("ff411055a5".to_owned()));
-        assert_eq!(ev.description().map(|e| e.raw().clone()) , Some("".to_owned()));
-        assert_eq!(ev.summary().map(|e| e.raw().clone())     , Some("Amon Amarth - Jomsviking".to_owned()));
-        assert_eq!(ev.url()                                  , None);
-        assert_eq!(ev.location().map(|e| e.raw().clone())    , Some("".to_owned()));
-        assert_eq!(ev.class().map(|e| e.raw().clone())       , None);
-        assert_eq!(ev.categories().map(|e| e.raw().clone())  , Some("".to_owned()));
-        assert_eq!(ev.transp()                               , None);
-        assert_eq!(ev.rrule()                                , None);
+    fn test_tzoffset_strict() {
+        let offset = |raw: &str| TzOffsetTo::new(String::from(raw), Parameters::new()).utc_offset();
+        assert_eq!(offset("-0500"), Some(-5 * 3600));
+        assert_eq!(offset("+053000"), Some(5 * 3600 + 1800));
+        assert_eq!(offset("+05:30"), None);
+        assert_eq!(offset("-02"), None);
     }
 
-    #[cfg(feature = "timeconversions")]
     #[test]
-    fn test_event_attributes_with_conversions() {
-        let ical = ICalendar::build(TEST_ENTRY).unwrap();
-        let ev = ical.events().next().unwrap().unwrap();
-        assert_eq!(ev.dtend().map(|e| e.as_datetime().unwrap()).unwrap(), Time::DateTime(NaiveDateTime::parse_from_str("20060919T215900Z", DATE_TIME_FMT).unwrap()));
-        assert_eq!(ev.dtstart().map(|e| e.as_datetime().unwrap()).unwrap(), Time::DateTime(NaiveDateTime::parse_from_str("20060910T220000Z", DATE_TIME_FMT).unwrap()));
-        assert_eq!(ev.dtstamp().map(|e| e.as_datetime().unwrap()).unwrap(), Time::DateTime(NaiveDateTime::parse_from_str("20060812T125900Z", DATE_TIME_FMT).unwrap()));
+    fn test_uid_lookup() {
+        let ical = ICalendar::build("BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            UID:standup\n\
+            RECURRENCE-ID:20240102T090000Z\n\
+            SUMMARY:Moved standup\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:standup\n\
+            RRULE:FREQ=DAILY\n\
+            SUMMARY:Standup\n\
+            END:VEVENT\n\
+            BEGIN:VTODO\n\
+            UID:URN:UUID:Todo\n\
+            END:VTODO\n\
+            BEGIN:VEVENT\n\
+            UID:standup\n\
+            RECURRENCE-ID:20240103T090000Z\n\
+            STATUS:CANCELLED\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+
+        assert_eq!(ical.get_event_by_uid("standup").unwrap().summary().unwrap().raw(), "Standup");
+        assert!(ical.get_event_by_uid("todo").is_none());
+        assert!(ical.get_event_by_uid("missing").is_none());
+
+        let index = ical.build_uid_index();
+        assert_eq!(index["standup"], vec![0, 1, 3]);
+        assert_eq!(index["todo"], vec![2]);
+        assert!(!index.contains_key("URN:UUID:Todo"));
+
+        let mut indexed = IndexedCalendar::new(ical);
+        assert_eq!(indexed.events_by_uid("standup").len(), 3);
+        assert!(indexed.events_by_uid("todo").is_empty());
+
+        indexed.add_event(Event::build().with_uid(Uid::from_raw("lunch".into()), None));
+        assert_eq!(indexed.events_by_uid("lunch").len(), 1);
+
+        assert_eq!(indexed.remove_event("standup").len(), 3);
+        assert!(indexed.events_by_uid("standup").is_empty());
+        assert_eq!(indexed.events_by_uid("lunch")[0].uid().unwrap().raw(), "lunch");
+        assert_eq!(indexed.build_uid_index()["todo"], vec![0]);
+        assert!(indexed.remove_event("standup").is_empty());
+        assert_eq!(indexed.into_inner().subcomponents.len(), 2);
     }
 
-    #[cfg(feature = "timeconversions")]
     #[test]
-    fn test_event_attributes_oc_with_conversions() {
-        let ical = ICalendar::build(TEST_ENTRY_OC).unwrap();
-        assert_eq!(ical.version().unwrap().raw(), "2.0");
-        assert_eq!(ical.prodid().unwrap().raw(), "ownCloud Calendar");
-        let ev = ical.events().next().unwrap().unwrap();
-        assert_eq!(ev.dtend().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Date(NaiveDate::parse_from_str("20160326", DATE_FMT).unwrap()));
-        assert_eq!(ev.dtstart().map(|e| e.as_datetime().unwrap()).unwrap(), Time::Date(NaiveDate::parse_from_str("20160325", DATE_FMT).unwrap()));
-        assert_eq!(ev.dtstamp().map(|e| e.as_datetime().unwrap()).unwrap(), Time::DateTime(NaiveDateTime::parse_from_str("20160128T223013Z", DATE_TIME_FMT).unwrap()));
+    fn test_recurrence_set() {
+        let ical = ICalendar::build("BEGIN:VCALENDAR\n\
+            PRODID:-//Google Inc//Google Calendar 70.9054//EN\n\
+            VERSION:2.0\n\
+            BEGIN:VEVENT\n\
+            DTSTART;TZID=Europe/Berlin:20240108T100000\n\
+            DTEND;TZID=Europe/Berlin:20240108T103000\n\
+            RRULE:FREQ=WEEKLY;BYDAY=MO\n\
+            EXDATE;TZID=Europe/Berlin:20240115T100000,20240122T100000\n\
+            EXDATE;TZID=Europe/Berlin:20240205T100000\n\
+            UID:5h1s2v0b8i4kq3q6o1l0c3m9t7@google.com\n\
+            SUMMARY:Weekly sync\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            DTSTART;TZID=Europe/Berlin:20240130T140000\n\
+            DTEND;TZID=Europe/Berlin:20240130T143000\n\
+            UID:5h1s2v0b8i4kq3q6o1l0c3m9t7@google.com\n\
+            RECURRENCE-ID;TZID=Europe/Berlin:20240129T100000\n\
+            SUMMARY:Weekly sync (moved)\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+
+        let set = ical.recurrence_set("5h1s2v0b8i4kq3q6o1l0c3m9t7@google.com").unwrap();
+        let master = set.master.unwrap();
+        assert_eq!(master.summary().unwrap().raw(), "Weekly sync");
+        assert!(master.recurrence_id().is_none());
+
+        let berlin = |month: u32, day: u32, hour: u32| {
+            Time::Local(NaiveDate::from_ymd_opt(2024, month, day).unwrap().and_hms_opt(hour, 0, 0).unwrap(),
+                        "Europe/Berlin".to_owned())
+        };
+        assert_eq!(master.exdate().len(), 2);
+        assert_eq!(master.exdates().unwrap(), vec![berlin(1, 15, 10), berlin(1, 22, 10), berlin(2, 5, 10)]);
+
+        assert_eq!(set.overrides.len(), 1);
+        let moved = &set.overrides[0];
+        assert_eq!(moved.recurrence_id().unwrap().as_datetime().unwrap(), berlin(1, 29, 10));
+        assert_eq!(moved.dtstart().unwrap().as_datetime().unwrap(), berlin(1, 30, 14));
+        assert!(moved.exdates().unwrap().is_empty());
+
+        assert!(ical.recurrence_set("missing").is_none());
     }
 
     #[test]
-    fn test_build_event() {
-        let mut ical = ICalendar::empty();
-        let mut builder = Event::build();
+    fn test_value_enums() {
+        use component::write_component;
 
-        let desc = Description::new(String::from("test"), BTreeMap::new());
-        builder.set_description(desc, None);
+        assert_eq!("cancelled".parse::<EventStatus>().unwrap(), EventStatus::Cancelled);
+        assert_eq!("X-CUSTOM".parse::<EventStatus>().unwrap(), EventStatus::Other("X-CUSTOM".into()));
+        assert_eq!(EventStatus::Other("X-CUSTOM".into()).as_ical_str(), "X-CUSTOM");
+        assert_eq!(Transparency::Transparent.to_string(), "TRANSPARENT");
+        assert_eq!(Partstat::from_ical_str("needs-action"), Partstat::NeedsAction);
 
-        let uid = Uid::new(String::from("testuid"), BTreeMap::new());
-        builder.set_uid(uid, None);
+        let ical = ICalendar::empty()
+            .with_event(Event::build()
+                        .with_status(EventStatus::Other("X-CUSTOM".into()))
+                        .with_transp(Transparency::Transparent.into(), None)
+                        .with_class(Classification::Private.into(), None))
+            .with_event(Event::build()
+                        .with_status(EventStatus::Confirmed)
+                        .with_class(Class::from_raw("x-secret".into()), None));
+        let ical = ICalendar::build(&write_component(&ical)).unwrap();
 
-        let summary = Summary::new(String::from("summary"), BTreeMap::new());
-        builder.set_summary(summary, None);
+        let events = ical.events().filter_map(Result::ok).collect::<Vec<_>>();
+        assert_eq!(events[0].status().unwrap().parsed(), EventStatus::Other("X-CUSTOM".into()));
+        assert_eq!(events[0].transp().unwrap().parsed(), Transparency::Transparent);
+        assert_eq!(events[0].class().unwrap().parsed(), Classification::Private);
+        assert_eq!(events[1].status().unwrap().parsed(), EventStatus::Confirmed);
+        assert_eq!(events[1].class().unwrap().parsed(), Classification::Other("x-secret".into()));
+        assert!(events[1].transp().is_none());
+    }
 
-        ical.add_event(builder);
+    #[test]
+    fn test_event_categories() {
+        let ical = ICalendar::empty()
+            .with_event(Event::build().with_categories(vec!["Food, Drink".into(), "Party".into()]));
+        let written = ::component::write_component(&ical);
+        assert!(written.contains("CATEGORIES:Food\\, Drink,Party\r\n"));
+
+        let ical = ICalendar::build(&written).unwrap();
+        let categories = ical.events().next().unwrap().unwrap().categories().unwrap();
+        assert_eq!(categories.values(), vec!["Food, Drink", "Party"]);
+        assert_eq!(Categories::from_raw(String::new()).values(), Vec::<String>::new());
+    }
 
+    #[test]
+    fn test_event_attendees() {
+        let ical = ICalendar::build("BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            ORGANIZER;CN=\"Alice Balder, Example Inc.\":MAILTO:alice@example.com\n\
+            ATTENDEE;CUTYPE=INDIVIDUAL;ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED;CN=Jo\n \
+            hn Doe;X-NUM-GUESTS=0:mailto:jd@cal.test\n\
+            ATTENDEE;RSVP=TRUE;DELEGATED-TO=\"mailto:b@cal.test\":mailto:a@cal.test\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
         let ev = ical.events().next().unwrap().unwrap();
-        assert_eq!(ev.uid().map(|e| e.raw().clone())         , Some("testuid".to_owned()));
-        assert_eq!(ev.description().map(|e| e.raw().clone()) , Some("test".to_owned()));
-        assert_eq!(ev.summary().map(|e| e.raw().clone())     , Some("summary".to_owned()));
 
+        let organizer = ev.organizer().unwrap();
+        assert_eq!(organizer.email(), "alice@example.com");
+        assert_eq!(organizer.common_name().unwrap(), "Alice Balder, Example Inc.");
+
+        let attendees = ev.attendees();
+        assert_eq!(attendees.len(), 2);
+        assert_eq!(attendees[0].common_name().unwrap(), "John Doe");
+        assert_eq!(attendees[0].email(), "jd@cal.test");
+        assert_eq!(attendees[0].partstat(), Partstat::Accepted);
+        assert_eq!(attendees[0].role(), Some("REQ-PARTICIPANT"));
+        assert_eq!(attendees[0].cutype(), Some("INDIVIDUAL"));
+        assert!(!attendees[0].rsvp());
+
+        assert_eq!(attendees[1].common_name(), None);
+        assert_eq!(attendees[1].partstat(), Partstat::NeedsAction);
+        assert_eq!(attendees[1].delegated_to(), vec!["b@cal.test"]);
+        assert!(attendees[1].rsvp());
+    }
+
+    #[test]
+    fn test_build_attendees() {
+        use component::write_component;
+
+        let ical = ICalendar::empty()
+            .with_event(Event::build()
+                        .with_organizer(Organizer::from_email("boss@cal.test").with_common_name("Boss"), None)
+                        .with_attendee(Attendee::from_email("jd@cal.test")
+                                       .with_common_name("Doe, John")
+                                       .with_partstat(Partstat::Tentative)
+                                       .with_role("CHAIR")
+                                       .with_rsvp(true), None)
+                        .with_attendee(Attendee::from_email("x@cal.test").with_common_name("Cott:n"), None));
+
+        let written = write_component(&ical);
+        let unfolded = written.replace("\r\n ", "");
+        assert!(unfolded.contains("ORGANIZER;CN=Boss:mailto:boss@cal.test\r\n"));
+        assert!(unfolded.contains("ATTENDEE;CN=\"Doe, John\";PARTSTAT=TENTATIVE;ROLE=CHAIR;RSVP=TRUE:mailto:jd@cal.test\r\n"));
+        assert!(unfolded.contains("ATTENDEE;CN=\"Cott:n\":mailto:x@cal.test\r\n"));
+
+        let ical = ICalendar::build(&written).unwrap();
+        let attendees = ical.events().next().unwrap().unwrap().attendees();
+        assert_eq!(attendees[0].common_name().unwrap(), "Doe, John");
+        assert_eq!(attendees[0].1.get("CN"), Some("Doe, John"));
+        assert_eq!(attendees[0].partstat(), Partstat::Tentative);
+        assert_eq!(attendees[1].common_name().unwrap(), "Cott:n");
+        assert_eq!(attendees[1].email(), "x@cal.test");
+        assert_eq!(write_component(&ical), written);
     }
 
 }
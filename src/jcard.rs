@@ -0,0 +1,362 @@
+//! Conversion between `Vcard` and jCard, the JSON format for vCard defined in
+//! [RFC 7095](https://tools.ietf.org/html/rfc7095). Available with the `jcard` feature.
+
+use serde_json::{Map, Value};
+
+use component::Component;
+use error::*;
+use property::{escape_chars, unescape_chars, Property};
+use util::{input_prefix, split_unescaped};
+use vcard::Vcard;
+
+/// Properties whose value consists of `;`-separated components.
+const STRUCTURED_PROPERTIES: &[&str] = &["ADR", "CLIENTPIDMAP", "GENDER", "N", "ORG"];
+
+/// Properties whose value is a `,`-separated list.
+const LIST_PROPERTIES: &[&str] = &["CATEGORIES", "NICKNAME"];
+
+/// The value type of a property when no `VALUE` parameter is given, see RFC 6350.
+fn default_type(name: &str) -> &'static str {
+    match name {
+        "ANNIVERSARY" | "BDAY" => "date-and-or-time",
+        "CALADRURI" | "CALURI" | "FBURL" | "GEO" | "IMPP" | "KEY" | "LOGO" | "MEMBER" | "PHOTO"
+            | "RELATED" | "SOUND" | "SOURCE" | "UID" | "URL" => "uri",
+        "LANG" => "language-tag",
+        "REV" => "timestamp",
+        name if name.starts_with("X-") => "unknown",
+        _ => "text",
+    }
+}
+
+/// Split a time into its local part and zone designator (`Z`, `+0200`, `-05`).
+fn split_zone(time: &str) -> (&str, &str) {
+    if let Some(local) = time.strip_suffix('Z') {
+        return (local, "Z");
+    }
+    match time.rfind(['+', '-']) {
+        Some(i) if i > 0 && !time[..i].ends_with('-') && time[i + 1..].chars().all(|c| c.is_ascii_digit() || c == ':') => {
+            time.split_at(i)
+        },
+        _ => (time, ""),
+    }
+}
+
+/// Insert `sep` between pairs of digits: `"102200"` becomes `"10:22:00"`.
+fn extend_pairs(s: &str, sep: char) -> String {
+    let mut rv = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if i > 0 && i % 2 == 0 {
+            rv.push(sep);
+        }
+        rv.push(c);
+    }
+    rv
+}
+
+fn extend_date(date: &str) -> String {
+    if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) {
+        format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])
+    } else if date.len() == 6 && date.starts_with("--") {
+        format!("--{}-{}", &date[2..4], &date[4..])
+    } else {
+        date.to_owned()
+    }
+}
+
+fn extend_time(time: &str) -> String {
+    let (local, zone) = split_zone(time);
+    let local = match local.strip_prefix('-') {
+        Some(rest) if !rest.starts_with('-') => format!("-{}", extend_pairs(rest, ':')),
+        Some(_) => local.to_owned(),
+        None => extend_pairs(local, ':'),
+    };
+    let zone = match zone.chars().next() {
+        Some(sign @ '+') | Some(sign @ '-') => format!("{}{}", sign, extend_pairs(&zone[1..], ':')),
+        _ => zone.to_owned(),
+    };
+    local + &zone
+}
+
+/// Convert a vCard date/time value from basic to extended ISO 8601 format, as required by jCard.
+fn extend_datetime(value: &str) -> String {
+    match value.find('T') {
+        Some(0) => format!("T{}", extend_time(&value[1..])),
+        Some(i) => format!("{}T{}", extend_date(&value[..i]), extend_time(&value[i + 1..])),
+        None => extend_date(value),
+    }
+}
+
+/// The reverse of `extend_datetime`.
+fn basic_datetime(value: &str) -> String {
+    let basic_date = |date: &str| {
+        if date.len() == 10 || (date.starts_with("--") && date.len() == 7) {
+            format!("{}{}", if date.starts_with("--") { "--" } else { "" }, date.replace('-', ""))
+        } else {
+            date.to_owned()
+        }
+    };
+
+    match value.find('T') {
+        Some(i) => format!("{}T{}", basic_date(&value[..i]), value[i + 1..].replace(':', "")),
+        None if value.contains(':') => value.replace(':', ""),
+        None => basic_date(value),
+    }
+}
+
+fn is_datetime_type(value_type: &str) -> bool {
+    matches!(value_type, "date" | "time" | "date-time" | "date-and-or-time" | "timestamp" | "utc-offset")
+}
+
+fn to_json_value(value: &str, value_type: &str) -> Value {
+    match value_type {
+        "text" => Value::String(unescape_chars(value)),
+        "boolean" => Value::Bool(value.eq_ignore_ascii_case("TRUE")),
+        "integer" | "float" => value.parse().map(Value::Number).unwrap_or_else(|_| Value::String(value.to_owned())),
+        t if t == "time" || t == "utc-offset" => Value::String(extend_time(value)),
+        t if is_datetime_type(t) => Value::String(extend_datetime(value)),
+        _ => Value::String(value.to_owned()),
+    }
+}
+
+fn from_json_value(value: &Value, value_type: &str) -> VObjectResult<String> {
+    Ok(match *value {
+        Value::String(ref s) if value_type == "text" => escape_chars(s),
+        Value::String(ref s) if is_datetime_type(value_type) => basic_datetime(s),
+        Value::String(ref s) => s.clone(),
+        Value::Bool(b) => String::from(if b { "TRUE" } else { "FALSE" }),
+        Value::Number(ref n) => n.to_string(),
+        ref x => return Err(VObjectError::InvalidJcard(format!("unexpected value: {}", x))),
+    })
+}
+
+fn property_to_jcard(prop: &Property) -> Value {
+    let name = prop.name.to_uppercase();
+    let mut params = Map::new();
+    let mut value_type = default_type(&name).to_owned();
+
+    if let Some(ref group) = prop.prop_group {
+        params.insert(String::from("group"), Value::String(group.clone()));
+    }
+    for (key, value) in &prop.params {
+        if key.eq_ignore_ascii_case("VALUE") {
+            value_type = value.to_lowercase();
+            continue;
+        }
+
+        let values = value.split(',').collect::<Vec<_>>();
+        let value = if values.len() > 1 {
+            Value::Array(values.into_iter().map(|v| Value::String(v.to_owned())).collect())
+        } else {
+            Value::String(value.clone())
+        };
+        params.insert(key.to_lowercase(), value);
+    }
+
+    let mut rv = vec![
+        Value::String(name.to_lowercase()),
+        Value::Object(params),
+        Value::String(value_type.clone()),
+    ];
+
+    if STRUCTURED_PROPERTIES.contains(&&name[..]) {
+        let components = split_unescaped(&prop.raw_value, ';')
+            .into_iter()
+            .map(|component| {
+                let values = split_unescaped(component, ',');
+                if values.len() > 1 {
+                    Value::Array(values.into_iter().map(|v| Value::String(unescape_chars(v))).collect())
+                } else {
+                    Value::String(unescape_chars(component))
+                }
+            })
+            .collect();
+        rv.push(Value::Array(components));
+    } else if LIST_PROPERTIES.contains(&&name[..]) {
+        rv.extend(split_unescaped(&prop.raw_value, ',').into_iter().map(|v| to_json_value(v, &value_type)));
+    } else {
+        rv.push(to_json_value(&prop.raw_value, &value_type));
+    }
+
+    Value::Array(rv)
+}
+
+fn property_from_jcard(value: &Value) -> VObjectResult<Property> {
+    let invalid = || VObjectError::InvalidJcard(format!("invalid property: {}", value));
+    let parts = value.as_array().filter(|p| p.len() >= 4).ok_or_else(invalid)?;
+    let name = parts[0].as_str().ok_or_else(invalid)?.to_uppercase();
+    let params = parts[1].as_object().ok_or_else(invalid)?;
+    let value_type = parts[2].as_str().ok_or_else(invalid)?;
+
+    let mut prop = Property::new(name, "");
+    for (key, value) in params {
+        let value = match *value {
+            Value::String(ref s) => s.clone(),
+            Value::Array(ref values) => values
+                .iter()
+                .map(|v| v.as_str().map(ToOwned::to_owned).ok_or_else(invalid))
+                .collect::<VObjectResult<Vec<_>>>()?
+                .join(","),
+            _ => return Err(invalid()),
+        };
+
+        if key == "group" {
+            prop.prop_group = Some(value);
+        } else {
+            prop.params.insert(key.to_uppercase(), value);
+        }
+    }
+    if value_type != "unknown" && value_type != default_type(&prop.name) {
+        prop.params.insert(String::from("VALUE"), value_type.to_uppercase());
+    }
+
+    let values = parts[3..]
+        .iter()
+        .map(|value| match *value {
+            Value::Array(ref components) => components
+                .iter()
+                .map(|component| match *component {
+                    Value::Array(ref values) => values
+                        .iter()
+                        .map(|v| from_json_value(v, "text"))
+                        .collect::<VObjectResult<Vec<_>>>()
+                        .map(|v| v.join(",")),
+                    ref v => from_json_value(v, "text"),
+                })
+                .collect::<VObjectResult<Vec<_>>>()
+                .map(|c| c.join(";")),
+            ref v => from_json_value(v, value_type),
+        })
+        .collect::<VObjectResult<Vec<_>>>()?;
+    prop.raw_value = values.join(",");
+
+    Ok(prop)
+}
+
+/// A short description of `jcard` for errors. Only the component name is serialized, as the
+/// whole jCard may be large.
+fn jcard_prefix(jcard: &Value) -> String {
+    match *jcard {
+        Value::Array(ref parts) => match parts.first() {
+            Some(name) => input_prefix(&format!("[{}, ...]", name)),
+            None => String::from("[]"),
+        },
+        Value::Object(_) => String::from("{...}"),
+        Value::String(ref s) => input_prefix(s),
+        ref x => x.to_string(),
+    }
+}
+
+impl Vcard {
+    /// Convert this vCard to jCard, e.g. `["vcard", [["version", {}, "text", "4.0"], ...]]`.
+    ///
+    /// Property groups are mapped to the `group` parameter, the `VALUE` parameter determines the
+    /// value type.
+    pub fn to_jcard(&self) -> Value {
+        let props = self.props
+            .values()
+            .flat_map(|props| props.iter())
+            .map(property_to_jcard)
+            .collect();
+
+        Value::Array(vec![Value::String(String::from("vcard")), Value::Array(props)])
+    }
+
+    /// Read a vCard from jCard, as produced by `to_jcard`.
+    pub fn from_jcard(jcard: &Value) -> VObjectResult<Vcard> {
+        let parts = match jcard.as_array() {
+            Some(parts) if parts.len() == 2 && parts[0] == "vcard" => parts,
+            _ => {
+                let found = jcard.get(0).and_then(Value::as_str).unwrap_or("no component");
+                return Err(VObjectError::NotAVCard {
+                    found: found.to_uppercase(),
+                    prefix: jcard_prefix(jcard),
+                });
+            },
+        };
+        let props = parts[1]
+            .as_array()
+            .ok_or_else(|| VObjectError::InvalidJcard(format!("expected property list, found {}", parts[1])))?;
+
+        let mut component = Component::new("VCARD");
+        for prop in props {
+            component.push(property_from_jcard(prop)?);
+        }
+
+        Vcard::from_component(component).map_err(|c| VObjectError::NotAVCard {
+            found: c.name,
+            prefix: jcard_prefix(jcard),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use vcard::Vcard;
+
+    #[test]
+    fn test_to_jcard() {
+        let card = Vcard::build("BEGIN:VCARD\n\
+                                 VERSION:4.0\n\
+                                 FN:Simon Perreault\n\
+                                 N:Perreault;Simon;;;ing. jr,M.Sc.\n\
+                                 BDAY:--0203\n\
+                                 ANNIVERSARY:20090808T1430-0500\n\
+                                 item1.TEL;VALUE=uri;TYPE=work,voice:tel:+1-418-656-9254\n\
+                                 CATEGORIES:a,b\\,c\n\
+                                 GEO:geo:37.386013,-122.082932\n\
+                                 PHOTO:data:image/png;base64,AAAA\n\
+                                 END:VCARD\n").unwrap();
+        let jcard = card.to_jcard();
+        let props = jcard[1].as_array().unwrap();
+        let find = |name: &str| props.iter().find(|p| p[0] == name).unwrap().clone();
+
+        assert_eq!(jcard[0], "vcard");
+        assert_eq!(find("version").to_string(), r#"["version",{},"text","4.0"]"#);
+        assert_eq!(find("n").to_string(), r#"["n",{},"text",["Perreault","Simon","","",["ing. jr","M.Sc."]]]"#);
+        assert_eq!(find("bday").to_string(), r#"["bday",{},"date-and-or-time","--02-03"]"#);
+        assert_eq!(find("anniversary")[3], "2009-08-08T14:30-05:00");
+        assert_eq!(find("tel").to_string(),
+                   r#"["tel",{"group":"item1","type":["work","voice"]},"uri","tel:+1-418-656-9254"]"#);
+        assert_eq!(find("categories").to_string(), r#"["categories",{},"text","a","b,c"]"#);
+        assert_eq!(find("geo").to_string(), r#"["geo",{},"uri","geo:37.386013,-122.082932"]"#);
+        assert_eq!(find("photo").to_string(), r#"["photo",{},"uri","data:image/png;base64,AAAA"]"#);
+    }
+
+    #[test]
+    fn test_jcard_roundtrip() {
+        let card = Vcard::build("BEGIN:VCARD\n\
+                                 VERSION:4.0\n\
+                                 FN:Erika Mustermann\n\
+                                 N:Mustermann;Erika;;Dr.;\n\
+                                 ADR;TYPE=home:;;Heidestra\u{df}e 17;K\u{f6}ln;;51147;Germany\n\
+                                 BDAY:19640812\n\
+                                 REV:20240101T120000Z\n\
+                                 item1.EMAIL;PREF=1:erika@example.com\n\
+                                 NOTE:Line one\\nLine two\\; with semicolon\n\
+                                 X-CUSTOM:foo\n\
+                                 URL:https://example.com/?a=1,2\n\
+                                 END:VCARD\n").unwrap();
+
+        let json = card.to_jcard().to_string();
+        let back = Vcard::from_jcard(&json.parse::<Value>().unwrap()).unwrap();
+        assert_eq!(*back, *card);
+    }
+
+    #[test]
+    fn test_from_jcard_errors() {
+        let parse = |s: &str| Vcard::from_jcard(&s.parse::<Value>().unwrap());
+        match parse(r#"["vcalendar", []]"#) {
+            Err(::error::VObjectError::NotAVCard { found, prefix }) => {
+                assert_eq!(found, "VCALENDAR");
+                assert_eq!(prefix, r#"["vcalendar", ...]"#);
+            },
+            x => panic!("unexpected result: {:?}", x),
+        }
+        assert!(parse(r#"["vcard", {}]"#).is_err());
+        assert!(parse(r#"["vcard", [["fn", {}]]]"#).is_err());
+        assert!(parse(r#"["vcard", [["fn", {}, "text", "Erika"]]]"#).is_ok());
+    }
+
+}
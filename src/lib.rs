@@ -1,25 +1,107 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// Code generated by thiserror refers to `::core`, which `no_std` brings in by itself
+#[cfg(feature = "std")]
+extern crate core;
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
 #[cfg(feature = "timeconversions")]
 extern crate chrono;
 
+#[cfg(feature = "serde")]
+extern crate serde;
+
 extern crate thiserror;
 
+#[cfg(feature = "uuid")]
+extern crate uuid;
+
+#[cfg(any(test, feature = "jcard"))]
+extern crate serde_json;
+
+/// Without `std`, stand in for the parts of it which the crate uses, so that paths like
+/// `std::collections::BTreeMap` work either way.
+#[cfg(not(feature = "std"))]
+mod std {
+    pub use core::*;
+    pub use alloc::{borrow, collections, fmt, slice, str};
+
+    /// What `std`'s prelude has on top of `core`'s.
+    pub mod prelude {
+        pub use alloc::borrow::ToOwned;
+        pub use alloc::boxed::Box;
+        pub use alloc::string::{String, ToString};
+        pub use alloc::vec::Vec;
+    }
+}
+
 #[macro_use] pub mod param;
 #[macro_use] mod util;
 
 pub mod component;
+mod detect;
 pub mod error;
+mod lint;
+mod normalize;
 mod parser;
 pub mod property;
+pub mod uid;
 pub mod vcard;
 pub mod icalendar;
+pub mod value;
+#[cfg(feature = "timeconversions")]
+pub mod rrule;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "jcard")]
+mod jcard;
 
 pub use component::Component;
+pub use component::ComponentDiff;
+pub use component::ComponentRef;
+pub use component::DumpOptions;
+pub use component::EquivalenceOptions;
+pub use component::EventParser;
+pub use component::parse_bytes;
 pub use component::parse_component;
+pub use component::parse_component_borrowed;
+pub use component::parse_component_with;
+pub use component::parse_component_with_spans;
+pub use component::parse_components;
+pub use component::parse_components_lenient;
+pub use component::ParseEvent;
 pub use component::read_component;
+pub use component::read_component_with;
+pub use component::SpanMap;
+pub use component::ParserOptions;
 pub use component::write_component;
+pub use component::write_component_with;
+#[cfg(feature = "std")]
+pub use component::write_component_to;
+pub use component::write_component_checked;
+pub use component::WriteOptions;
+pub use detect::detect;
+pub use detect::parse_any;
+pub use detect::AnyObject;
+pub use detect::ObjectKind;
+pub use lint::lint;
+pub use lint::Lint;
+pub use lint::LintKind;
+pub use normalize::normalize_ical;
+pub use normalize::normalize_ical_with;
+pub use normalize::normalize_vcard;
+pub use normalize::normalize_vcard_with;
+pub use normalize::NormalizeOptions;
 pub use property::Property;
+pub use property::PropertyRef;
+pub use property::TypedProperty;
 pub use property::escape_chars;
 pub use property::unescape_chars;
 
+pub use value::TypedValue;
+pub use value::ValueType;
+
 pub use vcard::Vcard;
 pub use icalendar::ICalendar;
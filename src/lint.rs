@@ -0,0 +1,201 @@
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use component::{property_line, Component};
+use icalendar::Severity;
+use property::Property;
+
+/// Unfolded lines longer than this are rejected by servers which check the line length only after
+/// unfolding.
+const MAX_LINE_OCTETS: usize = 998;
+
+/// `X-` properties with longer values are dropped by some servers, e.g. Google Calendar.
+const MAX_X_PROPERTY_OCTETS: usize = 8 * 1024;
+
+/// Properties for which an empty value carries no meaning.
+const NON_EMPTY_PROPERTIES: &[&str] = &[
+    "DTSTART", "DTEND", "DTSTAMP", "DUE", "RECURRENCE-ID", "RRULE", "TRIGGER", "UID", "VERSION",
+];
+
+/// Date-time properties which need `VALUE=DATE` to hold a date.
+const DATE_TIME_PROPERTIES: &[&str] = &["DTSTART", "DTEND", "DUE", "RECURRENCE-ID", "EXDATE", "RDATE"];
+
+/// The kind of a `Lint`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LintKind {
+    /// The unfolded content line is longer than 998 octets.
+    LongLine,
+
+    /// The property or group name contains characters other than ASCII.
+    NonAsciiName,
+
+    /// The property has an empty value, which means nothing for it, e.g. `DTSTART`.
+    EmptyValue,
+
+    /// An `X-` property has a value larger than 8 KiB.
+    LargeXProperty,
+
+    /// A date-time property holds a date such as `20240115`, but has no `VALUE=DATE`.
+    MissingValueDate,
+
+    /// The raw value contains a line break, which ends the line early when written.
+    LineBreakInValue,
+}
+
+impl LintKind {
+    pub fn severity(&self) -> Severity {
+        match *self {
+            LintKind::NonAsciiName | LintKind::LineBreakInValue => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+/// An interoperability warning found by `lint()`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Lint {
+    /// The path to the component, e.g. `VCALENDAR > VEVENT[2]`, as for
+    /// `ICalendar::validate()`.
+    pub path: String,
+
+    /// The offending property.
+    pub property: Option<String>,
+
+    pub kind: LintKind,
+
+    /// A description for humans.
+    pub message: String,
+}
+
+impl Lint {
+    /// Shortcut for `self.kind.severity()`.
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.property {
+            Some(ref name) => write!(f, "{}, {}: {}", self.path, name, self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Look for data which parses and writes fine, but which other implementations may reject or
+/// mangle: overlong lines, non-ASCII names, empty `DTSTART` and similar values, large `X-`
+/// properties, dates without `VALUE=DATE` and line breaks in raw values.
+///
+/// This only reports problems and changes nothing. See also `ICalendar::validate()` for checks
+/// against RFC 5545.
+pub fn lint(c: &Component) -> Vec<Lint> {
+    let mut lints = vec![];
+    lint_component(c, c.name.clone(), &mut lints);
+    lints
+}
+
+fn lint_component(c: &Component, path: String, lints: &mut Vec<Lint>) {
+    for prop in c.iter_props() {
+        let mut push = |kind, message: String| lints.push(Lint {
+            path: path.clone(),
+            property: Some(prop.name.clone()),
+            kind,
+            message,
+        });
+        lint_property(prop, &mut push);
+    }
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for sub in &c.subcomponents {
+        let index = counts.entry(&sub.name).or_insert(0);
+        let subpath = format!("{} > {}[{}]", path, sub.name, index);
+        *index += 1;
+        lint_component(sub, subpath, lints);
+    }
+}
+
+fn lint_property(prop: &Property, push: &mut dyn FnMut(LintKind, String)) {
+    let name = prop.name.to_uppercase();
+
+    let line = property_line(prop);
+    if line.len() > MAX_LINE_OCTETS {
+        push(LintKind::LongLine, format!("line of {} octets is longer than {}", line.len(), MAX_LINE_OCTETS));
+    }
+
+    let group = prop.prop_group.as_deref().unwrap_or("");
+    if !prop.name.is_ascii() || !group.is_ascii() {
+        push(LintKind::NonAsciiName, String::from("name contains non-ASCII characters"));
+    }
+
+    if prop.raw_value.trim().is_empty() && NON_EMPTY_PROPERTIES.contains(&&name[..]) {
+        push(LintKind::EmptyValue, String::from("value is empty"));
+    }
+
+    if name.starts_with("X-") && prop.raw_value.len() > MAX_X_PROPERTY_OCTETS {
+        push(LintKind::LargeXProperty,
+             format!("value of {} octets is larger than {}", prop.raw_value.len(), MAX_X_PROPERTY_OCTETS));
+    }
+
+    let is_date = prop.raw_value.len() == 8 && prop.raw_value.bytes().all(|b| b.is_ascii_digit());
+    let value_date = prop.params.get("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE"));
+    if is_date && !value_date && DATE_TIME_PROPERTIES.contains(&&name[..]) {
+        push(LintKind::MissingValueDate, format!("date {} without VALUE=DATE", prop.raw_value));
+    }
+
+    if prop.raw_value.contains(['\r', '\n']) {
+        push(LintKind::LineBreakInValue, String::from("value contains a line break"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))] use std::prelude::*;
+    use super::{lint, LintKind};
+    use component::{parse_component, Component};
+    use icalendar::Severity;
+    use property::Property;
+
+    #[test]
+    fn test_lint() {
+        let mut cal = parse_component("BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            PRODID:-//test//EN\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:ok\r\n\
+            DTSTART;VALUE=DATE:20240115\r\n\
+            SUMMARY:Fine\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:\r\n\
+            DTSTART:20240115\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n").unwrap();
+
+        let mut event = Component::new("VEVENT");
+        event.push(Property::new("UID", "broken"));
+        event.push(Property::new("X-BLOB", "a".repeat(9000)));
+        event.push(Property { name: "X-NOTÉ".into(), params: Default::default(), raw_value: "x".into(), prop_group: None });
+        event.push(Property { name: "DESCRIPTION".into(), params: Default::default(), raw_value: "a\r\nb".into(), prop_group: None });
+        cal.subcomponents.push(event);
+
+        let lints = lint(&cal);
+        let found = lints.iter()
+            .map(|l| (&l.path[..], l.property.as_deref().unwrap(), l.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![
+            ("VCALENDAR > VEVENT[1]", "DTSTART", LintKind::MissingValueDate),
+            ("VCALENDAR > VEVENT[1]", "UID", LintKind::EmptyValue),
+            ("VCALENDAR > VEVENT[2]", "DESCRIPTION", LintKind::LineBreakInValue),
+            ("VCALENDAR > VEVENT[2]", "X-BLOB", LintKind::LongLine),
+            ("VCALENDAR > VEVENT[2]", "X-BLOB", LintKind::LargeXProperty),
+            ("VCALENDAR > VEVENT[2]", "X-NOTÉ", LintKind::NonAsciiName),
+        ]);
+
+        assert_eq!(lints[0].severity(), Severity::Warning);
+        assert_eq!(lints[2].severity(), Severity::Error);
+        assert_eq!(lints[0].to_string(), "VCALENDAR > VEVENT[1], DTSTART: date 20240115 without VALUE=DATE");
+    }
+}
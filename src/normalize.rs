@@ -0,0 +1,102 @@
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
+use component::{parse_components, write_component_with, Component, WriteOptions};
+use error::*;
+use util::input_prefix;
+
+/// Options for `normalize_ical_with` and `normalize_vcard_with`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NormalizeOptions {
+    /// Sort the components of a calendar, such as events, and the cards of a file: time zones
+    /// first, then by name, `UID` and `RECURRENCE-ID`. Otherwise their order is kept.
+    pub sort_components: bool,
+
+    /// Line endings and folding of the output. The default is CRLF and folding at 75 octets.
+    pub write: WriteOptions,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            sort_components: true,
+            write: WriteOptions::default(),
+        }
+    }
+}
+
+/// Rewrite the calendars in `s` in a canonical form, e.g. to store them in version control so
+/// that diffs only show real changes.
+///
+/// Names are uppercased, parameters sorted, `VERSION` and `PRODID` written first, then the other
+/// properties sorted by name, see `write_component`. Normalizing the output again does not change
+/// it, and parsing it yields the same calendars, apart from the order of their components.
+///
+/// ```
+/// let input = "begin:vcalendar\nprodid:-//Example//EN\nversion:2.0\n\
+///              begin:vevent\nuid:b\nend:vevent\n\
+///              begin:vevent\nuid:a\nend:vevent\n\
+///              end:vcalendar\n";
+/// let normalized = vobject::normalize_ical(input).unwrap();
+/// assert_eq!(normalized, "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Example//EN\r\n\
+///                         BEGIN:VEVENT\r\nUID:a\r\nEND:VEVENT\r\n\
+///                         BEGIN:VEVENT\r\nUID:b\r\nEND:VEVENT\r\n\
+///                         END:VCALENDAR\r\n");
+/// assert_eq!(vobject::normalize_ical(&normalized).unwrap(), normalized);
+/// ```
+pub fn normalize_ical(s: &str) -> VObjectResult<String> {
+    normalize_ical_with(s, &NormalizeOptions::default())
+}
+
+/// Like `normalize_ical`, with custom options.
+pub fn normalize_ical_with(s: &str, opts: &NormalizeOptions) -> VObjectResult<String> {
+    let mut calendars = parse_expecting(s, "VCALENDAR")?;
+    if opts.sort_components {
+        for calendar in &mut calendars {
+            sort_components(&mut calendar.subcomponents, &opts.write);
+        }
+    }
+    Ok(calendars.iter().map(|c| write_component_with(c, &opts.write)).collect())
+}
+
+/// Rewrite the cards in `s` in a canonical form, like `normalize_ical` does for calendars.
+///
+/// With `NormalizeOptions::sort_components`, the cards are sorted by `UID`.
+pub fn normalize_vcard(s: &str) -> VObjectResult<String> {
+    normalize_vcard_with(s, &NormalizeOptions::default())
+}
+
+/// Like `normalize_vcard`, with custom options.
+pub fn normalize_vcard_with(s: &str, opts: &NormalizeOptions) -> VObjectResult<String> {
+    let mut cards = parse_expecting(s, "VCARD")?;
+    if opts.sort_components {
+        sort_components(&mut cards, &opts.write);
+    }
+    Ok(cards.iter().map(|c| write_component_with(c, &opts.write)).collect())
+}
+
+/// Parse all components, failing if any of them is not called `name`.
+fn parse_expecting(s: &str, name: &str) -> VObjectResult<Vec<Component>> {
+    let components = parse_components(s)?;
+    if let Some(c) = components.iter().find(|c| c.name != name) {
+        let found = c.name.clone();
+        let prefix = input_prefix(s);
+        return Err(match name {
+            "VCARD" => VObjectError::NotAVCard { found, prefix },
+            _ => VObjectError::NotAnICalendar { found, prefix },
+        });
+    }
+    Ok(components)
+}
+
+/// Sort into a total order, so that sorting again does not change anything. The written form
+/// breaks ties between components with the same `UID` and `RECURRENCE-ID`.
+fn sort_components(components: &mut [Component], opts: &WriteOptions) {
+    let value = |c: &Component, name: &str| c.get_all(name).first().map(|p| p.raw_value.clone());
+    components.sort_by_cached_key(|c| {
+        (c.name != "VTIMEZONE",
+         c.name.clone(),
+         value(c, "UID").or_else(|| value(c, "TZID")),
+         value(c, "RECURRENCE-ID"),
+         write_component_with(c, opts))
+    });
+}
@@ -1,16 +1,219 @@
+use std::collections::btree_map;
 use std::collections::BTreeMap;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+#[cfg(not(feature = "std"))] use std::prelude::*;
 
-pub type Parameters = BTreeMap<String, String>;
+/// Bare vCard 2.1 parameters which are encodings rather than types.
+pub(crate) const BARE_ENCODINGS: &[&str] = &["7BIT", "8BIT", "BASE64", "QUOTED-PRINTABLE"];
+
+/// Parameters whose value is a comma-separated list, so that commas in them are not quoted.
+pub(crate) const LIST_PARAMS: &[&str] = &["PID", "SORT-AS", "TYPE"];
+
+/// The parameters of a property, such as `TYPE=HOME` in `TEL;TYPE=HOME:...`.
+///
+/// Names are case-insensitive and stored uppercased. Values are stored as they appear in the
+/// file, a list like `TYPE=HOME,VOICE` is a single value.
+///
+/// For compatibility, the underlying map is still reachable through `Deref`. Lookups through
+/// it are case-sensitive.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Parameters(BTreeMap<String, String>);
+
+impl Parameters {
+    pub fn new() -> Parameters {
+        Parameters(BTreeMap::new())
+    }
+
+    /// Get the value of a parameter, ignoring the case of the name.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match self.0.get(key) {
+            Some(value) => Some(value),
+            None => self.0.get(&key.to_uppercase()[..]).map(String::as_str),
+        }
+    }
+
+    /// Get the values of a parameter which is a comma-separated list, such as `TYPE`.
+    ///
+    /// Surrounding quotes and empty entries are dropped.
+    pub fn get_values(&self, key: &str) -> Vec<&str> {
+        self.get(key)
+            .map(|value| {
+                value.split(',')
+                    .map(|v| v.trim().trim_matches('"'))
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Set a parameter, replacing the old value. Returns the old value.
+    pub fn insert(&mut self, key: String, value: String) -> Option<String> {
+        let key = if key.chars().any(char::is_lowercase) { key.to_uppercase() } else { key };
+        self.0.insert(key, value)
+    }
+
+    /// Remove a parameter, ignoring the case of the name.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.0.remove(key).or_else(|| self.0.remove(&key.to_uppercase()[..]))
+    }
+
+    /// Add the entries of a comma-separated `value` to a comma-separated list, except those
+    /// already contained, ignoring case.
+    pub fn append_value(&mut self, key: &str, value: &str) {
+        for entry in value.split(',') {
+            let bare = entry.trim().trim_matches('"');
+            if self.get_values(key).iter().any(|v| v.eq_ignore_ascii_case(bare))
+                || (bare.is_empty() && self.get(key).is_some()) {
+                continue;
+            }
+
+            let list = self.0.entry(key.to_uppercase()).or_default();
+            if !list.is_empty() {
+                list.push(',');
+            }
+            list.push_str(entry);
+        }
+    }
+
+    /// Whether `TYPE` contains the given type, ignoring case. Bare vCard 2.1 parameters like
+    /// `TEL;HOME:...` count as types too.
+    pub fn contains_type(&self, t: &str) -> bool {
+        self.get_values("TYPE").iter().any(|v| v.eq_ignore_ascii_case(t))
+            || self.get(t) == Some("")
+    }
+
+    /// The uppercased types, from `TYPE` and from bare vCard 2.1 parameters like
+    /// `TEL;HOME:...`, without duplicates.
+    pub fn types(&self) -> Vec<String> {
+        let bare = self.0
+            .iter()
+            .filter(|&(k, v)| v.is_empty() && !BARE_ENCODINGS.contains(&&k.to_ascii_uppercase()[..]))
+            .map(|(k, _)| &k[..]);
+
+        let mut rv: Vec<String> = vec![];
+        for t in self.get_values("TYPE").into_iter().chain(bare) {
+            let t = t.to_ascii_uppercase();
+            if !rv.contains(&t) {
+                rv.push(t);
+            }
+        }
+        rv
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, String, String> {
+        self.0.iter()
+    }
+
+    pub fn into_inner(self) -> BTreeMap<String, String> {
+        self.0
+    }
+}
+
+impl Deref for Parameters {
+    type Target = BTreeMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Parameters {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<BTreeMap<String, String>> for Parameters {
+    fn from(map: BTreeMap<String, String>) -> Parameters {
+        map.into_iter().collect()
+    }
+}
+
+/// Collect parameters, such as those of a parsed content line. Values of repeated names are
+/// merged into a comma-separated list, so `TYPE=HOME;TYPE=CELL` becomes `TYPE=HOME,CELL`.
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Parameters {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Parameters {
+        let mut rv = Parameters::new();
+        for (k, v) in iter {
+            let (k, v) = (k.into(), v.into());
+            if rv.get(&k).is_some() {
+                rv.append_value(&k, &v);
+            } else {
+                rv.insert(k, v);
+            }
+        }
+        rv
+    }
+}
+
+impl<'a> IntoIterator for &'a Parameters {
+    type Item = (&'a String, &'a String);
+    type IntoIter = btree_map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Parameters {
+    type Item = (String, String);
+    type IntoIter = btree_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
 
 #[macro_export]
 macro_rules! parameters(
     { $($key:expr => $value:expr),* } => {
-        #[allow(unused_mut)]
         {
-            let mut m : ::std::collections::BTreeMap<String, String> =
-                ::std::collections::BTreeMap::new();
+            #[allow(unused_mut)]
+            let mut m = $crate::param::Parameters::new();
             $( m.insert($key.into(), $value.into()); )*
             m
         }
      };
 );
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))] use std::prelude::*;
+    use super::Parameters;
+
+    #[test]
+    fn test_parameters() {
+        let mut params = parameters!("type" => "HOME,voice", "PREF" => "1", "X-BARE" => "");
+
+        assert_eq!(params.get("TYPE"), Some("HOME,voice"));
+        assert_eq!(params.get("Type"), Some("HOME,voice"));
+        assert_eq!(params.get("LABEL"), None);
+        assert_eq!(params.get_values("type"), vec!["HOME", "voice"]);
+        assert!(params.get_values("LABEL").is_empty());
+
+        assert!(params.contains_type("home") && params.contains_type("VOICE"));
+        assert!(params.contains_type("x-bare"));
+        assert!(!params.contains_type("WORK") && !params.contains_type("PREF"));
+        assert_eq!(params.types(), vec!["HOME", "VOICE", "X-BARE"]);
+
+        params.append_value("TYPE", "work");
+        params.append_value("TYPE", "Voice");
+        params.append_value("Label", "Somewhere");
+        assert_eq!(params.get("TYPE"), Some("HOME,voice,work"));
+        assert_eq!(params.get("LABEL"), Some("Somewhere"));
+
+        assert_eq!(params.remove("pref"), Some("1".to_owned()));
+        assert_eq!(params.iter().map(|(k, _)| &k[..]).collect::<Vec<_>>(), vec!["LABEL", "TYPE", "X-BARE"]);
+
+        let collected = vec![("cn", "Erika")].into_iter().collect::<Parameters>();
+        assert_eq!(collected.get("CN"), Some("Erika"));
+        assert!(collected.contains_key("CN"));
+
+        let merged = vec![("TYPE", "HOME"), ("type", "CELL,home"), ("TYPE", "cell,,WORK"), ("X-BARE", ""), ("X-BARE", "")]
+            .into_iter()
+            .collect::<Parameters>();
+        assert_eq!(merged.get("TYPE"), Some("HOME,CELL,WORK"));
+        assert_eq!(merged.get("X-BARE"), Some(""));
+    }
+}
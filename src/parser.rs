@@ -1,29 +1,129 @@
-use std::collections::BTreeMap;
-use std::fmt;
-
 use thiserror::Error;
 
-use component::Component;
-use property::Property;
+use std::borrow::Cow;
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
+use component::{normalize_name, Component, ComponentRef};
+use property::PropertyRef;
 
-#[derive(Debug, Clone, Error)]
+/// Why parsing failed. Positions are byte offsets into the input.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
 pub enum ParseErrorReason {
-    #[error("trailing data: {}", _0)]
-    TrailingData(String),
-    #[error("expected {}, found EOL", _0)]
-    UnexpectedEol(char),
-    #[error("expected {}, found {}", _0, _1)]
-    UnexpectedChar(char, char),
+    #[error("trailing data at byte {}: {}", at, data)]
+    TrailingData { at: usize, data: String },
+    #[error("expected {}, found end of input", expected)]
+    UnexpectedEof { expected: char },
+    #[error("expected {}, found {}", expected, found)]
+    UnexpectedChar { expected: char, found: char },
     #[error("expected EOL")]
     ExpectedEol,
     #[error("no property name found")]
-    NoPropertyName,
+    MissingPropertyName,
     #[error("no parameter name found")]
-    NoParameterName,
+    MissingParameterName,
     #[error("expected BEGIN tag")]
     ExpectedBegin,
-    #[error("mismatched tags: BEGIN:{} vs END:{}", _0, _1)]
-    MismatchedTag(String, String),
+    #[error("mismatched tags: BEGIN:{} vs END:{}", begin, end)]
+    MismatchedTag { begin: String, end: String },
+    #[error("expected CRLF, found lone LF")]
+    LoneLineFeed,
+    #[error("unexpected empty line")]
+    EmptyLine,
+    #[error("control character {:?} not allowed", found)]
+    ControlCharacter { found: char },
+    #[error("parameter {} has no value", name)]
+    MissingParameterValue { name: String },
+    #[error("unexpected end of input: component {name} begun at line {line} was never closed with END:{name}")]
+    UnclosedComponent { name: String, line: usize },
+    #[error("invalid parameter at byte {}: {}", at, reason)]
+    InvalidParam { at: usize, reason: Box<ParseErrorReason> },
+    #[error("components nested deeper than {} levels", max_depth)]
+    TooDeeplyNested { max_depth: usize },
+    #[error("component {} has more than {} properties", component, max)]
+    TooManyProperties { component: String, max: usize },
+    #[error("unexpected byte order mark at start of input")]
+    ByteOrderMark,
+}
+
+impl ParseErrorReason {
+    /// Whether the input ended too early, as happens with truncated files.
+    pub fn is_truncated(&self) -> bool {
+        match *self {
+            ParseErrorReason::UnexpectedEof { .. } | ParseErrorReason::UnclosedComponent { .. } => true,
+            ParseErrorReason::InvalidParam { ref reason, .. } => reason.is_truncated(),
+            _ => false,
+        }
+    }
+
+    /// The byte offset in the input the error refers to, if known.
+    pub fn position(&self) -> Option<usize> {
+        match *self {
+            ParseErrorReason::TrailingData { at, .. } | ParseErrorReason::InvalidParam { at, .. } => Some(at),
+            _ => None,
+        }
+    }
+}
+
+/// Options for `parse_component_with`.
+///
+/// The default options are as lax as `parse_component`, which accepts a lot of real-world data
+/// that violates the RFCs. Use `ParserOptions::strict()` to validate data before sending it to
+/// other implementations.
+///
+/// Independent of the other options, nesting depth and number of properties are limited to
+/// protect against malicious input.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ParserOptions {
+    /// Require every line, including the last one, to be terminated, and reject empty lines.
+    pub strict_line_endings: bool,
+
+    /// Reject control characters other than horizontal tab in property and parameter values.
+    pub forbid_control_chars: bool,
+
+    /// Reject lines terminated by a lone LF instead of CRLF.
+    pub require_crlf: bool,
+
+    /// Reject parameters without value, such as vCard 2.1's `TEL;HOME:...`.
+    pub require_param_values: bool,
+
+    /// Reject a byte order mark at the start of the input instead of skipping it.
+    pub forbid_byte_order_mark: bool,
+
+    /// Maximum nesting depth of components, the outermost component being at depth 1. Default
+    /// is 16.
+    pub max_depth: usize,
+
+    /// Maximum number of properties in a single component. Default is 10000.
+    pub max_properties: usize,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            strict_line_endings: false,
+            forbid_control_chars: false,
+            require_crlf: false,
+            require_param_values: false,
+            forbid_byte_order_mark: false,
+            max_depth: 16,
+            max_properties: 10_000,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Enable all checks.
+    pub fn strict() -> ParserOptions {
+        ParserOptions {
+            strict_line_endings: true,
+            forbid_control_chars: true,
+            require_crlf: true,
+            require_param_values: true,
+            forbid_byte_order_mark: true,
+            ..ParserOptions::default()
+        }
+    }
 }
 
 type ParseResult<T> = Result<T, ParseErrorReason>;
@@ -31,14 +131,59 @@ type ParseResult<T> = Result<T, ParseErrorReason>;
 pub struct Parser<'s> {
     pub input: &'s str,
     pub pos: usize,
+    pub options: ParserOptions,
 }
 
 impl<'s> Parser<'s> {
-    pub fn new(input: &'s str) -> Self {
+    pub fn with_options(input: &'s str, options: ParserOptions) -> Self {
+        // A leading byte order mark is common in files written on Windows
+        let pos = if !options.forbid_byte_order_mark && input.starts_with('\u{feff}') {
+            '\u{feff}'.len_utf8()
+        } else {
+            0
+        };
+
         Parser {
-            input: input,
-            pos: 0,
+            input,
+            pos,
+            options,
+        }
+    }
+
+    /// Read up to and including the first `BEGIN` line and return its uppercased value, i.e. the
+    /// name of the outermost component. Leading blank lines are skipped.
+    pub fn consume_begin_name(&mut self) -> ParseResult<Cow<'s, str>> {
+        self.skip_blank_lines();
+        if self.pos == 0 && self.input.starts_with('\u{feff}') {
+            return Err(ParseErrorReason::ByteOrderMark);
         }
+
+        let property = self.consume_property()?;
+        if property.name != "BEGIN" {
+            return Err(ParseErrorReason::ExpectedBegin);
+        }
+        Ok(uppercase(property.raw_value))
+    }
+
+    /// Error out on control characters in the raw input between `start` and the current
+    /// position, if configured. Line breaks of folded lines are fine.
+    fn check_control_chars(&self, start: usize) -> ParseResult<()> {
+        if !self.options.forbid_control_chars {
+            return Ok(());
+        }
+
+        let mut chars = self.input[start..self.pos].chars().peekable();
+        while let Some(c) = chars.next() {
+            let allowed = match c {
+                '\t' | '\n' => true,
+                '\r' => chars.peek() == Some(&'\n'),
+                c => !c.is_control(),
+            };
+            if !allowed {
+                return Err(ParseErrorReason::ControlCharacter { found: c });
+            }
+        }
+        Ok(())
     }
 
     /// look-ahead for next char at given offset from current position
@@ -56,17 +201,28 @@ impl<'s> Parser<'s> {
     /// - CR alone [is not acceptable content]
     ///   (https://tools.ietf.org/html/rfc5545#section-3.1)
     fn peek_at(&self, at: usize) -> Option<(char, usize)> {
-        match self.input[self.pos+at..].chars().next() {
-            None => None,
-            Some('\r') => self.peek_at(at + 1),
-            Some('\n') => {
-                match self.peek_at(at + 1) {
-                    Some((' ', offset)) |
-                    Some(('\t', offset)) => self.peek_at(offset),
-                    _ => Some(('\n', at + 1)),
+        // All special characters are ASCII, so looking at bytes never splits a char.
+        let bytes = self.input.as_bytes();
+        let mut i = self.pos + at;
+        loop {
+            match *bytes.get(i)? {
+                b'\r' => i += 1,
+                b'\n' => {
+                    let mut next = i + 1;
+                    while bytes.get(next) == Some(&b'\r') {
+                        next += 1;
+                    }
+                    match bytes.get(next) {
+                        Some(b' ') | Some(b'\t') => i = next + 1,
+                        _ => return Some(('\n', i + 1 - self.pos)),
+                    }
+                }
+                b if b.is_ascii() => return Some((b as char, i + 1 - self.pos)),
+                _ => {
+                    let c = self.input[i..].chars().next()?;
+                    return Some((c, i + c.len_utf8() - self.pos));
                 }
             }
-            Some(x) => Some((x, at + x.len_utf8()))
         }
     }
 
@@ -83,12 +239,12 @@ impl<'s> Parser<'s> {
         let real_c = match self.peek() {
             Some((x, _)) => x,
             None => {
-                return Err(ParseErrorReason::UnexpectedEol(c))
+                return Err(ParseErrorReason::UnexpectedEof { expected: c })
            }
         };
 
         if real_c != c {
-            return Err(ParseErrorReason::UnexpectedChar(c, real_c))
+            return Err(ParseErrorReason::UnexpectedChar { expected: c, found: real_c })
         };
 
         Ok(())
@@ -113,12 +269,13 @@ impl<'s> Parser<'s> {
     fn consume_eol(&mut self) -> ParseResult<()> {
         let start_pos = self.pos;
 
+        if self.options.require_crlf && self.input[self.pos..].starts_with('\n') {
+            return Err(ParseErrorReason::LoneLineFeed);
+        }
+
         let consumed = match self.consume_char() {
             Some('\n') => true,
-            Some('\r') => match self.consume_char() {
-                Some('\n') => true,
-                _ => false,
-            },
+            Some('\r') => matches!(self.consume_char(), Some('\n')),
             _ => false,
         };
 
@@ -126,30 +283,47 @@ impl<'s> Parser<'s> {
             Ok(())
         } else {
             self.pos = start_pos;
-            return Err(ParseErrorReason::ExpectedEol)
+            Err(ParseErrorReason::ExpectedEol)
         }
     }
 
     fn sloppy_terminate_line(&mut self) -> ParseResult<()> {
+        if self.options.strict_line_endings {
+            self.consume_eol()?;
+            if !self.eof() && self.consume_eol().is_ok() {
+                return Err(ParseErrorReason::EmptyLine);
+            }
+            return Ok(());
+        }
+
         if !self.eof() {
             self.consume_eol()?;
-            while let Ok(_) = self.consume_eol() {}
+            while self.consume_eol().is_ok() {}
         };
 
         Ok(())
     }
 
-    // GR this used to return just a slice from input, but line unfolding
-    // makes it contradictory, unless one'd want to rescan everything.
-    // Since actually useful calls used to_owned() on the result, which
-    // does copy into a String's buffer, let's create a String right away
+    // Returns a slice of the input if nothing was skipped. Line unfolding
+    // makes that impossible, in which case a String is built.
     // implementation detail : instead of pushing char after char, we
     // do it by the biggest contiguous slices possible, because I believe it
     // to be more efficient (less checks for reallocation etc).
-    pub fn consume_while<F: Fn(char) -> bool>(&mut self, test: F) -> String {
+    pub fn consume_while<F: Fn(char) -> bool>(&mut self, test: F) -> Cow<'s, str> {
+        let start_pos = self.pos;
         let mut sl_start_pos = self.pos;
-        let mut res = String::new();
+        let mut res: Option<String> = None;
         while !self.eof() {
+            // Fast path for the common case of a char which is not part of a line break
+            let b = self.input.as_bytes()[self.pos];
+            if b.is_ascii() && b != b'\r' && b != b'\n' {
+                if !test(b as char) {
+                    break
+                }
+                self.pos += 1;
+                continue;
+            }
+
             match self.peek() {
                 Some((c, offset)) => {
                     if !test(c) {
@@ -157,6 +331,7 @@ impl<'s> Parser<'s> {
                     } else {
                         if offset > c.len_utf8() {
                             // we have some skipping and therefore need to flush
+                            let res = res.get_or_insert_with(String::new);
                             res.push_str(&self.input[sl_start_pos..self.pos]);
                             res.push(c);
                             sl_start_pos = self.pos + offset;
@@ -167,71 +342,82 @@ impl<'s> Parser<'s> {
                 _ => break
             }
         }
-        // Final flush
-        if sl_start_pos < self.pos {
-            res.push_str(&self.input[sl_start_pos..self.pos])
+
+        match res {
+            None => Cow::Borrowed(&self.input[start_pos..self.pos]),
+            Some(mut res) => {
+                // Final flush
+                if sl_start_pos < self.pos {
+                    res.push_str(&self.input[sl_start_pos..self.pos])
+                }
+                Cow::Owned(res)
+            }
         }
-        res
     }
 
-    pub fn consume_property(&mut self) -> ParseResult<Property> {
-        let group = self.consume_property_group().ok();
-        let name = self.consume_property_name()?;
-        let params = self.consume_params();
+    pub fn consume_property(&mut self) -> ParseResult<PropertyRef<'s>> {
+        // Scan the name only once, it turns out to be the group if a dot follows
+        let first = self.consume_property_name()?;
+        let (group, name) = if self.consume_only_char('.') {
+            (Some(first), self.consume_property_name()?)
+        } else {
+            (None, first)
+        };
+        // Property names are case-insensitive, normalize them for lookups
+        let name = uppercase(name);
+        let params = self.consume_params()?;
 
         self.assert_char(':')?;
         self.consume_char();
 
         let value = self.consume_property_value()?;
 
-        Ok(Property {
-            name: name,
-            params: params,
+        Ok(PropertyRef {
+            name,
+            params,
             raw_value: value,
             prop_group: group,
         })
     }
 
-    fn consume_property_name(&mut self) -> ParseResult<String> {
+    fn consume_property_name(&mut self) -> ParseResult<Cow<'s, str>> {
         let rv = self.consume_while(|x| x == '-' || x.is_alphanumeric());
         if rv.is_empty() {
-            Err(ParseErrorReason::NoPropertyName)
+            Err(ParseErrorReason::MissingPropertyName)
         } else {
             Ok(rv)
         }
     }
 
-    fn consume_property_group(&mut self) -> ParseResult<String> {
-        let start_pos = self.pos;
-        let name = self.consume_property_name();
-
-        let e = match name {
-            Ok(name) => match self.assert_char('.') {
-                Ok(_) => {
-                    self.consume_char();
-                    return Ok(name);
-                },
-                Err(e) => Err(e),
-            },
-            Err(e) => Err(e),
-        };
-
-        self.pos = start_pos;
-        e
+    /// Skip lines which are empty or contain only whitespace, as found in some exports between
+    /// properties. The input is left alone if a line has any other content.
+    pub fn skip_blank_lines(&mut self) {
+        loop {
+            let start_pos = self.pos;
+            self.consume_while(|x| x == ' ' || x == '\t');
+            if self.consume_eol().is_err() {
+                if !self.eof() {
+                    self.pos = start_pos;
+                }
+                return;
+            }
+        }
     }
 
-    fn consume_property_value(&mut self) -> ParseResult<String> {
+    fn consume_property_value(&mut self) -> ParseResult<Cow<'s, str>> {
+        let start_pos = self.pos;
         let rv = self.consume_while(|x| x != '\r' && x != '\n');
+        self.check_control_chars(start_pos)?;
         self.sloppy_terminate_line()?;
         Ok(rv)
     }
 
-    fn consume_param_name(&mut self) -> ParseResult<String> {
+    fn consume_param_name(&mut self) -> ParseResult<Cow<'s, str>> {
         self.consume_property_name()
-            .map_err(|_| ParseErrorReason::NoParameterName)
+            .map_err(|_| ParseErrorReason::MissingParameterName)
     }
 
-    fn consume_param_value(&mut self) -> ParseResult<String> {
+    fn consume_param_value(&mut self) -> ParseResult<Cow<'s, str>> {
         let qsafe = |x| {
             x != '"' &&
             x != '\r' &&
@@ -250,79 +436,205 @@ impl<'s> Parser<'s> {
         }
     }
 
-    fn consume_param(&mut self) -> ParseResult<(String, String)> {
-        let name = self.consume_param_name()?;
-        let start_pos = self.pos;
+    fn consume_param(&mut self) -> ParseResult<(Cow<'s, str>, Cow<'s, str>)> {
+        let name = uppercase(self.consume_param_name()?);
         let value = if self.consume_only_char('=') {
-            match self.consume_param_value() {
-                Ok(x) => x,
-                Err(e) => { self.pos = start_pos; return Err(e); }
-            }
+            // `NAME=` is a parameter with an empty value
+            self.consume_param_value()?
+        } else if self.options.require_param_values {
+            return Err(ParseErrorReason::MissingParameterValue { name: name.into_owned() });
         } else {
-            String::new()
+            Cow::Borrowed("")
         };
 
-        Ok((name, value))
+        // A parameter is followed by either the next parameter or the value
+        match self.peek() {
+            Some((';', _)) | Some((':', _)) => Ok((name, value)),
+            Some((c, _)) => Err(ParseErrorReason::UnexpectedChar { expected: ':', found: c }),
+            None => Err(ParseErrorReason::UnexpectedEof { expected: ':' }),
+        }
     }
 
-    fn consume_params(&mut self) -> BTreeMap<String, String> {
-        let mut rv: BTreeMap<String, String> = BTreeMap::new();
+    fn consume_params(&mut self) -> ParseResult<Vec<(Cow<'s, str>, Cow<'s, str>)>> {
+        let mut rv = vec![];
         while self.consume_only_char(';') {
-            match self.consume_param() {
-                Ok((name, value)) => { rv.insert(name.to_owned(), value.to_owned()); },
-                Err(_) => break,
-            }
+            let start_pos = self.pos;
+            let param = self.consume_param()
+                .map_err(|e| ParseErrorReason::InvalidParam { at: start_pos, reason: Box::new(e) })?;
+            rv.push(param);
         }
-        rv
+        Ok(rv)
     }
 
     pub fn consume_component(&mut self) -> ParseResult<Component> {
+        self.consume_component_ref().map(ComponentRef::into_owned)
+    }
+
+    /// Like `consume_component`, but borrow from the input wherever possible.
+    pub fn consume_component_ref(&mut self) -> ParseResult<ComponentRef<'s>> {
+        let mut state = EventState::default();
+        let mut stack: Vec<ComponentRef<'s>> = Vec::new();
+
+        while let Some(event) = self.next_event(&mut state) {
+            match event? {
+                ParseEvent::ComponentStart(name) => stack.push(ComponentRef::new(name)),
+                ParseEvent::Property(property) => {
+                    if let Some(component) = stack.last_mut() {
+                        component.props.push(property);
+                    }
+                },
+                ParseEvent::ComponentEnd(_) => {
+                    let component = match stack.pop() {
+                        Some(component) => component,
+                        None => break,
+                    };
+                    match stack.last_mut() {
+                        Some(parent) => parent.subcomponents.push(component),
+                        None => return Ok(component),
+                    }
+                },
+            }
+        }
+
+        unreachable!("the event stream ends only after the outermost END or an error")
+    }
+
+    /// Produce the next event of the component at the current position.
+    ///
+    /// Returns `None` once the outermost component has been closed or an error was returned.
+    pub fn next_event(&mut self, state: &mut EventState<'s>) -> Option<ParseResult<ParseEvent<'s>>> {
+        if state.done {
+            return None;
+        }
+
+        let rv = self.consume_event(state);
+        if rv.is_err() {
+            state.done = true;
+        }
+        Some(rv)
+    }
+
+    fn consume_event(&mut self, state: &mut EventState<'s>) -> ParseResult<ParseEvent<'s>> {
+        if state.stack.is_empty() {
+            return self.consume_begin(state);
+        }
+
+        if !self.options.strict_line_endings {
+            self.skip_blank_lines();
+        }
+
+        if self.eof() {
+            let open = &state.stack[state.stack.len() - 1];
+            let line = self.input[..open.start_pos].matches('\n').count() + 1;
+            return Err(ParseErrorReason::UnclosedComponent { name: open.name.clone().into_owned(), line });
+        }
+
+        let previous_pos = self.pos;
+        let property = self.consume_property()?;
+        if property.name == "BEGIN" {
+            self.pos = previous_pos;
+            return self.consume_begin(state);
+        }
+
+        let open = state.stack.len() - 1;
+        if property.name == "END" {
+            if uppercase(property.raw_value.clone()) != state.stack[open].name {
+                self.pos = state.stack[open].start_pos;
+                return Err(ParseErrorReason::MismatchedTag {
+                    begin: state.stack[open].name.clone().into_owned(),
+                    end: property.raw_value.into_owned(),
+                });
+            }
+
+            let closed = state.stack.remove(open);
+            state.done = state.stack.is_empty();
+            return Ok(ParseEvent::ComponentEnd(closed.name));
+        }
+
+        let open = &mut state.stack[open];
+        open.property_count += 1;
+        if open.property_count > self.options.max_properties {
+            return Err(ParseErrorReason::TooManyProperties {
+                component: open.name.clone().into_owned(),
+                max: self.options.max_properties,
+            });
+        }
+        Ok(ParseEvent::Property(property))
+    }
+
+    fn consume_begin(&mut self, state: &mut EventState<'s>) -> ParseResult<ParseEvent<'s>> {
+        if state.stack.len() >= self.options.max_depth {
+            return Err(ParseErrorReason::TooDeeplyNested { max_depth: self.options.max_depth });
+        }
+
         let start_pos = self.pos;
-        let mut property = self.consume_property()?;
+        if start_pos == 0 && self.input.starts_with('\u{feff}') {
+            return Err(ParseErrorReason::ByteOrderMark);
+        }
+
+        let property = self.consume_property()?;
         if property.name != "BEGIN" {
             self.pos = start_pos;
             return Err(ParseErrorReason::ExpectedBegin);
         };
 
-        // Create a component with the name of the BEGIN tag's value
-        let mut component = Component::new(property.raw_value);
+        // Open a component with the name of the BEGIN tag's value
+        let name = uppercase(property.raw_value);
+        state.stack.push(OpenComponent { name: name.clone(), start_pos, property_count: 0 });
+        Ok(ParseEvent::ComponentStart(name))
+    }
+}
 
-        loop {
-            let previous_pos = self.pos;
-            property = self.consume_property()?;
-            if property.name == "BEGIN" {
-                self.pos = previous_pos;
-                component.subcomponents.push(self.consume_component()?);
-            } else if property.name == "END" {
-                if property.raw_value != component.name {
-                    self.pos = start_pos;
-                    return Err(ParseErrorReason::MismatchedTag(component.name, property.raw_value));
-                }
+/// A single step of parsing a component, as produced by `EventParser`.
+///
+/// Component names are uppercased, like `Component::name`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseEvent<'s> {
+    /// A `BEGIN` line.
+    ComponentStart(Cow<'s, str>),
+    /// The `END` line matching the most recent open `ComponentStart`.
+    ComponentEnd(Cow<'s, str>),
+    /// A property of the most recent open component.
+    Property(PropertyRef<'s>),
+}
 
-                break;
-            } else {
-                component.push(property);
-            }
-        }
+/// Components opened but not yet closed while producing `ParseEvent`s.
+#[derive(Debug, Default)]
+pub struct EventState<'s> {
+    stack: Vec<OpenComponent<'s>>,
+    done: bool,
+}
 
-        Ok(component)
+#[derive(Debug)]
+struct OpenComponent<'s> {
+    name: Cow<'s, str>,
+    start_pos: usize,
+    property_count: usize,
+}
+
+/// Uppercase a name, without allocating if it is uppercase already.
+fn uppercase(s: Cow<'_, str>) -> Cow<'_, str> {
+    match s {
+        Cow::Borrowed(s) => normalize_name(s),
+        Cow::Owned(s) => Cow::Owned(s.to_uppercase()),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Parser;
+    #[cfg(not(feature = "std"))] use std::prelude::*;
+    use super::{Parser, ParserOptions};
 
     #[test]
     fn test_unfold1() {
-        let mut p = Parser{input: "ab\r\n c", pos: 2};
+        let mut p = Parser { input: "ab\r\n c", pos: 2, options: ParserOptions::default() };
         assert_eq!(p.consume_char(), Some('c'));
         assert_eq!(p.pos, 6);
     }
 
     #[test]
     fn test_unfold2() {
-        let mut p = Parser{input: "ab\n\tc\nx", pos: 2};
+        let mut p = Parser { input: "ab\n\tc\nx", pos: 2, options: ParserOptions::default() };
         assert_eq!(p.consume_char(), Some('c'));
         assert_eq!(p.consume_char(), Some('\n'));
         assert_eq!(p.consume_char(), Some('x'));
@@ -330,7 +642,7 @@ mod tests {
 
     #[test]
     fn test_consume_while() {
-        let mut p = Parser{input:"af\n oo:bar", pos: 1};
+        let mut p = Parser { input: "af\n oo:bar", pos: 1, options: ParserOptions::default() };
         assert_eq!(p.consume_while(|x| x != ':'), "foo");
         assert_eq!(p.consume_char(), Some(':'));
         assert_eq!(p.consume_while(|x| x != '\n'), "bar");
@@ -338,7 +650,7 @@ mod tests {
 
     #[test]
     fn test_consume_while2() {
-        let mut p = Parser{input:"af\n oo\n\t:bar", pos: 1};
+        let mut p = Parser { input: "af\n oo\n\t:bar", pos: 1, options: ParserOptions::default() };
         assert_eq!(p.consume_while(|x| x != ':'), "foo");
         assert_eq!(p.consume_char(), Some(':'));
         assert_eq!(p.consume_while(|x| x != '\n'), "bar");
@@ -346,15 +658,104 @@ mod tests {
 
     #[test]
     fn test_consume_while3() {
-        let mut p = Parser{input:"af\n oo:\n bar", pos: 1};
+        let mut p = Parser { input: "af\n oo:\n bar", pos: 1, options: ParserOptions::default() };
         assert_eq!(p.consume_while(|x| x != ':'), "foo");
         assert_eq!(p.consume_char(), Some(':'));
         assert_eq!(p.consume_while(|x| x != '\n'), "bar");
     }
 
+    /// The previous, recursive implementation of `peek_at`, to check the optimized one against.
+    fn reference_peek_at(input: &str, at: usize) -> Option<(char, usize)> {
+        match input[at..].chars().next() {
+            None => None,
+            Some('\r') => reference_peek_at(input, at + 1),
+            Some('\n') => {
+                match reference_peek_at(input, at + 1) {
+                    Some((' ', offset)) |
+                    Some(('\t', offset)) => reference_peek_at(input, offset),
+                    _ => Some(('\n', at + 1)),
+                }
+            }
+            Some(x) => Some((x, at + x.len_utf8()))
+        }
+    }
+
+    #[test]
+    fn test_peek_at_matches_reference() {
+        let corpus = [
+            "",
+            "BEGIN:VCARD\r\nFN:Erika\r\n Mustermann\r\nEND:VCARD\r\n",
+            "a\n b\n\tc\n\n d\n",
+            "a\r\r\r\n\r \r\nx\r",
+            "\n \n \n\t",
+            "Köln;毎害止\r\n 加食\n\t😀x\r\n",
+            "trailing fold\r\n ",
+            "\r\n\r\n",
+        ];
+
+        for input in corpus.iter() {
+            for pos in (0..=input.len()).filter(|&i| input.is_char_boundary(i)) {
+                let p = Parser { input, pos, options: ParserOptions::default() };
+                assert_eq!(p.peek(), reference_peek_at(input, pos).map(|(c, end)| (c, end - pos)),
+                           "{:?} at {}", input, pos);
+            }
+        }
+
+        let long_cr_run = format!("a{}b", "\r".repeat(100_000));
+        let p = Parser { input: &long_cr_run, pos: 1, options: ParserOptions::default() };
+        assert_eq!(p.peek(), Some(('b', 100_001)));
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let input = "\u{feff}BEGIN:VCARD\r\nFN:Erika\r\nEND:VCARD\r\n";
+        let mut p = Parser::with_options(input, ParserOptions::default());
+        assert_eq!(p.consume_component().unwrap().name, "VCARD");
+        assert!(p.eof());
+
+        let mut p = Parser::with_options(input, ParserOptions::default());
+        assert_eq!(p.consume_begin_name().unwrap(), "VCARD");
+
+        let options = ParserOptions { strict_line_endings: true, ..ParserOptions::default() };
+        assert!(Parser::with_options(input, options).consume_component().is_ok());
+        let options = ParserOptions { forbid_byte_order_mark: true, ..ParserOptions::default() };
+        assert!(Parser::with_options(input, options).consume_component().is_err());
+
+        // Only at the very start
+        let input = "BEGIN:VCARD\r\n\u{feff}FN:Erika\r\nEND:VCARD\r\n";
+        assert!(Parser::with_options(input, ParserOptions::default()).consume_component().is_err());
+    }
+
+    #[test]
+    fn test_blank_lines_between_properties() {
+        use super::ParseErrorReason;
+
+        let input = "BEGIN:VCARD\r\n\
+                     VERSION:3.0\r\n\
+                     TEL;TYPE=CELL:+49 170 1234567\r\n\
+                     \r\n\
+                     \t \r\n\
+                     \r\n\
+                     TEL;TYPE=HOME:+49 221 1234567\r\n\
+                     \r\n\
+                     END:VCARD\r\n";
+
+        let mut p = Parser::with_options(input, ParserOptions::default());
+        let card = p.consume_component().unwrap();
+        assert_eq!(card.get_all("TEL").len(), 2);
+        assert!(p.eof());
+
+        let mut p = Parser::with_options(input, ParserOptions::strict());
+        assert!(p.consume_component().is_err());
+
+        let input = "BEGIN:VCARD\r\nTEL:1\r\n\r\n :x\r\nEND:VCARD\r\n";
+        let mut p = Parser::with_options(input, ParserOptions::default());
+        assert!(matches!(p.consume_component(), Err(ParseErrorReason::MissingPropertyName)));
+    }
+
     #[test]
     fn test_consume_only_char() {
-        let mut p = Parser{input:"\n \"bar", pos: 0};
+        let mut p = Parser { input: "\n \"bar", pos: 0, options: ParserOptions::default() };
         assert!(p.consume_only_char('"'));
         assert_eq!(p.pos, 3);
         assert!(!p.consume_only_char('"'));
@@ -363,30 +764,205 @@ mod tests {
         assert_eq!(p.pos, 4);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn mismatched_begin_end_tags_returns_error() {
         // Test for infinite loops as well
         use std::sync::mpsc::{channel, RecvTimeoutError};
         use std::time::Duration;
         use super::ParseErrorReason;
-        let mut p = Parser {input: "BEGIN:a\nBEGIN:b\nEND:a", pos: 0};
+        let mut p = Parser { input: "BEGIN:a\nBEGIN:b\nEND:a", pos: 0, options: ParserOptions::default() };
 
         let (tx, rx) = channel();
         ::std::thread::spawn(move|| { tx.send(p.consume_component()) });
 
         match rx.recv_timeout(Duration::from_millis(50)) {
-            Err(RecvTimeoutError::Timeout) => assert!(false),
-            Ok(Err(e)) => {
-                match e {
-                    ParseErrorReason::MismatchedTag(begin, end) => {
-                        assert_eq!(begin, "b");
-                        assert_eq!(end, "a");
-                    },
-                    _ => assert!(false),
-                }
+            Err(RecvTimeoutError::Timeout) => panic!("parser did not terminate"),
+            Ok(Err(ParseErrorReason::MismatchedTag { begin, end })) => {
+                assert_eq!(begin, "B");
+                assert_eq!(end, "a");
             },
-            _ => assert!(false),
+            x => panic!("unexpected result: {:?}", x),
         }
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn deeply_nested_components_return_error() {
+        use std::sync::mpsc::{channel, RecvTimeoutError};
+        use std::time::Duration;
+        use super::ParseErrorReason;
+
+        let input = "BEGIN:A\n".repeat(100_000);
+        let (tx, rx) = channel();
+        ::std::thread::spawn(move|| {
+            let mut p = Parser::with_options(&input, ParserOptions::default());
+            tx.send(p.consume_component())
+        });
+
+        match rx.recv_timeout(Duration::from_millis(500)) {
+            Err(RecvTimeoutError::Timeout) => panic!("parser did not terminate"),
+            Ok(Err(ParseErrorReason::TooDeeplyNested { max_depth: 16 })) => (),
+            x => panic!("unexpected result: {:?}", x),
+        }
+
+        let nested = "BEGIN:A\nBEGIN:B\nBEGIN:C\nEND:C\nEND:B\nEND:A\n";
+        let options = ParserOptions { max_depth: 2, ..ParserOptions::default() };
+        assert!(Parser::with_options(nested, options).consume_component().is_err());
+        let options = ParserOptions { max_depth: 3, ..ParserOptions::default() };
+        assert!(Parser::with_options(nested, options).consume_component().is_ok());
+    }
+
+    #[test]
+    fn too_many_properties_return_error() {
+        use super::ParseErrorReason;
+
+        let input = format!("BEGIN:A\n{}END:A\n", "X-FOO:bar\n".repeat(20_000));
+        match Parser::with_options(&input, ParserOptions::default()).consume_component() {
+            Err(ParseErrorReason::TooManyProperties { component, max: 10_000 }) => assert_eq!(component, "A"),
+            x => panic!("unexpected result: {:?}", x),
+        }
+
+        let options = ParserOptions { max_properties: 20_000, ..ParserOptions::default() };
+        assert!(Parser::with_options(&input, options).consume_component().is_ok());
+    }
+
+    #[test]
+    fn malformed_parameters_return_error() {
+        use super::ParseErrorReason;
+
+        let parse = |line: &str| {
+            let mut p = Parser::with_options(line, ParserOptions::default());
+            p.consume_property().map(|prop| prop.into_owned())
+        };
+
+        let malformed = [
+            "X;;;;:v",
+            "X;=;=:v",
+            "X;:v",
+            "X;=v:v",
+            "X;A=\"b:v",
+            "X;A=\"b\"c:v",
+            "X;A B=c:v",
+            "X;A=b\u{7}c:v",
+            "X;A=b",
+            "X;A",
+            "X;A=\"b\"",
+            "X;-\u{0}:v",
+        ];
+        for line in &malformed {
+            match parse(line) {
+                Err(ParseErrorReason::InvalidParam { at, .. }) => assert!(at <= line.len()),
+                x => panic!("unexpected result for {:?}: {:?}", line, x),
+            }
+        }
+
+        match parse("X;A=1;;B=2:v") {
+            Err(e) => assert_eq!(e.to_string(), "invalid parameter at byte 6: no parameter name found"),
+            x => panic!("unexpected result: {:?}", x),
+        }
+
+        let prop = parse("X;A=;B;C=\"\":v").unwrap();
+        assert_eq!(prop.params.get("A"), Some(""));
+        assert_eq!(prop.params.get("B"), Some(""));
+        assert_eq!(prop.params.get("C"), Some(""));
+        assert_eq!(prop.raw_value, "v");
+    }
+
+    #[test]
+    fn truncated_input_reports_unclosed_component() {
+        use super::ParseErrorReason;
+
+        let input = "BEGIN:VCALENDAR\r\n\
+                     VERSION:2.0\r\n\
+                     BEGIN:VEVENT\r\n\
+                     UID:x\r\n\
+                     BEGIN:VALARM\r\n\
+                     ACTION:DISPLAY\r\n\
+                     END:VALARM\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n";
+        let lines = input.split_terminator("\r\n").collect::<Vec<_>>();
+
+        let expected = [
+            (2, "VCALENDAR", 1),
+            (4, "VEVENT", 3),
+            (6, "VALARM", 5),
+            (7, "VEVENT", 3),
+            (8, "VCALENDAR", 1),
+        ];
+        for &(truncate_at, name, line) in &expected {
+            let truncated = lines[..truncate_at].join("\r\n") + "\r\n";
+            let mut p = Parser::with_options(&truncated, ParserOptions::default());
+            match p.consume_component() {
+                Err(ParseErrorReason::UnclosedComponent { name: n, line: l }) => {
+                    assert_eq!((&n[..], l), (name, line), "truncated after line {}", truncate_at);
+                },
+                x => panic!("unexpected result: {:?}", x),
+            }
+        }
+
+        let mut p = Parser::with_options("BEGIN:VCARD\nFN:x\n", ParserOptions::default());
+        assert_eq!(p.consume_component().unwrap_err().to_string(),
+                   "unexpected end of input: component VCARD begun at line 1 was never closed with END:VCARD");
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        use super::ParseErrorReason::*;
+        use component::parse_component;
+
+        let lax = ParserOptions::default();
+        let strict = ParserOptions::strict();
+        let cases = vec![
+            ("BEGIN", lax, UnexpectedEof { expected: ':' }),
+            ("BEGIN X:A\n", lax, UnexpectedChar { expected: ':', found: ' ' }),
+            ("BEGIN:A", strict, ExpectedEol),
+            (":A\n", lax, MissingPropertyName),
+            ("FN:A\n", lax, ExpectedBegin),
+            ("BEGIN:A\nEND:B\n", lax, MismatchedTag { begin: "A".into(), end: "B".into() }),
+            ("BEGIN:A\r\nEND:A\n", strict, LoneLineFeed),
+            ("BEGIN:A\r\n\r\nEND:A\r\n", strict, EmptyLine),
+            ("BEGIN:A\r\nX:\u{7}\r\nEND:A\r\n", strict, ControlCharacter { found: '\u{7}' }),
+            ("BEGIN:A\nX:y\n", lax, UnclosedComponent { name: "A".into(), line: 1 }),
+            ("BEGIN:A\nX;=y:z\n", lax, InvalidParam { at: 10, reason: Box::new(MissingParameterName) }),
+            ("BEGIN:A\r\nX;Y:z\r\nEND:A\r\n", strict,
+             InvalidParam { at: 11, reason: Box::new(MissingParameterValue { name: "Y".into() }) }),
+            ("BEGIN:A\nX;Y=z", lax, InvalidParam { at: 10, reason: Box::new(UnexpectedEof { expected: ':' }) }),
+            ("BEGIN:A\nBEGIN:B\nEND:B\nEND:A\n", ParserOptions { max_depth: 1, ..lax },
+             TooDeeplyNested { max_depth: 1 }),
+            ("BEGIN:A\nX:1\nX:2\nEND:A\n", ParserOptions { max_properties: 1, ..lax },
+             TooManyProperties { component: "A".into(), max: 1 }),
+            ("\u{feff}BEGIN:A\r\nEND:A\r\n", strict, ByteOrderMark),
+        ];
+
+        for (input, options, expected) in cases {
+            let err = Parser::with_options(input, options).consume_component().unwrap_err();
+            assert_eq!(err, expected, "{:?}", input);
+        }
+
+        let err = parse_component("BEGIN:A\nEND:A\nFOO").unwrap_err();
+        let reason = err.parse_error().unwrap();
+        assert_eq!(*reason, TrailingData { at: 14, data: "FOO".into() });
+        assert_eq!(reason.position(), Some(14));
+        assert!(!reason.is_truncated());
+
+        let err = parse_component("BEGIN:A\nX;Y=z").unwrap_err();
+        assert!(err.parse_error().unwrap().is_truncated());
+        assert!(parse_component("BEGIN:A\nX:y\n").unwrap_err().parse_error().unwrap().is_truncated());
+        assert!(!parse_component("FN:A\n").unwrap_err().parse_error().unwrap().is_truncated());
+    }
+
+    #[test]
+    fn test_case_insensitive_names() {
+        let mut p = Parser { input: "begin:vcard\nfoo.tel;type=cell:+1\nEnd:VCard\n", pos: 0, options: ParserOptions::default() };
+        let c = p.consume_component().unwrap();
+        assert_eq!(c.name, "VCARD");
+
+        let tel = c.get_only("TEL").unwrap();
+        assert_eq!(tel.name, "TEL");
+        assert_eq!(tel.prop_group, Some("foo".to_owned()));
+        assert_eq!(tel.params.get("TYPE"), Some("cell"));
+    }
+
 }
@@ -1,12 +1,22 @@
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+#[cfg(not(feature = "std"))] use std::prelude::*;
 
-#[derive(Clone, Debug)]
+use component::{property_line, push_folded, WriteOptions};
+use error::{VObjectError, VObjectResult};
+use param::{Parameters, BARE_ENCODINGS};
+use parser::{ParseErrorReason, Parser, ParserOptions};
+use util::{join_text_list, split_text_list};
+
+/// Properties whose value consists of `;`-separated components.
+const STRUCTURED_PROPERTIES: &[&str] = &["N", "ADR", "ORG", "GEO", "REQUEST-STATUS"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Property {
     /// Key in component.
     pub name: String,
 
     /// Parameters.
-    pub params: BTreeMap<String, String>,
+    pub params: Parameters,
 
     /// Value as unparsed string.
     pub raw_value: String,
@@ -24,41 +34,234 @@ impl Property {
     {
         Property {
             name: name.into(),
-            params: BTreeMap::new(),
+            params: Parameters::new(),
             raw_value: escape_chars(value.as_ref()),
             prop_group: None
         }
     }
 
+    /// Create a property holding a comma-separated list, such as `CATEGORIES` or `NICKNAME`,
+    /// escaping commas within the entries.
+    pub fn from_values<N: Into<String>>(name: N, values: &[&str]) -> Property {
+        Property {
+            name: name.into(),
+            params: Parameters::new(),
+            raw_value: join_text_list(values),
+            prop_group: None
+        }
+    }
+
+    /// Split a comma-separated list at unescaped commas and unescape each entry, so that
+    /// `a\,b,c` yields `a,b` and `c`.
+    ///
+    /// An empty value yields no entries rather than one empty entry, while empty entries
+    /// elsewhere are kept: `a,` yields `a` and an empty string.
+    pub fn values(&self) -> Vec<String> {
+        split_text_list(&self.raw_value)
+    }
+
+    /// Get value as unescaped string.
+    ///
+    /// An `ENCODING` such as quoted-printable is not decoded, so the result is only plain text if
+    /// there is none. Use `set_value` rather than `Property::new` to store a modified value.
+    pub fn value_as_string(&self) -> String {
+        unescape_chars(&self.raw_value)
+    }
+
+    /// Replace the value with the given unescaped text.
+    ///
+    /// This is meant for single text values: the whole value is escaped, including `;` and `,`.
+    /// Structured properties like `N`, `ADR`, `ORG`, `GEO` and `REQUEST-STATUS` are rejected, as
+    /// their separators would be escaped as well; set their `raw_value` instead.
+    ///
+    /// The new value is stored as plain UTF-8, so encoding-related parameters (`ENCODING`,
+    /// `CHARSET` and bare vCard 2.1 encodings like `QUOTED-PRINTABLE`) are removed. All other
+    /// parameters and the group are kept.
+    pub fn set_value(&mut self, value: &str) -> VObjectResult<()> {
+        if let Some(name) = STRUCTURED_PROPERTIES.iter().find(|n| self.name.eq_ignore_ascii_case(n)) {
+            return Err(VObjectError::InvalidValue(name, value.to_owned()));
+        }
+
+        self.raw_value = escape_chars(value);
+        self.params.remove("ENCODING");
+        self.params.remove("CHARSET");
+        let bare = self.params
+            .iter()
+            .filter(|&(k, v)| v.is_empty() && BARE_ENCODINGS.contains(&&k.to_ascii_uppercase()[..]))
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+        for k in bare {
+            self.params.remove(&k);
+        }
+        Ok(())
+    }
+
+    /// Chainable variant of `Property::set_value()`.
+    pub fn with_value(mut self, value: &str) -> VObjectResult<Property> {
+        self.set_value(value)?;
+        Ok(self)
+    }
+
+    /// Parse a single content line, such as `TEL;TYPE=cell:+1 555 1234`. The line may be
+    /// folded and may end with a line break.
+    pub fn parse_line(s: &str) -> VObjectResult<Property> {
+        let mut parser = Parser::with_options(s, ParserOptions::default());
+        let prop = parser.consume_property()?.into_owned();
+        if !parser.eof() {
+            let at = parser.pos;
+            return Err(ParseErrorReason::TrailingData { at, data: s[at..].into() }.into());
+        }
+        Ok(prop)
+    }
+
+    /// Write the content line for this property as `write_component_with` would, folded
+    /// according to `opts` but without the final line ending.
+    pub fn write_line(&self, opts: &WriteOptions) -> String {
+        let line = property_line(self);
+        match opts.fold_width {
+            Some(width) => {
+                let mut buf = String::with_capacity(line.len());
+                push_folded(&mut buf, &line, width, opts.fold_mode, opts.line_ending.as_str());
+                buf
+            },
+            None => line,
+        }
+    }
+
+    /// Get the value of a parameter, ignoring the case of the name.
+    ///
+    /// If the parameter was given more than once, the values are joined with commas.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)
+    }
+
+    /// Get the values of a parameter which is a comma-separated list, such as `TYPE`, also if
+    /// the parameter was given more than once.
+    pub fn param_values(&self, name: &str) -> Vec<&str> {
+        self.params.get_values(name)
+    }
+
+    /// The uppercased types, from `TYPE` and from bare vCard 2.1 parameters, so that both
+    /// `TEL;WORK;VOICE:...` and `TEL;TYPE=WORK,VOICE:...` yield `WORK` and `VOICE`.
+    ///
+    /// Bare parameters come in alphabetical order, since the order of parameters is not kept.
+    pub fn types(&self) -> Vec<String> {
+        self.params.types()
+    }
+
+    /// The vCard 4.0 `PID` parameter as pairs of property ID and optional source ID, which
+    /// refers to a `CLIENTPIDMAP`: `PID=1.1,2` yields `[(1, Some(1)), (2, None)]`.
+    ///
+    /// Malformed entries are skipped.
+    pub fn pids(&self) -> Vec<(u32, Option<u32>)> {
+        self.param_values("PID")
+            .into_iter()
+            .filter_map(|pid| {
+                let mut parts = pid.trim().splitn(2, '.');
+                let id = parts.next()?.parse().ok()?;
+                match parts.next() {
+                    Some(source) => source.parse().ok().map(|source| (id, Some(source))),
+                    None => Some((id, None)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A property which borrows from the parsed input where possible, see
+/// `parse_component_borrowed`.
+///
+/// Parameters are kept in the order they appeared in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyRef<'s> {
+    pub name: Cow<'s, str>,
+    pub params: Vec<(Cow<'s, str>, Cow<'s, str>)>,
+    pub raw_value: Cow<'s, str>,
+    pub prop_group: Option<Cow<'s, str>>,
+}
+
+impl<'s> PropertyRef<'s> {
+    /// Get the value of a parameter, ignoring the case of the name. If the parameter was given
+    /// more than once, this is the last value; `into_owned` merges them instead.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .rev()
+            .find(|&(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| &v[..])
+    }
+
     /// Get value as unescaped string.
     pub fn value_as_string(&self) -> String {
         unescape_chars(&self.raw_value)
     }
+
+    /// Copy into an owned `Property`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn to_owned(&self) -> Property {
+        self.clone().into_owned()
+    }
+
+    pub fn into_owned(self) -> Property {
+        Property {
+            name: self.name.into_owned(),
+            params: self.params.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect(),
+            raw_value: self.raw_value.into_owned(),
+            prop_group: self.prop_group.map(Cow::into_owned),
+        }
+    }
+}
+
+/// A wrapper type for a property with a fixed name, usually defined with `create_data_type!`.
+pub trait TypedProperty: From<Property> {
+    /// The property name, such as `SUMMARY`.
+    const NAME: &'static str;
 }
 
 /// Escape text for a VObject property value.
+///
+/// Backslashes, semicolons and commas get a backslash in front, and newlines become `\n`, with
+/// `\r\n` counting as a single newline. Nothing else is changed, so `unescape_chars` restores
+/// any input which does not contain `\r\n`.
 pub fn escape_chars(s: &str) -> String {
-    // Order matters! Lifted from icalendar.parser
-    // https://github.com/collective/icalendar/
-    s
-        .replace("\\N", "\n")
-        .replace("\\", "\\\\")
-        .replace(";", "\\;")
-        .replace(",", "\\,")
-        .replace("\r\n", "\\n")
-        .replace("\n", "\\n")
+    let mut rv = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | ';' | ',' => {
+                rv.push('\\');
+                rv.push(c);
+            },
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' => rv.push_str("\\n"),
+            c => rv.push(c),
+        }
+    }
+    rv
 }
 
 /// Unescape text from a VObject property value.
+///
+/// This reverses `escape_chars`, and also reads `\N` as a newline, as RFC 5545 allows. Other
+/// backslashes are kept as they are.
 pub fn unescape_chars(s: &str) -> String {
-    // Order matters! Lifted from icalendar.parser
-    // https://github.com/collective/icalendar/
-    s
-        .replace("\\N", "\\n")
-        .replace("\r\n", "\n")
-        .replace("\\n", "\n")
-        .replace("\\,", ",")
-        .replace("\\;", ";")
-        .replace("\\\\", "\\")
+    let mut rv = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            rv.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => rv.push('\n'),
+            Some(escaped @ ('\\' | ';' | ',')) => rv.push(escaped),
+            Some(other) => {
+                rv.push('\\');
+                rv.push(other);
+            },
+            None => rv.push('\\'),
+        }
+    }
+    rv
 }
 
@@ -0,0 +1,311 @@
+//! Structured access to recurrence rules (`RRULE`) as defined in
+//! [RFC 5545](https://tools.ietf.org/html/rfc5545#section-3.3.10).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Weekday;
+
+use error::*;
+use icalendar::{parse_time, Time};
+use param::Parameters;
+use util::{DATE_FMT, DATE_TIME_FMT, LOCAL_DATE_TIME_FMT};
+
+/// The `FREQ` part of a recurrence rule.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Frequency::Secondly => "SECONDLY",
+            Frequency::Minutely => "MINUTELY",
+            Frequency::Hourly   => "HOURLY",
+            Frequency::Daily    => "DAILY",
+            Frequency::Weekly   => "WEEKLY",
+            Frequency::Monthly  => "MONTHLY",
+            Frequency::Yearly   => "YEARLY",
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = VObjectError;
+
+    fn from_str(s: &str) -> VObjectResult<Frequency> {
+        Ok(match &s.to_ascii_uppercase()[..] {
+            "SECONDLY" => Frequency::Secondly,
+            "MINUTELY" => Frequency::Minutely,
+            "HOURLY"   => Frequency::Hourly,
+            "DAILY"    => Frequency::Daily,
+            "WEEKLY"   => Frequency::Weekly,
+            "MONTHLY"  => Frequency::Monthly,
+            "YEARLY"   => Frequency::Yearly,
+            _ => return Err(VObjectError::InvalidRrule(format!("unknown frequency: {}", s))),
+        })
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A parsed recurrence rule such as `FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20250101T000000Z`.
+///
+/// Parts which are not known to this library are kept in `extensions`, so writing the rule back
+/// does not lose data.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: Option<u32>,
+    pub count: Option<u32>,
+    pub until: Option<Time>,
+    pub by_second: Vec<u8>,
+    pub by_minute: Vec<u8>,
+    pub by_hour: Vec<u8>,
+
+    /// Weekdays, optionally with an ordinal: `-1FR` is `(Some(-1), Weekday::Fri)`.
+    pub by_day: Vec<(Option<i8>, Weekday)>,
+    pub by_month_day: Vec<i8>,
+    pub by_year_day: Vec<i16>,
+    pub by_week_no: Vec<i8>,
+    pub by_month: Vec<u8>,
+    pub by_set_pos: Vec<i16>,
+    pub wkst: Option<Weekday>,
+
+    /// Unknown parts, such as `X-NAME=VALUE`.
+    pub extensions: BTreeMap<String, String>,
+}
+
+impl RecurrenceRule {
+    /// Create a rule with the given frequency and no other parts.
+    pub fn new(freq: Frequency) -> RecurrenceRule {
+        RecurrenceRule {
+            freq,
+            interval: None,
+            count: None,
+            until: None,
+            by_second: vec![],
+            by_minute: vec![],
+            by_hour: vec![],
+            by_day: vec![],
+            by_month_day: vec![],
+            by_year_day: vec![],
+            by_week_no: vec![],
+            by_month: vec![],
+            by_set_pos: vec![],
+            wkst: None,
+            extensions: BTreeMap::new(),
+        }
+    }
+
+    /// Alias for `self.to_string()`.
+    pub fn to_rrule_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+fn invalid(key: &str, value: &str) -> VObjectError {
+    VObjectError::InvalidRrule(format!("invalid value for {}: {}", key, value))
+}
+
+fn parse_number<T: FromStr>(key: &str, value: &str) -> VObjectResult<T> {
+    value.trim_start_matches('+').parse().map_err(|_| invalid(key, value))
+}
+
+fn parse_list<T: FromStr>(key: &str, value: &str) -> VObjectResult<Vec<T>> {
+    value.split(',').map(|v| parse_number(key, v)).collect()
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    Some(match s {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn weekday_str(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Parse a `BYDAY` entry like `SU`, `2SU` or `-1SU`.
+fn parse_by_day(value: &str) -> VObjectResult<(Option<i8>, Weekday)> {
+    let value = value.to_ascii_uppercase();
+    if value.len() < 2 || !value.is_char_boundary(value.len() - 2) {
+        return Err(invalid("BYDAY", &value));
+    }
+
+    let (n, day) = value.split_at(value.len() - 2);
+    let day = parse_weekday(day).ok_or_else(|| invalid("BYDAY", &value))?;
+    let n = match n {
+        "" => None,
+        n => Some(parse_number("BYDAY", n)?),
+    };
+    Ok((n, day))
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = VObjectError;
+
+    fn from_str(s: &str) -> VObjectResult<RecurrenceRule> {
+        let mut freq = None;
+        let mut rule = RecurrenceRule::new(Frequency::Yearly);
+
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let mut kv = part.splitn(2, '=');
+            let key = kv.next().unwrap_or("").to_ascii_uppercase();
+            let value = kv.next().ok_or_else(|| invalid(&key, ""))?;
+
+            match &key[..] {
+                "FREQ"       => freq = Some(value.parse()?),
+                "INTERVAL"   => rule.interval = Some(parse_number(&key, value)?),
+                "COUNT"      => rule.count = Some(parse_number(&key, value)?),
+                "UNTIL"      => rule.until = Some(parse_time(value, &Parameters::new())?),
+                "BYSECOND"   => rule.by_second = parse_list(&key, value)?,
+                "BYMINUTE"   => rule.by_minute = parse_list(&key, value)?,
+                "BYHOUR"     => rule.by_hour = parse_list(&key, value)?,
+                "BYDAY"      => rule.by_day = value.split(',').map(parse_by_day).collect::<VObjectResult<_>>()?,
+                "BYMONTHDAY" => rule.by_month_day = parse_list(&key, value)?,
+                "BYYEARDAY"  => rule.by_year_day = parse_list(&key, value)?,
+                "BYWEEKNO"   => rule.by_week_no = parse_list(&key, value)?,
+                "BYMONTH"    => rule.by_month = parse_list(&key, value)?,
+                "BYSETPOS"   => rule.by_set_pos = parse_list(&key, value)?,
+                "WKST"       => {
+                    let day = parse_weekday(&value.to_ascii_uppercase()).ok_or_else(|| invalid(&key, value))?;
+                    rule.wkst = Some(day);
+                },
+                _ => { rule.extensions.insert(key, value.to_owned()); },
+            }
+        }
+
+        rule.freq = freq.ok_or_else(|| VObjectError::InvalidRrule(String::from("missing FREQ")))?;
+
+        // An ordinal selects a week of the month, or of the year for yearly rules
+        let max_ordinal = if rule.freq == Frequency::Yearly { 53 } else { 5 };
+        if let Some((n, day)) = rule.by_day.iter().find(|(n, _)| n.is_some_and(|n| n.unsigned_abs() > max_ordinal)) {
+            return Err(invalid("BYDAY", &format!("{}{}", n.unwrap_or_default(), weekday_str(*day))));
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, key: &str, values: &[T]) -> fmt::Result {
+            if values.is_empty() {
+                return Ok(());
+            }
+            let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+            write!(f, ";{}={}", key, values.join(","))
+        }
+
+        write!(f, "FREQ={}", self.freq)?;
+
+        if let Some(until) = self.until.as_ref() {
+            let until = match *until {
+                Time::Date(ref d) => d.format(DATE_FMT),
+                Time::Utc(ref dt) => dt.format(DATE_TIME_FMT),
+                Time::Floating(ref dt) | Time::Local(ref dt, _) => dt.format(LOCAL_DATE_TIME_FMT),
+            };
+            write!(f, ";UNTIL={}", until)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(interval) = self.interval {
+            write!(f, ";INTERVAL={}", interval)?;
+        }
+
+        write_list(f, "BYSECOND", &self.by_second)?;
+        write_list(f, "BYMINUTE", &self.by_minute)?;
+        write_list(f, "BYHOUR", &self.by_hour)?;
+
+        let by_day = self.by_day
+            .iter()
+            .map(|&(n, day)| match n {
+                Some(n) => format!("{}{}", n, weekday_str(day)),
+                None => String::from(weekday_str(day)),
+            })
+            .collect::<Vec<_>>();
+        write_list(f, "BYDAY", &by_day)?;
+
+        write_list(f, "BYMONTHDAY", &self.by_month_day)?;
+        write_list(f, "BYYEARDAY", &self.by_year_day)?;
+        write_list(f, "BYWEEKNO", &self.by_week_no)?;
+        write_list(f, "BYMONTH", &self.by_month)?;
+        write_list(f, "BYSETPOS", &self.by_set_pos)?;
+
+        if let Some(wkst) = self.wkst {
+            write!(f, ";WKST={}", weekday_str(wkst))?;
+        }
+
+        for (key, value) in &self.extensions {
+            write!(f, ";{}={}", key, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Weekday};
+
+    use icalendar::Time;
+    use super::*;
+
+    #[test]
+    fn test_parse_rrule() {
+        let rule: RecurrenceRule = "FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20250101T000000Z".parse().unwrap();
+        assert_eq!(rule.freq, Frequency::Monthly);
+        assert_eq!(rule.by_day, vec![(Some(-1), Weekday::Fri)]);
+        assert_eq!(rule.until, Some(Time::Utc(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap())));
+        assert_eq!(rule.interval, None);
+        assert!(rule.extensions.is_empty());
+        assert_eq!(rule.to_rrule_string(), "FREQ=MONTHLY;UNTIL=20250101T000000Z;BYDAY=-1FR");
+    }
+
+    #[test]
+    fn test_rrule_extensions_and_errors() {
+        let rule: RecurrenceRule = "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;WKST=SU;X-FOO=bar".parse().unwrap();
+        assert_eq!(rule.interval, Some(2));
+        assert_eq!(rule.by_day, vec![(None, Weekday::Mon), (None, Weekday::Wed)]);
+        assert_eq!(rule.wkst, Some(Weekday::Sun));
+        assert_eq!(rule.extensions.get("X-FOO").map(|s| &s[..]), Some("bar"));
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;WKST=SU;X-FOO=bar");
+
+        assert!("INTERVAL=2".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=FORTNIGHTLY".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;COUNT=many".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=MONTHLY;BYDAY=-128SU".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=MONTHLY;BYDAY=6SU".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=YEARLY;BYDAY=-53SU".parse::<RecurrenceRule>().is_ok());
+        assert!("BYDAY=54SU;FREQ=YEARLY".parse::<RecurrenceRule>().is_err());
+    }
+
+}
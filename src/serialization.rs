@@ -0,0 +1,220 @@
+//! `Serialize` and `Deserialize` implementations, available with the `serde` feature.
+//!
+//! Components are represented as
+//!
+//! ```json
+//! {
+//!   "name": "VCARD",
+//!   "props": {
+//!     "FN": [{"params": {}, "value": "Erika Mustermann", "group": null}]
+//!   },
+//!   "subcomponents": []
+//! }
+//! ```
+//!
+//! A standalone `Property` additionally carries its `name`.
+
+use std::collections::BTreeMap;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use component::{normalize_name, Component};
+use icalendar::ICalendar;
+use property::Property;
+use vcard::Vcard;
+
+#[derive(Serialize)]
+struct PropertyValueRef<'a> {
+    params: &'a BTreeMap<String, String>,
+    value: &'a str,
+    group: &'a Option<String>,
+}
+
+impl<'a> From<&'a Property> for PropertyValueRef<'a> {
+    fn from(p: &'a Property) -> Self {
+        PropertyValueRef {
+            params: &p.params,
+            value: &p.raw_value,
+            group: &p.prop_group,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PropertyValue {
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+    value: String,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+impl PropertyValue {
+    fn into_property(self, name: String) -> Property {
+        Property {
+            name,
+            params: self.params.into(),
+            raw_value: self.value,
+            prop_group: self.group,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PropertyRef<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    value: PropertyValueRef<'a>,
+}
+
+#[derive(Deserialize)]
+struct PropertyRepr {
+    name: String,
+    #[serde(flatten)]
+    value: PropertyValue,
+}
+
+impl Serialize for Property {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PropertyRef { name: &self.name, value: self.into() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Property {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Property, D::Error> {
+        let repr = PropertyRepr::deserialize(deserializer)?;
+        Ok(repr.value.into_property(repr.name))
+    }
+}
+
+#[derive(Serialize)]
+struct ComponentRef<'a> {
+    name: &'a str,
+    props: BTreeMap<&'a str, Vec<PropertyValueRef<'a>>>,
+    subcomponents: &'a [Component],
+}
+
+#[derive(Deserialize)]
+struct ComponentRepr {
+    name: String,
+    #[serde(default)]
+    props: BTreeMap<String, Vec<PropertyValue>>,
+    #[serde(default)]
+    subcomponents: Vec<Component>,
+}
+
+impl Serialize for Component {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let props = self.props
+            .iter()
+            .map(|(name, values)| (&name[..], values.iter().map(From::from).collect()))
+            .collect();
+
+        ComponentRef {
+            name: &self.name,
+            props,
+            subcomponents: &self.subcomponents,
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Component, D::Error> {
+        let repr = ComponentRepr::deserialize(deserializer)?;
+        let mut component = Component::new(repr.name);
+        component.subcomponents = repr.subcomponents;
+
+        for (name, values) in repr.props {
+            let key = normalize_name(&name).into_owned();
+            let props = values.into_iter().map(|v| v.into_property(name.clone()));
+            component.props.entry(key).or_default().extend(props);
+        }
+
+        Ok(component)
+    }
+}
+
+impl Serialize for Vcard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Vcard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Vcard, D::Error> {
+        Vcard::from_component(Component::deserialize(deserializer)?)
+            .map_err(|c| D::Error::custom(format!("expected VCARD component, found {}", c.name)))
+    }
+}
+
+impl Serialize for ICalendar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ICalendar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ICalendar, D::Error> {
+        ICalendar::from_component(Component::deserialize(deserializer)?)
+            .map_err(|c| D::Error::custom(format!("expected VCALENDAR component, found {}", c.name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use component::{parse_component, Component};
+    use icalendar::ICalendar;
+    use property::Property;
+    use vcard::Vcard;
+
+    #[test]
+    fn test_component_roundtrip() {
+        let c = parse_component("BEGIN:VCARD\n\
+                                 VERSION:4.0\n\
+                                 item1.TEL;TYPE=cell;PREF=1:+49 170 1234567\n\
+                                 item1.X-ABLABEL:Mobil\n\
+                                 EMAIL:erika@example.com\n\
+                                 EMAIL:erika@example.org\n\
+                                 BEGIN:X-CHILD\n\
+                                 FOO:bar\n\
+                                 END:X-CHILD\n\
+                                 END:VCARD\n").unwrap();
+
+        let json = serde_json::to_string(&c).unwrap();
+        let back: Component = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, c);
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+        let tel = back.get_only("TEL").unwrap();
+        assert_eq!(tel.prop_group, Some("item1".to_owned()));
+        assert_eq!(tel.params.get("PREF"), Some("1"));
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let json = r#"{"name":"VEVENT","props":{"SUMMARY":[{"params":{"LANGUAGE":"de"},"value":"Treffen","group":null}]},"subcomponents":[{"name":"VALARM","props":{},"subcomponents":[]}]}"#;
+
+        let c: Component = serde_json::from_str(json).unwrap();
+        assert_eq!(c.get_only("SUMMARY").unwrap().raw_value, "Treffen");
+        assert_eq!(c.subcomponents[0].name, "VALARM");
+        assert_eq!(serde_json::to_string(&c).unwrap(), json);
+
+        let p: Property = serde_json::from_str(r#"{"name":"FN","value":"Erika"}"#).unwrap();
+        assert_eq!(p, Property::new("FN", "Erika"));
+        assert_eq!(serde_json::to_string(&p).unwrap(), r#"{"name":"FN","params":{},"value":"Erika","group":null}"#);
+    }
+
+    #[test]
+    fn test_typed_wrappers() {
+        let card = Vcard::build("BEGIN:VCARD\nVERSION:4.0\nFN:Erika\nEND:VCARD\n").unwrap();
+        let json = serde_json::to_string(&card).unwrap();
+        let back: Vcard = serde_json::from_str(&json).unwrap();
+        assert_eq!(*back, *card);
+
+        assert!(serde_json::from_str::<ICalendar>(&json).is_err());
+    }
+
+}
@@ -0,0 +1,130 @@
+//! Generating and comparing the `UID`s which identify cards, events and todos.
+
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
+#[cfg(feature = "std")] use util::random_uuid;
+
+/// The format of a UID made by `generate()`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum UidKind {
+    /// A `urn:uuid:` URI, as RFC 6350 recommends for vCard 4.
+    Vcard,
+
+    /// A bare UUID followed by `@` and the given host, such as
+    /// `4fbe8971-0bc3-424c-9c26-36c3e1eff6b1@example.com`. Without a host, just the UUID.
+    ICalendar(String),
+}
+
+/// Generate a new UID based on a random (version 4) UUID. Requires the `std` feature.
+///
+/// With the `uuid` feature, the UUID comes from the `uuid` crate, which uses the random number
+/// generator of the operating system. Otherwise it is derived from the current time and the random
+/// keys of std's hasher, which is good enough to be unique, but not unpredictable.
+///
+/// ```
+/// use vobject::uid::{generate, UidKind};
+///
+/// assert!(generate(UidKind::Vcard).starts_with("urn:uuid:"));
+/// assert!(generate(UidKind::ICalendar("example.com".into())).ends_with("@example.com"));
+/// ```
+#[cfg(feature = "std")]
+pub fn generate(kind: UidKind) -> String {
+    match kind {
+        UidKind::Vcard => uuid_uri(&random_uuid()),
+        UidKind::ICalendar(ref host) if host.is_empty() => random_uuid(),
+        UidKind::ICalendar(host) => format!("{}@{}", random_uuid(), host),
+    }
+}
+
+/// The form in which UIDs are compared: trimmed, without a `urn:uuid:` prefix, and lowercased.
+///
+/// ```
+/// use vobject::uid::normalize_uid;
+///
+/// assert_eq!(normalize_uid("URN:UUID:4FBE8971-0BC3-424C-9C26-36C3E1EFF6B1"),
+///            "4fbe8971-0bc3-424c-9c26-36c3e1eff6b1");
+/// ```
+pub fn normalize_uid(uid: &str) -> String {
+    let uid = uid.trim();
+    urn_uuid(uid).unwrap_or(uid).to_lowercase()
+}
+
+/// The UUID of a `urn:uuid:` URI, matching the prefix case-insensitively. `None` for other
+/// values.
+pub fn urn_uuid(uri: &str) -> Option<&str> {
+    let prefix = "urn:uuid:";
+    match uri.get(..prefix.len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(prefix) => Some(&uri[prefix.len()..]),
+        _ => None,
+    }
+}
+
+/// A URI referring to `uid`: values which are URIs already are kept, others are taken to be
+/// UUIDs and get a `urn:uuid:` prefix.
+///
+/// ```
+/// use vobject::uid::uuid_uri;
+///
+/// assert_eq!(uuid_uri("abc-123"), "urn:uuid:abc-123");
+/// assert_eq!(uuid_uri("mailto:erika@example.com"), "mailto:erika@example.com");
+/// ```
+pub fn uuid_uri(uid: &str) -> String {
+    if uid.contains(':') {
+        String::from(uid)
+    } else {
+        format!("urn:uuid:{}", uid)
+    }
+}
+
+/// Whether two UIDs refer to the same object, comparing them by `normalize_uid()`.
+pub fn uids_equal(a: &str, b: &str) -> bool {
+    normalize_uid(a) == normalize_uid(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_uid, uids_equal, urn_uuid, uuid_uri};
+
+    #[test]
+    fn test_normalize_uid() {
+        assert_eq!(normalize_uid("urn:uuid:abc-123"), "abc-123");
+        assert_eq!(normalize_uid("URN:UUID:ABC-123"), "abc-123");
+        assert_eq!(normalize_uid("Urn:Uuid:Abc-123"), "abc-123");
+        assert_eq!(normalize_uid("  abc-123\t"), "abc-123");
+        assert_eq!(normalize_uid("ABC-123@Example.com"), "abc-123@example.com");
+        assert_eq!(normalize_uid("urn:isbn:0451450523"), "urn:isbn:0451450523");
+        assert_eq!(normalize_uid("urn:uuid"), "urn:uuid");
+        assert_eq!(normalize_uid(""), "");
+
+        assert!(uids_equal("URN:UUID:ABC-123", "urn:uuid:abc-123"));
+        assert!(uids_equal("urn:uuid:abc-123", "ABC-123"));
+        assert!(!uids_equal("abc-123", "abc-124"));
+    }
+
+    #[test]
+    fn test_urn_uuid() {
+        assert_eq!(urn_uuid("URN:UUID:ABC-123"), Some("ABC-123"));
+        assert_eq!(urn_uuid("mailto:erika@example.com"), None);
+        assert_eq!(urn_uuid("urn:uu"), None);
+        assert_eq!(uuid_uri("ABC-123"), "urn:uuid:ABC-123");
+        assert_eq!(uuid_uri("urn:uuid:ABC-123"), "urn:uuid:ABC-123");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_generate() {
+        use super::{generate, UidKind};
+
+        let vcard = generate(UidKind::Vcard);
+        assert!(vcard.starts_with("urn:uuid:"));
+        assert_eq!(vcard.len(), "urn:uuid:".len() + 36);
+
+        let bare = generate(UidKind::ICalendar(String::new()));
+        assert_eq!(bare.len(), 36);
+        assert_eq!(bare.as_bytes()[14], b'4');
+
+        let event = generate(UidKind::ICalendar("example.com".into()));
+        assert!(event.ends_with("@example.com"));
+        assert!(!uids_equal(&event, &generate(UidKind::ICalendar("example.com".into()))));
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
 macro_rules! make_getter_function_for_optional {
     ($fnname:ident, $name:expr, $mapper:ty) => {
         pub fn $fnname(&self) -> Option<$mapper> {
@@ -19,20 +21,70 @@ macro_rules! make_getter_function_for_values {
     }
 }
 
+macro_rules! make_getter_function_for_language {
+    ($fnname:ident, $name:expr, $mapper:ty) => {
+        /// The first value whose `LANGUAGE` matches the language range `lang` (`en` matches `en-US`),
+        /// else the first value without `LANGUAGE`.
+        pub fn $fnname(&self, lang: &str) -> Option<$mapper> {
+            $crate::util::select_language(self.0.get_all($name), lang).cloned().map(From::from)
+        }
+    }
+}
+
+/// Define a wrapper type for the value and parameters of a property.
+///
+/// The value is stored in its raw, escaped form. `raw()` returns it as is, `value()` unescapes
+/// it.
+///
+/// With a property name, the type also implements `TypedProperty`, so it can be retrieved from
+/// any component with `Component::get_typed()`:
+///
+/// ```
+/// #[macro_use] extern crate vobject;
+///
+/// create_data_type!(XMozLastAck, "X-MOZ-LASTACK");
+///
+/// fn main() {
+///     let event = vobject::parse_component("BEGIN:VEVENT\n\
+///                                           X-MOZ-LASTACK:20240101T000000Z\n\
+///                                           END:VEVENT\n").unwrap();
+///     let ack: XMozLastAck = event.get_typed().unwrap();
+///     assert_eq!(ack.raw(), "20240101T000000Z");
+/// }
+/// ```
+#[macro_export]
 macro_rules! create_data_type {
+    ( $name:ident, $property_name:expr ) => {
+        create_data_type!($name);
+
+        impl $crate::property::TypedProperty for $name {
+            const NAME: &'static str = $property_name;
+        }
+    };
+
     ( $name:ident ) => {
         #[derive(Eq, PartialEq, Debug)]
         pub struct $name(String, $crate::param::Parameters);
 
         impl $name {
-            pub fn new(raw: String, params: $crate::param::Parameters) -> $name {
-                $name(raw, params)
+            /// Create a value from its raw, already escaped form, e.g. `a\, b`.
+            pub fn new<P: Into<$crate::param::Parameters>>(raw: String, params: P) -> $name {
+                $name(raw, params.into())
             }
 
+            /// Same as `new`, without parameters.
             pub fn from_raw(raw: String) -> $name {
-                $name(raw, BTreeMap::new())
+                $name(raw, $crate::param::Parameters::new())
+            }
+
+            /// Create a value from unescaped text, e.g. `a, b`.
+            pub fn new_unescaped(value: &str, params: $crate::param::Parameters) -> $name {
+                $name($crate::property::escape_chars(value), params)
             }
 
+            /// The raw value, with escape sequences like `\n` as they appear in the file.
+            ///
+            /// For text, `value()` is usually what you want.
             pub fn raw(&self) -> &String {
                 &self.0
             }
@@ -41,22 +93,547 @@ macro_rules! create_data_type {
                 self.0
             }
 
+            /// The unescaped value, e.g. with `\n` turned into a line break.
+            pub fn value(&self) -> String {
+                $crate::property::unescape_chars(&self.0)
+            }
+
+            /// Same as `value`, consuming the value.
+            pub fn into_value(self) -> String {
+                $crate::property::unescape_chars(&self.0)
+            }
+
             pub fn params(&self) -> &$crate::param::Parameters {
                 &self.1
             }
+
+            /// The `LANGUAGE` parameter, such as `en-US`.
+            pub fn language(&self) -> Option<&str> {
+                self.1.get("LANGUAGE").map(|l| l.trim_matches('"'))
+            }
         }
 
-        impl From<Property> for $name {
-            fn from(p: Property) -> $name {
+        impl From<$crate::property::Property> for $name {
+            fn from(p: $crate::property::Property) -> $name {
                 $name::new(p.raw_value, p.params)
             }
         }
     }
 }
 
+/// Define an enum for a property or parameter with a fixed set of values.
+///
+/// Unknown values, such as `X-` names, are kept in an additional `Other` variant. The parsing
+/// and formatting functions are called `from_ical_str` and `as_ical_str` unless other names
+/// are given in parentheses after the type name.
+macro_rules! create_value_enum {
+    ($(#[$attr:meta])* $name:ident { $( $variant:ident => $value:expr ),* $(,)* }) => {
+        create_value_enum!($(#[$attr])* $name (from_ical_str, as_ical_str) { $( $variant => $value ),* });
+    };
+
+    ($(#[$attr:meta])* $name:ident ($from:ident, $as:ident) { $( $variant:ident => $value:expr ),* $(,)* }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+        pub enum $name {
+            $( $variant, )*
+            Other(String),
+        }
+
+        impl $name {
+            /// Parse a value case-insensitively. Unknown values become `Other`.
+            pub fn $from(s: &str) -> $name {
+                $(
+                    if s.eq_ignore_ascii_case($value) {
+                        return $name::$variant;
+                    }
+                )*
+                $name::Other(s.to_owned())
+            }
+
+            /// The value as written in the data.
+            pub fn $as(&self) -> &str {
+                match *self {
+                    $( $name::$variant => $value, )*
+                    $name::Other(ref s) => s,
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ::std::convert::Infallible;
+
+            /// Same as the parsing function above, which never fails.
+            fn from_str(s: &str) -> Result<$name, Self::Err> {
+                Ok($name::$from(s))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.$as())
+            }
+        }
+    }
+}
+
+/// Add accessors for the UTC offset to a data type holding one, like `TZOFFSETTO`. `$parse` is
+/// `parse_utc_offset` or, for vCard, `parse_vcard_utc_offset`.
+macro_rules! make_utc_offset_conversions {
+    ( $name:ident, $parse:ident ) => {
+        impl $name {
+            /// The offset in seconds east of UTC, or `None` if the value is not a UTC offset.
+            pub fn utc_offset(&self) -> Option<i32> {
+                $crate::util::$parse(&self.0)
+            }
+
+            /// The value as UTC offset.
+            #[cfg(feature = "timeconversions")]
+            pub fn as_offset(&self) -> $crate::error::VObjectResult<::chrono::FixedOffset> {
+                self.utc_offset()
+                    .and_then(::chrono::FixedOffset::east_opt)
+                    .ok_or_else(|| $crate::error::VObjectError::InvalidValue("UTC-OFFSET", self.0.clone()))
+            }
+        }
+    }
+}
+
+macro_rules! make_chrono_conversions {
+    ( $name:ident ) => {
+        #[cfg(feature = "timeconversions")]
+        impl From<::chrono::NaiveDate> for $name {
+            /// Create a date value with the `VALUE=DATE` parameter set.
+            fn from(d: ::chrono::NaiveDate) -> $name {
+                $name::new(d.format($crate::util::DATE_FMT).to_string(), parameters!("VALUE" => "DATE"))
+            }
+        }
+
+        #[cfg(feature = "timeconversions")]
+        impl $name {
+            /// Create a UTC date-time value, e.g. `20160325T120000Z`.
+            pub fn from_datetime_utc(dt: ::chrono::DateTime<::chrono::Utc>) -> $name {
+                $name::from_raw(dt.format($crate::util::DATE_TIME_FMT).to_string())
+            }
+
+            /// Create a floating date-time value without timezone, e.g. `20160325T120000`.
+            pub fn from_naive_floating(dt: ::chrono::NaiveDateTime) -> $name {
+                $name::from_raw(dt.format($crate::util::LOCAL_DATE_TIME_FMT).to_string())
+            }
+        }
+    }
+}
+
+/// Add accessors for a timestamp that is always in UTC, like `REV` or `LAST-MODIFIED`.
+macro_rules! make_timestamp_conversions {
+    ( $name:ident ) => {
+        #[cfg(feature = "timeconversions")]
+        impl $name {
+            /// Parse the timestamp, accepting `20140301T221110Z` as well as the extended ISO 8601
+            /// form `2014-03-01T22:11:10Z` which some servers emit.
+            pub fn as_datetime(&self) -> $crate::error::VObjectResult<::chrono::DateTime<::chrono::Utc>> {
+                $crate::util::parse_timestamp(&self.0)
+            }
+
+            /// The current time, in the canonical form `20140301T221110Z`.
+            pub fn now() -> $name {
+                $name::from_datetime_utc(::chrono::Utc::now())
+            }
+        }
+    }
+}
+
+/// Parse a `GEO` value, either `LAT;LON` or a `geo:LAT,LON` URI, into latitude and longitude.
+pub(crate) fn parse_geo(raw: &str) -> ::error::VObjectResult<(f64, f64)> {
+    let invalid = || ::error::VObjectError::InvalidGeo(String::from(raw));
+
+    let parts = if raw.len() >= 4 && raw[..4].eq_ignore_ascii_case("geo:") {
+        // Drop URI parameters such as `;u=35`, and the optional altitude
+        raw[4..].split(';').next().unwrap_or("").split(',').take(2).collect::<Vec<_>>()
+    } else {
+        raw.split(';').collect::<Vec<_>>()
+    };
+
+    if parts.len() != 2 {
+        return Err(invalid());
+    }
+
+    let lat = parts[0].trim().parse().map_err(|_| invalid())?;
+    let lon = parts[1].trim().parse().map_err(|_| invalid())?;
+    Ok((lat, lon))
+}
+
+/// Parse an iCalendar UTC offset like `+0100` or `-053000` into seconds east of UTC.
+pub(crate) fn parse_utc_offset(s: &str) -> Option<i32> {
+    let s = s.trim();
+    let (sign, digits) = match s.chars().next()? {
+        '+' => (1, &s[1..]),
+        '-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    utc_offset_seconds(sign, digits)
+}
+
+/// Like `parse_utc_offset`, but also accept the vCard forms `+01:00` and `+01`.
+pub(crate) fn parse_vcard_utc_offset(s: &str) -> Option<i32> {
+    let s = s.trim();
+    let (sign, digits) = match s.chars().next()? {
+        '+' => (1, &s[1..]),
+        '-' => (-1, &s[1..]),
+        _ => return None,
+    };
+
+    let digits = match digits.len() {
+        5 | 8 if digits.as_bytes()[2] == b':' => digits.replace(':', ""),
+        _ => String::from(digits),
+    };
+    let digits = if digits.len() == 2 { digits + "00" } else { digits };
+    utc_offset_seconds(sign, &digits)
+}
+
+fn utc_offset_seconds(sign: i32, digits: &str) -> Option<i32> {
+    if (digits.len() != 4 && digits.len() != 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours: i32 = digits[0..2].parse().ok()?;
+    let minutes: i32 = digits[2..4].parse().ok()?;
+    let seconds: i32 = if digits.len() == 6 { digits[4..6].parse().ok()? } else { 0 };
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// The first few characters of `s`, to include the input in errors without making them huge.
+pub(crate) fn input_prefix(s: &str) -> String {
+    const MAX_CHARS: usize = 64;
+    match s.char_indices().nth(MAX_CHARS) {
+        Some((i, _)) => format!("{}...", &s[..i]),
+        None => String::from(s),
+    }
+}
+
+/// Decode raw bytes to text: UTF-8, possibly with a byte order mark, or UTF-16 with a byte order
+/// mark. With the `encoding` feature, anything else is read as latin-1.
+pub(crate) fn decode_bytes(b: &[u8]) -> ::error::VObjectResult<::std::borrow::Cow<'_, str>> {
+    use std::borrow::Cow;
+    use error::VObjectError;
+
+    fn utf16(b: &[u8], from_bytes: fn([u8; 2]) -> u16) -> ::error::VObjectResult<String> {
+        let chunks = b.chunks_exact(2);
+        if !chunks.remainder().is_empty() {
+            return Err(VObjectError::Encoding(String::from("UTF-16 input with odd number of bytes")));
+        }
+        let units = chunks.map(|c| from_bytes([c[0], c[1]])).collect::<Vec<_>>();
+        String::from_utf16(&units).map_err(|e| VObjectError::Encoding(format!("invalid UTF-16: {}", e)))
+    }
+
+    if let Some(rest) = b.strip_prefix(b"\xef\xbb\xbf") {
+        return ::std::str::from_utf8(rest)
+            .map(Cow::Borrowed)
+            .map_err(|e| VObjectError::Encoding(format!("invalid UTF-8: {}", e)));
+    }
+    if let Some(rest) = b.strip_prefix(b"\xff\xfe") {
+        return utf16(rest, u16::from_le_bytes).map(Cow::Owned);
+    }
+    if let Some(rest) = b.strip_prefix(b"\xfe\xff") {
+        return utf16(rest, u16::from_be_bytes).map(Cow::Owned);
+    }
+
+    match ::std::str::from_utf8(b) {
+        Ok(s) => Ok(Cow::Borrowed(s)),
+        #[cfg(feature = "encoding")]
+        Err(_) => Ok(Cow::Owned(b.iter().map(|&c| char::from(c)).collect())),
+        #[cfg(not(feature = "encoding"))]
+        Err(e) => Err(VObjectError::Encoding(format!("invalid UTF-8: {}", e))),
+    }
+}
+
+/// Pick the first property whose `LANGUAGE` matches the language range `lang`, falling back to the
+/// first property without `LANGUAGE`.
+///
+/// Matching is RFC 4647 basic filtering: `en` matches `en` and `en-US`, case-insensitively, and
+/// `*` matches any language.
+pub(crate) fn select_language<'a>(props: &'a [::property::Property], lang: &str) -> Option<&'a ::property::Property> {
+    let matches = |tag: &str| {
+        lang == "*" ||
+            tag.eq_ignore_ascii_case(lang) ||
+            (tag.len() > lang.len() &&
+             tag.as_bytes()[lang.len()] == b'-' &&
+             tag[..lang.len()].eq_ignore_ascii_case(lang))
+    };
+    let language = |p: &'a ::property::Property| p.params.get("LANGUAGE").map(|l| l.trim_matches('"'));
+
+    props.iter()
+        .find(|p| language(p).is_some_and(matches))
+        .or_else(|| props.iter().find(|p| language(p).is_none()))
+}
+
+/// Split at `sep`, ignoring backslash-escaped occurences.
+pub(crate) fn split_unescaped(s: &str, sep: char) -> Vec<&str> {
+    let mut rv = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == sep {
+            rv.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    rv.push(&s[start..]);
+    rv
+}
+
+/// Split a comma-separated list of text values, such as `CATEGORIES`, and unescape each entry.
+///
+/// This is where all list splitting happens, see `Property::values`.
+pub(crate) fn split_text_list(raw: &str) -> Vec<String> {
+    if raw.is_empty() {
+        return vec![];
+    }
+    split_unescaped(raw, ',').into_iter().map(::property::unescape_chars).collect()
+}
+
+/// Escape each entry and join them into a comma-separated list.
+pub(crate) fn join_text_list<S: AsRef<str>>(values: &[S]) -> String {
+    values.iter().map(|v| ::property::escape_chars(v.as_ref())).collect::<Vec<_>>().join(",")
+}
+
+/// Escape a text value, leaving existing escape sequences intact.
+///
+/// Unlike `escape_chars`, this can be applied to values which may or may not be escaped
+/// already: `a, b` and `a\, b` both become `a\, b`.
+pub(crate) fn escape_unescaped(raw: &str) -> String {
+    let mut rv = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some(&next) if "\\;,nN".contains(next) => {
+                    rv.push(c);
+                    rv.push(next);
+                    chars.next();
+                },
+                _ => rv.push_str("\\\\"),
+            },
+            ';' => rv.push_str("\\;"),
+            ',' => rv.push_str("\\,"),
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' => rv.push_str("\\n"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+/// Encode `data` as standard base64 with padding.
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut rv = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                rv.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                rv.push('=');
+            }
+        }
+    }
+    rv
+}
+
+/// Split a URI into its scheme and the part after the colon, e.g. `xmpp` and `foo@jabber.org`.
+/// `None` if there is no valid scheme.
+pub(crate) fn split_uri_scheme(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim();
+    let colon = s.find(':')?;
+    let scheme = &s[..colon];
+    let mut chars = scheme.chars();
+    if !chars.next()?.is_ascii_alphabetic() ||
+        !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) {
+        return None;
+    }
+    Some((scheme, &s[colon + 1..]))
+}
+
+/// Decode the payload of a base64 `data:` URI, returning the media type if one is given.
+pub(crate) fn decode_data_uri(s: &str) -> Option<(Option<&str>, Vec<u8>)> {
+    let (scheme, rest) = split_uri_scheme(s)?;
+    if !scheme.eq_ignore_ascii_case("data") {
+        return None;
+    }
+    let comma = rest.find(',')?;
+    let meta = &rest[..comma];
+    // `get` instead of indexing, as the split may fall inside a non-ASCII char
+    let split = meta.len().checked_sub(7)?;
+    if !meta.get(split..)?.eq_ignore_ascii_case(";base64") {
+        return None;
+    }
+    let mime = meta[..split].split(';').next().filter(|m| !m.is_empty());
+    Some((mime, base64_decode(&rest[comma + 1..])?))
+}
+
+/// Guess the media type of an image from its first bytes: JPEG, PNG, GIF or WebP.
+pub(crate) fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Precomposed Latin letters with diacritics, by the letter without them.
+const DIACRITICS: &[(&str, char)] = &[
+    ("àáâãäåāăą", 'a'), ("çćĉċč", 'c'), ("ďđ", 'd'), ("èéêëēĕėęě", 'e'), ("ĝğġģ", 'g'),
+    ("ĥħ", 'h'), ("ìíîïĩīĭįı", 'i'), ("ĵ", 'j'), ("ķ", 'k'), ("ĺļľŀł", 'l'), ("ñńņňŉ", 'n'),
+    ("òóôõöøōŏő", 'o'), ("ŕŗř", 'r'), ("śŝşš", 's'), ("ţťŧ", 't'), ("ùúûüũūŭůűų", 'u'),
+    ("ŵ", 'w'), ("ýÿŷ", 'y'), ("źżž", 'z'),
+];
+
+/// Lowercase `s` for comparisons. With `strip_diacritics`, combining marks are dropped and the
+/// letters in `DIACRITICS` replaced, so that `Café` and `cafe` compare equal.
+pub(crate) fn fold_text(s: &str, strip_diacritics: bool) -> String {
+    let lower = s.to_lowercase();
+    if !strip_diacritics {
+        return lower;
+    }
+    lower.chars()
+        .filter(|c| !('\u{300}'..='\u{36f}').contains(c))
+        .map(|c| {
+            DIACRITICS.iter()
+                .find(|&&(letters, _)| letters.contains(c))
+                .map_or(c, |&(_, base)| base)
+        })
+        .collect()
+}
+
+/// Decode standard base64. Whitespace is ignored, padding is optional.
+pub(crate) fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let mut rv = Vec::with_capacity(s.len() / 4 * 3);
+    let mut buf: u32 = 0;
+    let mut bits = 0;
+
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buf = (buf << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            rv.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+    Some(rv)
+}
+
+/// Generate a random (version 4) UUID such as `4fbe8971-0bc3-424c-9c26-36c3e1eff6b1`.
+#[cfg(feature = "uuid")]
+pub(crate) fn random_uuid() -> String {
+    ::uuid::Uuid::new_v4().to_string()
+}
+
+/// Generate a random (version 4) UUID such as `4fbe8971-0bc3-424c-9c26-36c3e1eff6b1`.
+///
+/// The randomness comes from the current time and std's randomly seeded hasher, which is good
+/// enough for unique identifiers but must not be used for anything security-related.
+#[cfg(all(feature = "std", not(feature = "uuid")))]
+pub(crate) fn random_uuid() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    let random = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_usize(count);
+        hasher.finish()
+    };
+    let (hi, lo) = (random(), random());
+
+    // Set version 4 and the RFC 4122 variant
+    let hi = (hi & !0xf000) | 0x4000;
+    let lo = (lo & !(0xc << 60)) | (0x8 << 60);
+
+    format!("{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            hi >> 32, (hi >> 16) & 0xffff, hi & 0xffff, lo >> 48, lo & 0xffff_ffff_ffff)
+}
+
+/// The 64-bit FNV-1a hash. Unlike std's hashers, its output is guaranteed to stay the same
+/// across Rust versions.
+pub(crate) struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Hash a string, prefixed by its length so that consecutive strings cannot run together.
+    pub fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Parse a timestamp in the basic (`20140301T221110Z`, `20140301T231110+0100`) or extended
+/// ISO 8601 form, converted to UTC.
+#[cfg(feature = "timeconversions")]
+pub(crate) fn parse_timestamp(raw: &str) -> ::error::VObjectResult<::chrono::DateTime<::chrono::Utc>> {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+
+    let raw = raw.trim();
+    if let Ok(dt) = NaiveDateTime::parse_from_str(raw, DATE_TIME_FMT) {
+        return Ok(dt.and_utc());
+    }
+    match DateTime::parse_from_str(raw, "%Y%m%dT%H%M%S%z") {
+        Ok(dt) => Ok(dt.with_timezone(&Utc)),
+        Err(_) => Ok(DateTime::parse_from_rfc3339(raw)?.with_timezone(&Utc)),
+    }
+}
+
+#[cfg(feature = "timeconversions")]
+pub const DATE_TIME_FMT : &str = "%Y%m%dT%H%M%SZ";
+
 #[cfg(feature = "timeconversions")]
-pub const DATE_TIME_FMT : &'static str = "%Y%m%dT%H%M%SZ";
+pub const LOCAL_DATE_TIME_FMT : &str = "%Y%m%dT%H%M%S";
 
 #[cfg(feature = "timeconversions")]
-pub const DATE_FMT      : &'static str = "%Y%m%d";
+pub const DATE_FMT      : &str = "%Y%m%d";
 
@@ -0,0 +1,300 @@
+//! Value types of properties, as defined in
+//! [RFC 5545](https://tools.ietf.org/html/rfc5545#section-3.3) and
+//! [RFC 6350](https://tools.ietf.org/html/rfc6350#section-4).
+
+use std::fmt;
+#[cfg(not(feature = "std"))] use std::prelude::*;
+
+#[cfg(feature = "timeconversions")] use chrono::NaiveDate;
+
+use error::*;
+use property::Property;
+use util::{base64_decode, parse_utc_offset, parse_vcard_utc_offset};
+use vcard::VcardVersion;
+
+#[cfg(feature = "timeconversions")] use icalendar::{parse_time, Duration, Time};
+#[cfg(feature = "timeconversions")] use util::DATE_FMT;
+
+/// The type of a property value, see `Property::value_type()`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ValueType {
+    Text,
+    Uri,
+    Date,
+    DateTime,
+    Duration,
+    Binary,
+    Integer,
+    Float,
+    Boolean,
+    UtcOffset,
+
+    /// Any other type, such as `RECUR` or `PERIOD`, or a property whose type is not known.
+    Unknown,
+}
+
+impl ValueType {
+    /// Parse the value of a `VALUE` parameter, e.g. `DATE-TIME`.
+    pub fn from_param(s: &str) -> ValueType {
+        match &s.to_ascii_uppercase()[..] {
+            "TEXT"                    => ValueType::Text,
+            "URI" | "URL" | "CAL-ADDRESS" => ValueType::Uri,
+            "DATE"                    => ValueType::Date,
+            "DATE-TIME" | "TIMESTAMP" => ValueType::DateTime,
+            "DURATION"                => ValueType::Duration,
+            "BINARY"                  => ValueType::Binary,
+            "INTEGER"                 => ValueType::Integer,
+            "FLOAT"                   => ValueType::Float,
+            "BOOLEAN"                 => ValueType::Boolean,
+            "UTC-OFFSET"              => ValueType::UtcOffset,
+            _                         => ValueType::Unknown,
+        }
+    }
+
+    /// The default type of the given property, when there is no `VALUE` parameter.
+    ///
+    /// `version` is the version of the containing vCard, if any. It matters for `GEO`, which is
+    /// a `geo:` URI in vCard 4.0 rather than two floats.
+    pub fn default_for(property_name: &str, version: Option<VcardVersion>) -> ValueType {
+        match &property_name.to_ascii_uppercase()[..] {
+            "GEO" if version == Some(VcardVersion::V4_0) => ValueType::Uri,
+            "ACTION" | "CALSCALE" | "CATEGORIES" | "CLASS" | "COMMENT" | "CONTACT" | "DESCRIPTION"
+                | "LOCATION" | "METHOD" | "PRODID" | "RELATED-TO" | "RESOURCES" | "STATUS"
+                | "SUMMARY" | "TRANSP" | "TZID" | "TZNAME" | "UID" | "VERSION"
+                | "ADR" | "EMAIL" | "FN" | "KIND" | "N" | "NICKNAME" | "NOTE" | "ORG" | "ROLE"
+                | "TITLE" => ValueType::Text,
+            "ATTACH" | "ATTENDEE" | "ORGANIZER" | "TZURL" | "URL"
+                | "CALADRURI" | "CALURI" | "FBURL" | "IMPP" | "KEY" | "LOGO" | "MEMBER" | "PHOTO"
+                | "RELATED" | "SOUND" | "SOURCE" => ValueType::Uri,
+            "COMPLETED" | "CREATED" | "DTEND" | "DTSTAMP" | "DTSTART" | "DUE" | "EXDATE"
+                | "LAST-MODIFIED" | "RDATE" | "RECURRENCE-ID" | "REV" => ValueType::DateTime,
+            "DURATION" | "TRIGGER" => ValueType::Duration,
+            "PERCENT-COMPLETE" | "PRIORITY" | "REPEAT" | "SEQUENCE" => ValueType::Integer,
+            "GEO" => ValueType::Float,
+            "TZOFFSETFROM" | "TZOFFSETTO" => ValueType::UtcOffset,
+            _ => ValueType::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ValueType::Text      => "TEXT",
+            ValueType::Uri       => "URI",
+            ValueType::Date      => "DATE",
+            ValueType::DateTime  => "DATE-TIME",
+            ValueType::Duration  => "DURATION",
+            ValueType::Binary    => "BINARY",
+            ValueType::Integer   => "INTEGER",
+            ValueType::Float     => "FLOAT",
+            ValueType::Boolean   => "BOOLEAN",
+            ValueType::UtcOffset => "UTC-OFFSET",
+            ValueType::Unknown   => "UNKNOWN",
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A parsed property value, see `Property::typed_value()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    /// Unescaped text.
+    Text(String),
+    Uri(String),
+    #[cfg(feature = "timeconversions")]
+    Date(NaiveDate),
+    #[cfg(feature = "timeconversions")]
+    DateTime(Time),
+    #[cfg(feature = "timeconversions")]
+    Duration(::chrono::Duration),
+    Binary(Vec<u8>),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+
+    /// Seconds east of UTC.
+    UtcOffset(i32),
+
+    /// Multiple values, such as both coordinates of `GEO` or the dates of `EXDATE`.
+    List(Vec<TypedValue>),
+
+    /// The raw value of a property of unknown type, or of dates and durations if the
+    /// `timeconversions` feature is disabled.
+    Unknown(String),
+}
+
+impl Property {
+    /// The type of the value, taken from the `VALUE` parameter or the property's default type.
+    ///
+    /// Inline binary data of vCard 3.0 (`ENCODING=b`) is also recognized. As the version of the
+    /// containing card is not known, a `GEO` holding a `geo:` URI is taken to be from vCard 4.0;
+    /// see `Property::value_type_in()`.
+    pub fn value_type(&self) -> ValueType {
+        let geo_uri = self.name.eq_ignore_ascii_case("GEO")
+            && self.raw_value.get(..4).is_some_and(|s| s.eq_ignore_ascii_case("geo:"));
+        self.value_type_in(if geo_uri { Some(VcardVersion::V4_0) } else { None })
+    }
+
+    /// Like `Property::value_type()`, with the default type taken for the given vCard version.
+    pub fn value_type_in(&self, version: Option<VcardVersion>) -> ValueType {
+        if let Some(value) = self.params.get("VALUE") {
+            return ValueType::from_param(value);
+        }
+
+        let inline = self.params.get("ENCODING").is_some_and(|e| {
+            e.eq_ignore_ascii_case("b") || e.eq_ignore_ascii_case("BASE64")
+        });
+        if inline {
+            return ValueType::Binary;
+        }
+
+        ValueType::default_for(&self.name, version)
+    }
+
+    /// Parse the value according to `Property::value_type()`.
+    ///
+    /// Values of list types (dates, date-times, durations, floats) are split at commas, `GEO`
+    /// additionally at semicolons. If there is more than one part, a `TypedValue::List` is
+    /// returned.
+    pub fn typed_value(&self) -> VObjectResult<TypedValue> {
+        self.parse_typed(self.value_type())
+    }
+
+    /// Like `Property::typed_value()`, with the default type taken for the given vCard version.
+    pub fn typed_value_in(&self, version: Option<VcardVersion>) -> VObjectResult<TypedValue> {
+        self.parse_typed(self.value_type_in(version))
+    }
+
+    fn parse_typed(&self, value_type: ValueType) -> VObjectResult<TypedValue> {
+        let invalid = || VObjectError::InvalidValue(value_type.as_str(), self.raw_value.clone());
+
+        let parse_one = |raw: &str| -> VObjectResult<TypedValue> {
+            Ok(match value_type {
+                ValueType::Text => TypedValue::Text(self.value_as_string()),
+                ValueType::Uri => TypedValue::Uri(raw.to_owned()),
+                #[cfg(feature = "timeconversions")]
+                ValueType::Date => TypedValue::Date(NaiveDate::parse_from_str(raw, DATE_FMT).map_err(|_| invalid())?),
+                #[cfg(feature = "timeconversions")]
+                ValueType::DateTime => TypedValue::DateTime(parse_time(raw, &self.params).map_err(|_| invalid())?),
+                #[cfg(feature = "timeconversions")]
+                ValueType::Duration => TypedValue::Duration(Duration::from_raw(raw.to_owned()).as_duration()?),
+                ValueType::Binary => TypedValue::Binary(base64_decode(raw).ok_or_else(invalid)?),
+                ValueType::Integer => TypedValue::Integer(raw.trim().parse().map_err(|_| invalid())?),
+                ValueType::Float => TypedValue::Float(raw.trim().parse().map_err(|_| invalid())?),
+                ValueType::Boolean => match &raw.to_ascii_uppercase()[..] {
+                    "TRUE" => TypedValue::Boolean(true),
+                    "FALSE" => TypedValue::Boolean(false),
+                    _ => return Err(invalid()),
+                },
+                // Only vCard's `TZ` allows forms like `+01:00`
+                ValueType::UtcOffset if self.name.eq_ignore_ascii_case("TZ") => {
+                    TypedValue::UtcOffset(parse_vcard_utc_offset(raw).ok_or_else(invalid)?)
+                },
+                ValueType::UtcOffset => TypedValue::UtcOffset(parse_utc_offset(raw).ok_or_else(invalid)?),
+                _ => TypedValue::Unknown(raw.to_owned()),
+            })
+        };
+
+        let separators: &[char] = match value_type {
+            ValueType::Float if self.name.eq_ignore_ascii_case("GEO") => &[';', ','],
+            ValueType::Date | ValueType::DateTime | ValueType::Duration | ValueType::Float => &[','],
+            _ => &[],
+        };
+
+        let mut values = self.raw_value
+            .split(separators)
+            .map(parse_one)
+            .collect::<VObjectResult<Vec<_>>>()?;
+
+        Ok(if values.len() == 1 { values.remove(0) } else { TypedValue::List(values) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))] use std::prelude::*;
+    use property::Property;
+    use super::*;
+
+    fn prop(name: &str, raw: &str, params: &[(&str, &str)]) -> Property {
+        let mut p = Property::new(name, "");
+        p.raw_value = raw.to_owned();
+        for &(k, v) in params {
+            p.params.insert(k.to_owned(), v.to_owned());
+        }
+        p
+    }
+
+    #[test]
+    fn test_value_type() {
+        assert_eq!(prop("DTSTART", "20240101T120000Z", &[]).value_type(), ValueType::DateTime);
+        assert_eq!(prop("DTSTART", "20240101", &[("VALUE", "DATE")]).value_type(), ValueType::Date);
+        assert_eq!(prop("ATTACH", "http://example.com/", &[]).value_type(), ValueType::Uri);
+        assert_eq!(prop("ATTACH", "Zm9v", &[("VALUE", "BINARY"), ("ENCODING", "BASE64")]).value_type(),
+                   ValueType::Binary);
+        assert_eq!(prop("PHOTO", "Zm9v", &[("ENCODING", "b")]).value_type(), ValueType::Binary);
+        assert_eq!(prop("GEO", "1;2", &[]).value_type(), ValueType::Float);
+        assert_eq!(prop("GEO", "geo:1,2", &[]).value_type(), ValueType::Uri);
+        assert_eq!(prop("GEO", "1;2", &[]).value_type_in(Some(VcardVersion::V4_0)), ValueType::Uri);
+        assert_eq!(prop("GEO", "1;2", &[]).value_type_in(Some(VcardVersion::V3_0)), ValueType::Float);
+        assert_eq!(ValueType::default_for("REV", Some(VcardVersion::V4_0)), ValueType::DateTime);
+        assert_eq!(prop("RRULE", "FREQ=DAILY", &[]).value_type(), ValueType::Unknown);
+        assert_eq!(prop("X-FOO", "bar", &[]).value_type(), ValueType::Unknown);
+        assert_eq!(prop("X-FOO", "1", &[("VALUE", "integer")]).value_type(), ValueType::Integer);
+    }
+
+    #[test]
+    fn test_typed_value() {
+        let typed = |p: Property| p.typed_value().unwrap();
+
+        assert_eq!(typed(prop("SUMMARY", "a\\, b", &[])), TypedValue::Text("a, b".to_owned()));
+        assert_eq!(typed(prop("URL", "http://example.com/", &[])), TypedValue::Uri("http://example.com/".to_owned()));
+        assert_eq!(typed(prop("ATTACH", "Zm9vYmE=", &[("VALUE", "BINARY")])), TypedValue::Binary(b"fooba".to_vec()));
+        assert_eq!(typed(prop("PRIORITY", "5", &[])), TypedValue::Integer(5));
+        assert_eq!(typed(prop("GEO", "37.386013;-122.082932", &[])),
+                   TypedValue::List(vec![TypedValue::Float(37.386013), TypedValue::Float(-122.082932)]));
+        assert_eq!(typed(prop("GEO", "geo:37.386,-122.08", &[])), TypedValue::Uri("geo:37.386,-122.08".to_owned()));
+        assert_eq!(typed(prop("X-ALLDAY", "TRUE", &[("VALUE", "BOOLEAN")])), TypedValue::Boolean(true));
+        assert_eq!(typed(prop("TZOFFSETTO", "-0530", &[])), TypedValue::UtcOffset(-19800));
+        assert_eq!(typed(prop("RRULE", "FREQ=DAILY", &[])), TypedValue::Unknown("FREQ=DAILY".to_owned()));
+
+        assert!(prop("PRIORITY", "high", &[]).typed_value().is_err());
+        assert!(prop("X-ALLDAY", "yes", &[("VALUE", "BOOLEAN")]).typed_value().is_err());
+        assert!(prop("ATTACH", "Zm9v!", &[("VALUE", "BINARY")]).typed_value().is_err());
+        assert!(prop("TZOFFSETTO", "0530", &[]).typed_value().is_err());
+        assert!(prop("TZOFFSETTO", "+05:3", &[]).typed_value().is_err());
+        assert!(prop("TZOFFSETFROM", "+05:30", &[]).typed_value().is_err());
+        assert!(prop("TZOFFSETFROM", "-02", &[]).typed_value().is_err());
+        assert_eq!(typed(prop("TZOFFSETFROM", "+053000", &[])), TypedValue::UtcOffset(19800));
+        assert_eq!(typed(prop("TZ", "+05:30", &[("VALUE", "utc-offset")])), TypedValue::UtcOffset(19800));
+        assert_eq!(typed(prop("TZ", "-02", &[("VALUE", "utc-offset")])), TypedValue::UtcOffset(-7200));
+    }
+
+    #[cfg(feature = "timeconversions")]
+    #[test]
+    fn test_typed_value_times() {
+        use chrono::NaiveDate;
+        use icalendar::Time;
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let noon = date.and_hms_opt(12, 0, 0).unwrap();
+
+        assert_eq!(prop("DTSTART", "20240101", &[("VALUE", "DATE")]).typed_value().unwrap(),
+                   TypedValue::Date(date));
+        assert_eq!(prop("DTSTART", "20240101T120000", &[("TZID", "Europe/Berlin")]).typed_value().unwrap(),
+                   TypedValue::DateTime(Time::Local(noon, "Europe/Berlin".to_owned())));
+        assert_eq!(prop("EXDATE", "20240101T120000Z,20240102T120000Z", &[]).typed_value().unwrap(),
+                   TypedValue::List(vec![
+                       TypedValue::DateTime(Time::Utc(noon)),
+                       TypedValue::DateTime(Time::Utc(noon + ::chrono::Duration::days(1))),
+                   ]));
+        assert_eq!(prop("TRIGGER", "-PT15M", &[]).typed_value().unwrap(),
+                   TypedValue::Duration(::chrono::Duration::minutes(-15)));
+        assert!(prop("DTSTART", "tomorrow", &[]).typed_value().is_err());
+    }
+
+}
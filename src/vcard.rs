@@ -1,12 +1,38 @@
-use std::ops::Deref;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+#[cfg(feature = "std")] use std::io;
+use std::ops::Deref;
+use std::str::FromStr;
 
-use component::Component;
+use component::{normalize_name, Component, EventParser, ParseEvent};
 use component::parse_component;
+use component::write_component;
+#[cfg(feature = "std")] use component::{write_component_to, WriteOptions};
+use param::{Parameters, BARE_ENCODINGS};
 use property::Property;
+use property::{escape_chars, unescape_chars};
+#[cfg(feature = "std")] use uid::{self, UidKind};
+use uid::{urn_uuid, uuid_uri};
 
 use std::result::Result as RResult;
+#[cfg(not(feature = "std"))] use std::prelude::*;
+use thiserror::Error;
+
 use error::*;
+#[cfg(feature = "timeconversions")] use chrono::{Datelike, NaiveDate};
+#[cfg(feature = "timeconversions")] use util::DATE_FMT;
+use util::{base64_decode, base64_encode, decode_data_uri, input_prefix, join_text_list, parse_geo, split_text_list,
+           sniff_image_mime, split_unescaped, split_uri_scheme};
+
+/// Properties which RFC 6350 allows at most once per card.
+const SINGLETON_PROPERTIES: &[&str] = &[
+    "ANNIVERSARY", "BDAY", "GENDER", "KIND", "N", "PRODID", "REV", "UID", "VERSION",
+];
+
+/// Properties which RFC 6350 requires at least once per card.
+const REQUIRED_PROPERTIES: &[&str] = &["FN", "VERSION"];
 
 #[derive(Debug)]
 pub struct Vcard(Component);
@@ -25,10 +51,88 @@ impl Vcard {
     pub fn build(s: &str) -> VObjectResult<Vcard> {
         parse_component(s)
             .and_then(|c| {
-                Self::from_component(c).map_err(|_| VObjectError::NotAVCard)
+                Self::from_component(c).map_err(|c| VObjectError::NotAVCard {
+                    found: c.name,
+                    prefix: input_prefix(s),
+                })
             })
     }
 
+    /// Parse a string to a Vcard object and validate it
+    ///
+    /// Same as `Vcard::build()`, but additionally returns an error if `Vcard::validate()` reports
+    /// any problems, e.g. if the card contains more than one `N` property.
+    ///
+    pub fn build_strict(s: &str) -> VObjectResult<Vcard> {
+        let card = Self::build(s)?;
+        let errors = card.validate();
+        if errors.is_empty() {
+            Ok(card)
+        } else {
+            Err(VObjectError::InvalidVcard(errors))
+        }
+    }
+
+    /// Check the property cardinalities defined in RFC 6350.
+    ///
+    /// Returns an empty list if the card is valid. Note that the order of properties is not
+    /// preserved by `Component`, so it is not checked whether `VERSION` comes first; use
+    /// `Vcard::validate_source()` for that.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = vec![];
+
+        for name in REQUIRED_PROPERTIES {
+            if self.0.get_all(name).is_empty() {
+                errors.push(ValidationError::MissingProperty(String::from(*name)));
+            }
+        }
+
+        for name in SINGLETON_PROPERTIES {
+            let count = self.0.get_all(name).len();
+            if count > 1 {
+                errors.push(ValidationError::DuplicateProperty(String::from(*name), count));
+            }
+        }
+
+        errors
+    }
+
+    /// Validate every card in `s`, which may hold several cards one after another, and return
+    /// the problems of each card in order.
+    ///
+    /// On top of `Vcard::validate()`, this warns with `ValidationError::VersionNotFirst` about
+    /// cards whose `VERSION` is not the first property, as written by some LDAP gateways. Such
+    /// cards are read and written like any other, `VERSION` is always written first.
+    pub fn validate_source(s: &str) -> VObjectResult<Vec<Vec<ValidationError>>> {
+        let mut rv = vec![];
+        let mut rest = s;
+        while !rest.trim().is_empty() {
+            let mut events = EventParser::new(rest);
+            let mut depth = 0;
+            let mut version_first = None;
+            for event in events.by_ref() {
+                match event? {
+                    ParseEvent::ComponentStart(_) => depth += 1,
+                    ParseEvent::ComponentEnd(_) => depth -= 1,
+                    ParseEvent::Property(ref prop) if depth == 1 && version_first.is_none() => {
+                        version_first = Some(prop.name.eq_ignore_ascii_case("VERSION"));
+                    },
+                    ParseEvent::Property(_) => (),
+                }
+            }
+
+            let remainder = events.remainder();
+            let card = Vcard::build(&rest[..rest.len() - remainder.len()])?;
+            let mut errors = card.validate();
+            if version_first == Some(false) && !card.get_all("VERSION").is_empty() {
+                errors.push(ValidationError::VersionNotFirst);
+            }
+            rv.push(errors);
+            rest = remainder;
+        }
+        Ok(rv)
+    }
+
     /// Helper for `VcardBuilder::new()`
     pub fn builder() -> VcardBuilder {
         VcardBuilder::new()
@@ -47,7 +151,7 @@ impl Vcard {
     make_getter_function_for_optional!(anniversary  , "ANNIVERSARY"  , Anniversary);
     make_getter_function_for_optional!(bday         , "BDAY"         , BDay);
     make_getter_function_for_values!(categories     , "CATEGORIES"   , Category);
-    make_getter_function_for_optional!(clientpidmap , "CLIENTPIDMAP" , ClientPidMap);
+    make_getter_function_for_values!(clientpidmap   , "CLIENTPIDMAP" , ClientPidMap);
     make_getter_function_for_values!(email          , "EMAIL"        , Email);
     make_getter_function_for_values!(fullname       , "FN"           , FullName);
     make_getter_function_for_optional!(gender       , "GENDER"       , Gender);
@@ -62,7 +166,7 @@ impl Vcard {
     make_getter_function_for_values!(note           , "NOTE"         , Note);
     make_getter_function_for_values!(org            , "ORG"          , Organization);
     make_getter_function_for_values!(photo          , "PHOTO"        , Photo);
-    make_getter_function_for_optional!(proid        , "PRIOD"        , Proid);
+    make_getter_function_for_optional!(proid        , "PRODID"       , Proid);
     make_getter_function_for_values!(related        , "RELATED"      , Related);
     make_getter_function_for_optional!(rev          , "REV"          , Rev);
     make_getter_function_for_values!(role           , "ROLE"         , Title);
@@ -74,12 +178,631 @@ impl Vcard {
     make_getter_function_for_values!(url            , "URL"          , Url);
     make_getter_function_for_optional!(version      , "VERSION"      , Version);
 
+    make_getter_function_for_language!(fullname_for_lang , "FN"    , FullName);
+    make_getter_function_for_language!(org_for_lang      , "ORG"   , Organization);
+    make_getter_function_for_language!(title_for_lang    , "TITLE" , Title);
+
+    /// The `KIND` of the card. Apple's `X-ADDRESSBOOKSERVER-KIND` is used if `KIND` is missing.
+    ///
+    /// A card without either is an individual according to RFC 6350, but `None` is returned so
+    /// callers can tell the difference.
+    pub fn kind(&self) -> Option<Kind> {
+        self.0.get_only("KIND")
+            .or_else(|| self.0.get_only("X-ADDRESSBOOKSERVER-KIND"))
+            .map(|p| Kind::from_kind_str(&p.raw_value))
+    }
+
+    /// The UUIDs of the members of a group card, from `MEMBER` and Apple's
+    /// `X-ADDRESSBOOKSERVER-MEMBER`.
+    ///
+    /// Only `urn:uuid:` members are returned, other URIs like `mailto:` can be read with
+    /// `member()`.
+    pub fn members(&self) -> Vec<String> {
+        let apple = self.0.get_all("X-ADDRESSBOOKSERVER-MEMBER").iter().cloned().map(Member::from);
+        self.member()
+            .into_iter()
+            .chain(apple)
+            .filter_map(|m| m.uuid().map(String::from))
+            .collect()
+    }
+
+    /// The UUIDs of all `RELATED` properties which refer to another card by `urn:uuid:`, e.g. to
+    /// look the cards up by `UID`. Other targets are skipped.
+    pub fn related_uids(&self) -> Vec<String> {
+        self.related()
+            .iter()
+            .filter_map(|r| match r.target() {
+                RelatedTarget::Uid(uid) => Some(uid),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The highest source ID in use, from `CLIENTPIDMAP` and from the `PID` parameters of all
+    /// properties. A new client takes the next one.
+    pub fn max_pid_source(&self) -> Option<u32> {
+        let mapped = self.clientpidmap().into_iter().filter_map(|m| m.pid());
+        let used = self.0.iter_props().flat_map(|p| p.pids()).filter_map(|(_, source)| source);
+        mapped.chain(used).max()
+    }
+
+    /// Create a vCard 4.0 group card with a random `UID`, listing the cards with the given
+    /// UIDs as members.
+    ///
+    /// UIDs which are not URIs already are written as `urn:uuid:` URIs. Requires the `std`
+    /// feature.
+    #[cfg(feature = "std")]
+    pub fn new_group(name: &str, member_uids: &[&str]) -> Vcard {
+        let mut card = Vcard::default();
+        card.0.push(Property::new("VERSION", VcardVersion::V4_0.as_str()));
+        card.0.push(Property::new("KIND", Kind::Group.as_str()));
+        card.set_fullname(name);
+        card.0.push(Property::new("UID", uid::generate(UidKind::Vcard)));
+
+        for uid in member_uids {
+            card.0.push(Property {
+                name: String::from("MEMBER"),
+                params: Parameters::new(),
+                raw_value: uuid_uri(uid),
+                prop_group: None,
+            });
+        }
+
+        card
+    }
+
+    /// The cards nested in this one, as vCard 2.1 does for `AGENT`.
+    pub fn agents(&self) -> Vec<Vcard> {
+        self.0.subcomponents
+            .iter()
+            .filter(|c| c.name == "VCARD")
+            .cloned()
+            .map(Vcard)
+            .collect()
+    }
+
+    /// A copy of the card without `PHOTO`, `LOGO`, `SOUND` and `KEY`, which often hold large
+    /// binary data, e.g. for logging.
+    pub fn without_binary_blobs(&self) -> Vcard {
+        let mut c = self.0.clone();
+        c.retain_props(|p| !["PHOTO", "LOGO", "SOUND", "KEY"].contains(&&p.name[..]));
+        Vcard(c)
+    }
+
+    /// Same as `Component::fingerprint()`, e.g. to detect changed cards in a cache.
+    pub fn fingerprint(&self) -> u64 {
+        self.0.fingerprint()
+    }
+
+    /// Write the card to `w` with the default options, without building a String first.
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        write_component_to(&self.0, w, &WriteOptions::default())
+    }
+
+    /// The preferred `EMAIL`, or the first one if none is marked as preferred.
+    pub fn primary_email(&self) -> Option<Email> {
+        let mut emails = self.email();
+        let i = emails.iter().position(Email::is_preferred).unwrap_or(0);
+        if emails.is_empty() {
+            None
+        } else {
+            Some(emails.swap_remove(i))
+        }
+    }
+
+    /// A lowercase key for sorting contact lists by name.
+    ///
+    /// This is the `SORT-AS` parameter of `N`, or else of `ORG`, then surname and given name from
+    /// `N`, and then `FN`. Entries are joined with spaces.
+    pub fn sort_key(&self) -> String {
+        let sort_as = self.name().and_then(|n| n.sort_as())
+            .or_else(|| self.org().into_iter().find_map(|o| o.sort_as()));
+        let key = match sort_as {
+            Some(values) => values.join(" "),
+            None => {
+                let name = self.name()
+                    .map(|n| {
+                        let parts = [n.surname(), n.given_name()];
+                        let parts = parts.iter().flatten().map(|p| unescape_chars(p.trim()));
+                        parts.filter(|p| !p.is_empty()).collect::<Vec<_>>().join(" ")
+                    })
+                    .unwrap_or_default();
+                if name.trim().is_empty() {
+                    self.fullname().first().map(|f| unescape_chars(f.raw())).unwrap_or_default()
+                } else {
+                    name
+                }
+            },
+        };
+        key.trim_start().to_lowercase()
+    }
+
+    /// Compare cards by `Vcard::sort_key()`, e.g. `cards.sort_by(Vcard::cmp_by_name)`. Keys are
+    /// compared by code point, so `ü` sorts after `z`.
+    pub fn cmp_by_name(&self, other: &Vcard) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+
+    /// Pair every grouped property with the `X-ABLABEL` of its group, if there is one.
+    ///
+    /// Apple Contacts attaches custom labels to properties like this:
+    ///
+    /// ```text
+    /// item1.TEL:+49 170 1234567
+    /// item1.X-ABLabel:Werkstatt
+    /// ```
+    ///
+    /// The labels themselves are not returned as separate entries.
+    pub fn labelled_properties(&self) -> Vec<(&Property, Option<&Property>)> {
+        self.props
+            .values()
+            .flat_map(|props| props.iter())
+            .filter(|p| p.prop_group.is_some() && p.name.to_uppercase() != "X-ABLABEL")
+            .map(|p| {
+                let group = p.prop_group.as_ref().map(|g| &g[..]).unwrap_or("");
+                let label = self.group(group).into_iter().find(|l| l.name.to_uppercase() == "X-ABLABEL");
+                (p, label)
+            })
+            .collect()
+    }
+
+    /// Parse the `VERSION` property. Returns `None` if it is missing or unknown.
+    pub fn version_enum(&self) -> Option<VcardVersion> {
+        self.version().and_then(|v| VcardVersion::from_version_str(v.raw()))
+    }
+
+    /// Serialize the card for the given vCard version.
+    ///
+    /// The in-memory card is not modified. On output, `VERSION` is replaced and these known
+    /// differences between the versions are taken care of:
+    ///
+    /// * Type parameters: bare parameters in 2.1 (`TEL;HOME;VOICE`), uppercase `TYPE` lists in
+    ///   3.0 (`TYPE=HOME,VOICE`) and lowercase `TYPE` lists plus `PREF=1` in 4.0.
+    /// * `TEL` values are `tel:` URIs with `VALUE=uri` in 4.0, and plain text otherwise.
+    /// * Inline `PHOTO`, `LOGO`, `SOUND` and `KEY` values use data URIs in 4.0, and
+    ///   `ENCODING=b` (3.0) or `ENCODING=BASE64` (2.1) otherwise.
+    /// * Address labels are a `LABEL` parameter on `ADR` in 4.0, and separate `LABEL`
+    ///   properties otherwise. A `LABEL` property is only moved onto an `ADR` with the same
+    ///   types.
+    ///
+    /// Other properties are passed through unchanged.
+    pub fn write_versioned(&self, version: VcardVersion) -> VObjectResult<String> {
+        let mut c = self.0.clone();
+        c.set(Property::new("VERSION", version.as_str()));
+
+        if version == VcardVersion::V4_0 {
+            attach_labels(&mut c);
+        } else {
+            detach_labels(&mut c);
+        }
+
+        for prop in c.props.values_mut().flat_map(|props| props.iter_mut()) {
+            match &prop.name[..] {
+                "TEL" => convert_tel(prop, version),
+                "PHOTO" | "LOGO" | "SOUND" | "KEY" => convert_binary(prop, version),
+                _ => (),
+            }
+
+            if TYPED_PROPERTIES.contains(&&prop.name[..]) {
+                convert_types(prop, version);
+            }
+        }
+
+        Ok(write_component(&c))
+    }
+
+    /// Set the `FN` property, replacing existing ones. The value is escaped.
+    pub fn set_fullname(&mut self, fullname: &str) {
+        self.0.set(Property::new("FN", fullname));
+    }
+
+    /// Set the `N` property, replacing an existing one. The components are escaped.
+    pub fn set_name(&mut self,
+                    params: Parameters,
+                    surname: &str,
+                    given_name: &str,
+                    additional_names: &str,
+                    honorific_prefixes: &str,
+                    honorific_suffixes: &str) {
+        let raw_value = [surname, given_name, additional_names, honorific_prefixes, honorific_suffixes]
+            .iter()
+            .map(|c| escape_chars(c))
+            .collect::<Vec<_>>()
+            .join(";");
+        self.0.set(Property { name: String::from("N"), params, raw_value, prop_group: None });
+    }
+
+    /// Add an `EMAIL` property. The value is escaped.
+    pub fn add_email(&mut self, email: &str, params: Parameters) {
+        let mut prop = Property::new("EMAIL", email);
+        prop.params = params;
+        self.0.push(prop);
+    }
+
+    /// Add a `TEL` property. The value is escaped.
+    pub fn add_tel(&mut self, tel: &str, params: Parameters) {
+        let mut prop = Property::new("TEL", tel);
+        prop.params = params;
+        self.0.push(prop);
+    }
+
+    /// Set `REV` to the current time, e.g. after modifying the card.
+    #[cfg(feature = "timeconversions")]
+    pub fn touch_rev(&mut self) {
+        self.0.set(Property {
+            name: String::from("REV"),
+            params: Parameters::new(),
+            raw_value: Rev::now().into_raw(),
+            prop_group: None,
+        });
+    }
+
+    /// Remove all properties with the given name and return them.
+    pub fn remove_property(&mut self, name: &str) -> Vec<Property> {
+        self.0.remove(name).unwrap_or_default()
+    }
+
+    /// Merge two cards describing the same contact into one.
+    ///
+    /// The strategy decides which card is preferred. Of properties which may occur only once
+    /// (`N`, `UID`, `REV`, ...) and of `FN`, the value from the preferred card is kept, and the
+    /// other card only fills in missing ones. All other properties are combined, skipping
+    /// duplicates: `EMAIL` addresses are compared case-insensitively, `TEL` numbers by their
+    /// digits, everything else by value and parameters. Property groups of the other card are
+    /// renamed if they clash with groups of the preferred card.
+    pub fn merge(&self, other: &Vcard, strategy: MergeStrategy) -> Vcard {
+        let prefer_self = match strategy {
+            MergeStrategy::PreferSelf => true,
+            MergeStrategy::PreferOther => false,
+            MergeStrategy::PreferNewerRev => compare_rev(self, other) != Ordering::Less,
+        };
+        let (preferred, secondary) = if prefer_self { (self, other) } else { (other, self) };
+
+        let mut rv = preferred.0.clone();
+        let mut groups = rv.props
+            .values()
+            .flat_map(|props| props.iter())
+            .filter_map(|p| p.prop_group.clone())
+            .collect::<Vec<_>>();
+        let mut renamed: BTreeMap<String, String> = BTreeMap::new();
+
+        for (name, props) in &secondary.0.props {
+            if name == "FN" || SINGLETON_PROPERTIES.contains(&&name[..]) {
+                if !rv.props.contains_key(name) {
+                    rv.props.insert(name.clone(), props.clone());
+                }
+                continue;
+            }
+
+            for prop in props {
+                // Labels belong to their group, unused ones are dropped below
+                let duplicate = rv.get_all(name).iter().any(|p| merge_key(p) == merge_key(prop));
+                if duplicate && name != "X-ABLABEL" {
+                    continue;
+                }
+
+                let mut prop = prop.clone();
+                if let Some(group) = prop.prop_group.take() {
+                    let group = renamed.entry(group.clone()).or_insert_with(|| {
+                        let mut new = group.clone();
+                        let mut i = 1;
+                        while groups.iter().any(|g| g.eq_ignore_ascii_case(&new)) {
+                            new = format!("item{}", i);
+                            i += 1;
+                        }
+                        groups.push(new.clone());
+                        new
+                    });
+                    prop.prop_group = Some(group.clone());
+                }
+                rv.push(prop);
+            }
+        }
+
+        // Drop labels whose properties were all duplicates
+        let labelled = rv.props
+            .iter()
+            .filter(|&(name, _)| name != "X-ABLABEL")
+            .flat_map(|(_, props)| props.iter())
+            .filter_map(|p| p.prop_group.clone())
+            .collect::<Vec<_>>();
+        if let Some(labels) = rv.props.get_mut("X-ABLABEL") {
+            labels.retain(|l| l.prop_group.as_ref().is_none_or(|g| labelled.contains(g)));
+        }
+
+        Vcard(rv)
+    }
+
     fn set_properties(&mut self, props: BTreeMap<String, Vec<Property>>) {
         self.0.props = props;
     }
 
 }
 
+/// Which card wins in `Vcard::merge()`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MergeStrategy {
+    PreferSelf,
+    PreferOther,
+
+    /// Prefer the card with the later `REV`. A card without `REV` counts as older, if neither
+    /// has one the card `merge()` is called on wins.
+    PreferNewerRev,
+}
+
+/// Compare the `REV` of two cards, where a card without `REV` is older.
+///
+/// Timestamps are compared as instants if both can be parsed, so UTC offsets are taken into
+/// account. Otherwise their digits are compared, which works for UTC timestamps in both the basic
+/// (`20240101T120000Z`) and extended (`2024-01-01T12:00:00Z`) formats.
+fn compare_rev(card: &Vcard, other: &Vcard) -> Ordering {
+    match (card.rev(), other.rev()) {
+        (Some(rev), Some(other_rev)) => {
+            #[cfg(feature = "timeconversions")]
+            {
+                if let (Ok(a), Ok(b)) = (rev.as_datetime(), other_rev.as_datetime()) {
+                    return a.cmp(&b);
+                }
+            }
+            let digits = |rev: &Rev| rev.raw().chars().filter(char::is_ascii_digit).collect::<String>();
+            digits(&rev).cmp(&digits(&other_rev))
+        },
+        (rev, other_rev) => rev.is_some().cmp(&other_rev.is_some()),
+    }
+}
+
+/// What is compared to find duplicates when merging cards.
+fn merge_key(prop: &Property) -> (String, Option<&Parameters>) {
+    match &prop.name.to_ascii_uppercase()[..] {
+        "EMAIL" => {
+            let value = prop.raw_value.trim();
+            let value = if value.len() >= 7 && value[..7].eq_ignore_ascii_case("mailto:") { &value[7..] } else { value };
+            (value.to_lowercase(), None)
+        },
+        "TEL" => (prop.raw_value.chars().filter(char::is_ascii_digit).collect(), None),
+        _ => (prop.raw_value.clone(), Some(&prop.params)),
+    }
+}
+
+/// Properties whose `TYPE` parameter is a list of types, which `write_versioned` converts.
+const TYPED_PROPERTIES: &[&str] = &["ADR", "EMAIL", "IMPP", "LABEL", "TEL", "URL"];
+
+/// Collect the types of a property, from `TYPE` and from bare 2.1-style parameters.
+fn take_types(prop: &mut Property) -> Vec<String> {
+    let mut types: Vec<String> = vec![];
+    if let Some(t) = prop.params.remove("TYPE") {
+        types.extend(t.split(',').map(|t| t.trim().to_owned()).filter(|t| !t.is_empty()));
+    }
+
+    let bare = prop.params
+        .iter()
+        .filter(|&(k, v)| v.is_empty() && !BARE_ENCODINGS.contains(&&k.to_ascii_uppercase()[..]))
+        .map(|(k, _)| k.clone())
+        .collect::<Vec<_>>();
+    for k in bare {
+        prop.params.remove(&k);
+        types.push(k);
+    }
+
+    let mut rv: Vec<String> = vec![];
+    for t in types {
+        if !rv.iter().any(|x| x.eq_ignore_ascii_case(&t)) {
+            rv.push(t);
+        }
+    }
+    rv
+}
+
+fn convert_types(prop: &mut Property, version: VcardVersion) {
+    let mut types = take_types(prop);
+    let mut pref = prop.params.remove("PREF");
+    if let Some(i) = types.iter().position(|t| t.eq_ignore_ascii_case("PREF")) {
+        types.remove(i);
+        pref = pref.or_else(|| Some(String::from("1")));
+    }
+
+    match version {
+        VcardVersion::V2_1 => {
+            for t in types {
+                prop.params.insert(t.to_ascii_uppercase(), String::new());
+            }
+            if pref.is_some() {
+                prop.params.insert(String::from("PREF"), String::new());
+            }
+        },
+        VcardVersion::V3_0 => {
+            if pref.is_some() {
+                types.push(String::from("PREF"));
+            }
+            if !types.is_empty() {
+                let types = types.iter().map(|t| t.to_ascii_uppercase()).collect::<Vec<_>>();
+                prop.params.insert(String::from("TYPE"), types.join(","));
+            }
+        },
+        VcardVersion::V4_0 => {
+            if !types.is_empty() {
+                let types = types.iter().map(|t| t.to_ascii_lowercase()).collect::<Vec<_>>();
+                prop.params.insert(String::from("TYPE"), types.join(","));
+            }
+            if let Some(pref) = pref {
+                prop.params.insert(String::from("PREF"), pref);
+            }
+        },
+    }
+}
+
+fn convert_tel(prop: &mut Property, version: VcardVersion) {
+    let is_uri = prop.raw_value.len() >= 4 && prop.raw_value[..4].eq_ignore_ascii_case("tel:");
+    match version {
+        VcardVersion::V4_0 => {
+            if !is_uri {
+                prop.raw_value = format!("tel:{}", prop.raw_value.trim().replace(' ', "-"));
+            }
+            prop.params.insert(String::from("VALUE"), String::from("uri"));
+        },
+        _ => {
+            if is_uri {
+                prop.raw_value = prop.raw_value[4..].to_owned();
+                prop.params.remove("VALUE");
+            }
+        },
+    }
+}
+
+fn convert_binary(prop: &mut Property, version: VcardVersion) {
+    let media = match &prop.name[..] {
+        "PHOTO" | "LOGO" => "image",
+        "SOUND" => "audio",
+        _ => "application",
+    };
+
+    let inline = is_inline_binary(&prop.params);
+
+    match version {
+        VcardVersion::V4_0 => {
+            if inline {
+                let subtype = take_types(prop).into_iter().next();
+                prop.params.remove("ENCODING");
+                prop.params.remove("BASE64");
+                prop.raw_value = match subtype {
+                    Some(subtype) => format!("data:{}/{};base64,{}",
+                                             media, subtype.to_ascii_lowercase(), prop.raw_value),
+                    None => format!("data:;base64,{}", prop.raw_value),
+                };
+            } else if prop.params.get("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("uri")) {
+                prop.params.remove("VALUE");
+            }
+        },
+        _ => {
+            let data = match prop.raw_value.find(";base64,") {
+                Some(i) if prop.raw_value.starts_with("data:") => Some(i),
+                _ => None,
+            };
+
+            let encoding = if version == VcardVersion::V3_0 { "b" } else { "BASE64" };
+            if let Some(i) = data {
+                let subtype = prop.raw_value[5..i].split('/').nth(1).map(|s| s.to_ascii_uppercase());
+                prop.params.insert(String::from("ENCODING"), String::from(encoding));
+                if let Some(subtype) = subtype {
+                    prop.params.insert(String::from("TYPE"), subtype);
+                }
+                prop.raw_value = prop.raw_value[i + 8..].to_owned();
+            } else if inline {
+                // `b` is only known to 3.0, `BASE64` only to 2.1
+                prop.params.remove("BASE64");
+                prop.params.insert(String::from("ENCODING"), String::from(encoding));
+            } else if version == VcardVersion::V3_0 {
+                prop.params.insert(String::from("VALUE"), String::from("uri"));
+            }
+        },
+    }
+}
+
+/// Whether the value is base64 data marked by `ENCODING=b`, `ENCODING=BASE64` or a bare vCard 2.1
+/// `BASE64` parameter.
+fn is_inline_binary(params: &Parameters) -> bool {
+    params.get("ENCODING").is_some_and(|e| e.eq_ignore_ascii_case("b") || e.eq_ignore_ascii_case("BASE64"))
+        || params.get("BASE64").is_some_and(|v| v.is_empty())
+}
+
+/// A property holding `bytes`, as a `data:` URI for vCard 4.0 and with `ENCODING` and `TYPE`
+/// parameters otherwise.
+fn binary_property(name: &str, mut params: Parameters, bytes: &[u8], mime: &str, version: VcardVersion) -> Property {
+    let data = base64_encode(bytes);
+    let raw_value = match version {
+        VcardVersion::V4_0 => format!("data:{};base64,{}", mime, data),
+        _ => {
+            let encoding = if version == VcardVersion::V3_0 { "b" } else { "BASE64" };
+            params.insert(String::from("ENCODING"), String::from(encoding));
+            if let Some(subtype) = mime.split('/').nth(1).filter(|s| !s.is_empty()) {
+                params.insert(String::from("TYPE"), subtype.to_ascii_uppercase());
+            }
+            data
+        },
+    };
+
+    Property {
+        name: String::from(name),
+        params,
+        raw_value,
+        prop_group: None
+    }
+}
+
+fn same_types(a: &Property, b: &Property) -> bool {
+    let types = |p: &Property| {
+        let mut p = p.clone();
+        let mut t = take_types(&mut p).iter().map(|t| t.to_ascii_uppercase()).collect::<Vec<_>>();
+        t.sort();
+        t
+    };
+    types(a) == types(b)
+}
+
+/// Move `LABEL` properties onto the `ADR` with the same types, as vCard 4.0 wants it.
+fn attach_labels(c: &mut Component) {
+    let labels = match c.props.remove("LABEL") {
+        Some(labels) => labels,
+        None => return,
+    };
+
+    let mut remaining = vec![];
+    for label in labels {
+        let adrs = c.props.get_mut("ADR");
+        let mut candidates = adrs
+            .into_iter()
+            .flat_map(|adrs| adrs.iter_mut())
+            .filter(|adr| !adr.params.contains_key("LABEL") && same_types(adr, &label))
+            .collect::<Vec<_>>();
+
+        if candidates.len() == 1 {
+            // RFC 6868 caret encoding, since parameter values can't contain newlines
+            let value = unescape_chars(&label.raw_value)
+                .replace('^', "^^")
+                .replace('\n', "^n")
+                .replace('"', "^'");
+            candidates[0].params.insert(String::from("LABEL"), value);
+        } else {
+            remaining.push(label);
+        }
+    }
+
+    if !remaining.is_empty() {
+        c.props.insert(String::from("LABEL"), remaining);
+    }
+}
+
+/// Turn `LABEL` parameters on `ADR` into separate `LABEL` properties, as vCard 2.1 and 3.0 want
+/// it.
+fn detach_labels(c: &mut Component) {
+    let mut labels = vec![];
+    for adr in c.props.get_mut("ADR").into_iter().flat_map(|adrs| adrs.iter_mut()) {
+        if let Some(value) = adr.params.remove("LABEL") {
+            let value = value
+                .replace("^n", "\n")
+                .replace("^N", "\n")
+                .replace("^'", "\"")
+                .replace("^^", "^");
+            let mut label = Property {
+                name: String::from("LABEL"),
+                params: Parameters::new(),
+                raw_value: escape_chars(&value),
+                prop_group: adr.prop_group.clone(),
+            };
+            if let Some(t) = adr.params.get("TYPE") {
+                label.params.insert(String::from("TYPE"), t.to_owned());
+            }
+            labels.push(label);
+        }
+    }
+
+    for label in labels {
+        c.push(label);
+    }
+}
+
 impl Default for Vcard {
     fn default() -> Self {
         Vcard(Component::new(String::from("VCARD")))
@@ -94,56 +817,129 @@ impl Deref for Vcard {
     }
 }
 
+impl FromStr for Vcard {
+    type Err = VObjectError;
+
+    /// Same as `Vcard::build`
+    fn from_str(s: &str) -> VObjectResult<Vcard> {
+        Vcard::build(s)
+    }
+}
+
+impl fmt::Display for Vcard {
+    /// Same as `vobject::write_component` on the inner component.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&write_component(&self.0))
+    }
+}
+
+impl TryFrom<Component> for Vcard {
+    type Error = Component;
+
+    /// Same as `Vcard::from_component`
+    fn try_from(c: Component) -> RResult<Vcard, Component> {
+        Vcard::from_component(c)
+    }
+}
+
+impl From<Vcard> for Component {
+    fn from(item: Vcard) -> Component {
+        item.0
+    }
+}
+
+/// A problem found by `Vcard::validate()`.
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ValidationError {
+    /// A required property is missing.
+    #[error("missing required property {}", _0)]
+    MissingProperty(String),
+
+    /// A property which may occur at most once was found the given number of times.
+    #[error("property {} may occur at most once, found {} times", _0, _1)]
+    DuplicateProperty(String, usize),
+
+    /// `VERSION` is not the first property. Only reported by `Vcard::validate_source()`, and
+    /// only a warning.
+    #[error("VERSION should be the first property")]
+    VersionNotFirst,
+}
+
+impl ValidationError {
+    /// Whether the card is still usable, so that `Vcard::build_strict()` would accept it.
+    pub fn is_warning(&self) -> bool {
+        matches!(*self, ValidationError::VersionNotFirst)
+    }
+}
+
 /// A builder for building a Vcard object.
 pub struct VcardBuilder {
     properties: BTreeMap<String, Vec<Property>>
 }
 
 macro_rules! make_builder_fn {
+    // A list of values, joined by `$joinfn`
     (
-        fn $fnname:ident building $property_name:tt with_params,
-        $mapfn:expr => $( $arg_name:ident : $arg_type:ty ),*
+        fn $fnname:ident, $fnname_with_params:ident building $property_name:tt,
+        $joinfn:path => $arg_name:ident: list
     ) => {
-        pub fn $fnname(mut self, params: $crate::param::Parameters, $( $arg_name : $arg_type ),*) -> Self {
-            let raw_value = vec![ $( $arg_name ),* ]
-                .into_iter()
-                .map($mapfn)
-                .collect::<Vec<_>>()
-                .join(";");
+        pub fn $fnname<I, S>(self, $arg_name: I) -> Self
+            where I: IntoIterator<Item = S>,
+                  S: Into<String>
+        {
+            self.$fnname_with_params(Parameters::new(), $arg_name)
+        }
 
+        pub fn $fnname_with_params<I, S>(mut self, params: Parameters, $arg_name: I) -> Self
+            where I: IntoIterator<Item = S>,
+                  S: Into<String>
+        {
+            let values = $arg_name.into_iter().map(Into::into).collect::<Vec<String>>();
             let prop = Property {
                 name: String::from($property_name),
-                params: params,
-                raw_value: raw_value,
+                params,
+                raw_value: $joinfn(&values),
                 prop_group: None
             };
 
-            self.properties.entry(String::from($property_name)).or_insert(vec![]).push(prop);
+            self.properties.entry(String::from($property_name)).or_default().push(prop);
             self
         }
     };
 
     (
-        fn $fnname:ident building $property_name:tt,
+        fn $fnname:ident, $fnname_with_params:ident building $property_name:tt,
         $mapfn:expr => $( $arg_name:ident : $arg_type:ty ),*
     ) => {
-        pub fn $fnname(mut self, $( $arg_name : $arg_type ),*) -> Self {
-            let raw_value = vec![ $( $arg_name ),* ]
+        #[allow(clippy::too_many_arguments)]
+        pub fn $fnname(self, $( $arg_name : $arg_type ),*) -> Self {
+            self.$fnname_with_params(Parameters::new(), $( $arg_name ),*)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn $fnname_with_params(mut self, params: Parameters, $( $arg_name : $arg_type ),*) -> Self {
+            let raw_value = vec![ $( $arg_name.into() ),* ]
                 .into_iter()
                 .map($mapfn)
                 .collect::<Vec<_>>()
                 .join(";");
 
-
             let prop = Property {
                 name: String::from($property_name),
-                params: BTreeMap::new(),
-                raw_value: raw_value,
+                params,
+                raw_value,
                 prop_group: None
             };
-            self.properties.entry(String::from($property_name)).or_insert(vec![]).push(prop);
+
+            self.properties.entry(String::from($property_name)).or_default().push(prop);
             self
         }
+    };
+}
+
+impl Default for VcardBuilder {
+    fn default() -> Self {
+        VcardBuilder::new()
     }
 }
 
@@ -160,8 +956,8 @@ impl VcardBuilder {
         Ok(v)
     }
 
-    make_builder_fn!(fn with_adr building "ADR" with_params,
-                     |o| o.unwrap_or(String::from("")) =>
+    make_builder_fn!(fn with_adr, with_adr_with_params building "ADR",
+                     |o: Option<String>| o.unwrap_or_default() =>
                      pobox    : Option<String>,
                      ext      : Option<String>,
                      street   : Option<String>,
@@ -170,140 +966,707 @@ impl VcardBuilder {
                      code     : Option<String>,
                      country  : Option<String>);
 
-    make_builder_fn!(fn with_anniversary  building "ANNIVERSARY"        , |o| o => value: String);
-    make_builder_fn!(fn with_bday         building "BDAY" with_params   , |o| o => value: String);
-    make_builder_fn!(fn with_categories   building "CATEGORIES"         , |o| o.join(";") => org: Vec<String>);
-    make_builder_fn!(fn with_clientpidmap building "CLIENTPIDMAP"       , |o| o => raw: String);
-    make_builder_fn!(fn with_email        building "EMAIL"              , |o| o => email: String);
-    make_builder_fn!(fn with_fullname     building "FN"                 , |o| o => fullname: String);
-    make_builder_fn!(fn with_gender       building "GENDER" with_params , |o| o => value: String);
-    make_builder_fn!(fn with_geo          building "GEO"                , |o| o => uri: String);
-    make_builder_fn!(fn with_impp         building "IMPP"               , |o| o => uri: String);
-    make_builder_fn!(fn with_key          building "KEY"                , |o| o => uri: String);
-    make_builder_fn!(fn with_lang         building "LANG"               , |o| o => lang: String);
-    make_builder_fn!(fn with_logo         building "LOGO"               , |o| o => uri: String);
-    make_builder_fn!(fn with_member       building "MEMBER"             , |o| o => uri: String);
-
-    make_builder_fn!(fn with_name building "N" with_params,
-                     |o| o.unwrap_or(String::from("")) =>
+    make_builder_fn!(fn with_anniversary, with_anniversary_with_params   building "ANNIVERSARY" , |o: String| o                       => value: impl Into<String>);
+    make_builder_fn!(fn with_bday, with_bday_with_params                 building "BDAY"        , |o: String| o                       => value: impl Into<String>);
+    make_builder_fn!(fn with_categories, with_categories_with_params     building "CATEGORIES"  , join_text_list                      => categories: list);
+    make_builder_fn!(fn with_clientpidmap, with_clientpidmap_with_params building "CLIENTPIDMAP", |o: String| o                       => raw: impl Into<String>);
+    make_builder_fn!(fn with_email, with_email_with_params               building "EMAIL"       , |o: String| o                       => email: impl Into<String>);
+    make_builder_fn!(fn with_fullname, with_fullname_with_params         building "FN"          , |o: String| o                       => fullname: impl Into<String>);
+    make_builder_fn!(fn with_gender, with_gender_with_params             building "GENDER"      , |o: String| o                       => value: impl Into<String>);
+    make_builder_fn!(fn with_geo, with_geo_with_params                   building "GEO"         , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_impp, with_impp_with_params                 building "IMPP"        , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_key, with_key_with_params                   building "KEY"         , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_kind, with_kind_with_params                 building "KIND"        , |o: Kind| String::from(o.as_str())  => kind: Kind);
+    make_builder_fn!(fn with_lang, with_lang_with_params                 building "LANG"        , |o: String| o                       => lang: impl Into<String>);
+    make_builder_fn!(fn with_logo, with_logo_with_params                 building "LOGO"        , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_member, with_member_with_params             building "MEMBER"      , |o: String| o                       => uri: impl Into<String>);
+
+    make_builder_fn!(fn with_name, with_name_with_params building "N",
+                     |o: Option<String>| o.unwrap_or_default() =>
                      surname            : Option<String>,
                      given_name         : Option<String>,
                      additional_name    : Option<String>,
                      honorific_prefixes : Option<String>,
                      honorific_suffixes : Option<String>);
 
-    make_builder_fn!(fn with_nickname building "NICKNAME" with_params , |o| o => name: String);
-    make_builder_fn!(fn with_note     building "NOTE"                 , |o| o => text: String);
-    make_builder_fn!(fn with_org      building "ORG"                  , |o| o.join(";") => org: Vec<String>);
-    make_builder_fn!(fn with_photo    building "PHOTO" with_params    , |o| o => param: String);
-    make_builder_fn!(fn with_proid    building "PRODID"               , |o| o => param: String);
-    make_builder_fn!(fn with_related  building "RELATED"              , |o| o => uri: String);
-    make_builder_fn!(fn with_rev      building "REV"                  , |o| o => timestamp: String);
-    make_builder_fn!(fn with_role     building "ROLE"                 , |o| o => role: String);
-    make_builder_fn!(fn with_sound    building "SOUND"                , |o| o => uri: String);
-    make_builder_fn!(fn with_tel      building "TEL" with_params      , |o| o => value: String);
-    make_builder_fn!(fn with_title    building "TITLE"                , |o| o => title: String);
-    make_builder_fn!(fn with_tz       building "TZ"                   , |o| o => tz: String);
-    make_builder_fn!(fn with_uid      building "UID"                  , |o| o => uri: String);
-    make_builder_fn!(fn with_url      building "URL"                  , |o| o => uri: String);
-    make_builder_fn!(fn with_version  building "VERSION"              , |o| o => version: String);
-
-}
-
-create_data_type!(Adr);
-create_data_type!(Anniversary);
-create_data_type!(BDay);
-create_data_type!(Category);
-create_data_type!(ClientPidMap);
-create_data_type!(Email);
-create_data_type!(FullName);
-create_data_type!(Gender);
-create_data_type!(Geo);
-create_data_type!(IMPP);
-create_data_type!(Key);
-create_data_type!(Lang);
-create_data_type!(Logo);
-create_data_type!(Member);
-create_data_type!(Name);
-create_data_type!(NickName);
-create_data_type!(Note);
-create_data_type!(Organization);
-create_data_type!(PhoneNumber);
-create_data_type!(Photo);
-create_data_type!(Proid);
-create_data_type!(Related);
-create_data_type!(Rev);
-create_data_type!(Sound);
-create_data_type!(Tel);
-create_data_type!(Title);
-create_data_type!(Tz);
-create_data_type!(Uid);
-create_data_type!(Url);
-create_data_type!(Version);
+    make_builder_fn!(fn with_nickname, with_nickname_with_params         building "NICKNAME"    , |o: String| o                       => name: impl Into<String>);
+    make_builder_fn!(fn with_note, with_note_with_params                 building "NOTE"        , |o: String| o                       => text: impl Into<String>);
+    make_builder_fn!(fn with_org, with_org_with_params                   building "ORG"         , join_units                          => org: list);
+    make_builder_fn!(fn with_photo, with_photo_with_params               building "PHOTO"       , |o: String| o                       => param: impl Into<String>);
+    make_builder_fn!(fn with_proid, with_proid_with_params               building "PRODID"      , |o: String| o                       => param: impl Into<String>);
+    make_builder_fn!(fn with_related, with_related_with_params           building "RELATED"     , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_rev, with_rev_with_params                   building "REV"         , |o: String| o                       => timestamp: impl Into<String>);
+    make_builder_fn!(fn with_role, with_role_with_params                 building "ROLE"        , |o: String| o                       => role: impl Into<String>);
+    make_builder_fn!(fn with_sound, with_sound_with_params               building "SOUND"       , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_tel, with_tel_with_params                   building "TEL"         , |o: String| o                       => value: impl Into<String>);
+    make_builder_fn!(fn with_title, with_title_with_params               building "TITLE"       , |o: String| o                       => title: impl Into<String>);
+    make_builder_fn!(fn with_tz, with_tz_with_params                     building "TZ"          , |o: String| o                       => tz: impl Into<String>);
+    make_builder_fn!(fn with_uid, with_uid_with_params                   building "UID"         , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_url, with_url_with_params                   building "URL"         , |o: String| o                       => uri: impl Into<String>);
+    make_builder_fn!(fn with_version, with_version_with_params           building "VERSION"     , |o: String| o                       => version: impl Into<String>);
 
-/// A Name type
-///
-/// offers functionality to get firstname, middlenames and lastname.
-///
-/// The parsing behaviour is implemented in a way that splits at whitespace, following these rules:
-///
-/// * If there is only one element after splitting, this is considered the lastname
-/// * If there are two elements, this is firstname and lastname
-/// * If there are more than two elements, firstname and lastname are the first and last elements
-/// respectively, all others are middlenames.
-///
-impl Name {
+    /// Add a `RELATED` property pointing to the card with the given `UID`. Plain UUIDs get a
+    /// `urn:uuid:` prefix, URIs are used as they are.
+    pub fn with_related_uid(self, uid: &str, relation: RelationType) -> Self {
+        let mut params = Parameters::new();
+        params.insert(String::from("TYPE"), String::from(relation.as_str()));
+        self.with_related_with_params(params, uuid_uri(uid))
+    }
 
-    pub fn plain(&self) -> String {
-        self.0.clone()
+    /// Add a `RELATED` property with free text, such as a name, and `VALUE=text`.
+    pub fn with_related_text(self, text: &str, relation: RelationType) -> Self {
+        let mut params = Parameters::new();
+        params.insert(String::from("VALUE"), String::from("text"));
+        params.insert(String::from("TYPE"), String::from(relation.as_str()));
+        self.with_related_with_params(params, escape_chars(text))
     }
 
-    pub fn surname(&self) -> Option<String> {
-        self.0.split(";").nth(0).map(String::from)
+    /// Add an arbitrary property, such as `X-ABShowAs`, which has no dedicated builder function.
+    pub fn with_property(mut self, prop: Property) -> Self {
+        self.properties.entry(normalize_name(&prop.name).into_owned()).or_default().push(prop);
+        self
     }
 
-    pub fn given_name(&self) -> Option<String> {
-        self.0.split(";").nth(1).map(String::from)
+    /// Add `prop` as part of `group`, such as `item1` for `item1.URL:...`.
+    ///
+    /// Apple Contacts uses groups to attach custom labels to properties:
+    ///
+    /// ```
+    /// use vobject::vcard::Vcard;
+    /// use vobject::Property;
+    ///
+    /// let card = Vcard::builder()
+    ///     .with_fullname("Erika Mustermann")
+    ///     .with_grouped_property("item1", Property::new("URL", "https://example.com"))
+    ///     .with_grouped_property("item1", Property::new("X-ABLabel", "_$!<HomePage>!$_"))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(card.group("item1").len(), 2);
+    /// ```
+    pub fn with_grouped_property(mut self, group: &str, mut prop: Property) -> Self {
+        prop.prop_group = Some(String::from(group));
+        self.properties.entry(normalize_name(&prop.name).into_owned()).or_default().push(prop);
+        self
     }
 
-    pub fn additional_names(&self) -> Option<String> {
-        self.0.split(";").nth(2).map(String::from)
+    /// Set a random `urn:uuid:` value as `UID`. Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn with_uid_random(self) -> Self {
+        self.with_uid(uid::generate(UidKind::Vcard))
     }
 
-    pub fn honorific_prefixes(&self) -> Option<String> {
-        self.0.split(";").nth(3).map(String::from)
+    /// Embed a picture, with `mime` being its media type, such as `image/jpeg`.
+    ///
+    /// # Notice
+    ///
+    /// The encoding depends on the `VERSION` of the builder, so set that first. For 4.0 or if
+    /// the version is not set, a `data:` URI is written. For 3.0 and 2.1 the data is written with
+    /// `ENCODING` and `TYPE` parameters.
+    pub fn with_photo_binary(self, params: ::param::Parameters, bytes: &[u8], mime: &str) -> Self {
+        self.with_binary("PHOTO", params, bytes, mime)
     }
 
-    pub fn honorific_suffixes(&self) -> Option<String> {
-        self.0.split(";").nth(4).map(String::from)
+    /// Embed a public key, see `with_photo_binary`.
+    pub fn with_key_binary(self, params: ::param::Parameters, bytes: &[u8], mime: &str) -> Self {
+        self.with_binary("KEY", params, bytes, mime)
     }
 
-    /// Alias for Name::surname()
-    #[inline]
-    pub fn family_name(&self) -> Option<String> {
-        self.surname()
+    /// Embed a sound, see `with_photo_binary`.
+    pub fn with_sound_binary(self, params: ::param::Parameters, bytes: &[u8], mime: &str) -> Self {
+        self.with_binary("SOUND", params, bytes, mime)
+    }
+
+    fn with_binary(mut self, name: &str, params: ::param::Parameters, bytes: &[u8], mime: &str) -> Self {
+        let version = self.properties
+            .get("VERSION")
+            .and_then(|v| v.first())
+            .and_then(|v| VcardVersion::from_version_str(&v.raw_value))
+            .unwrap_or(VcardVersion::V4_0);
+
+        let prop = binary_property(name, params, bytes, mime, version);
+        self.properties.entry(String::from(name)).or_default().push(prop);
+        self
     }
 
 }
 
-#[cfg(test)]
-mod test {
-    use super::Vcard;
+create_data_type!(Adr, "ADR");
+create_data_type!(Anniversary, "ANNIVERSARY");
+create_data_type!(BDay, "BDAY");
+create_data_type!(Category, "CATEGORIES");
+create_data_type!(ClientPidMap, "CLIENTPIDMAP");
+create_data_type!(Email, "EMAIL");
+create_data_type!(FullName, "FN");
+create_data_type!(Gender, "GENDER");
+create_data_type!(Geo, "GEO");
+create_data_type!(IMPP, "IMPP");
+create_data_type!(Key, "KEY");
+create_data_type!(Lang, "LANG");
+create_data_type!(Logo, "LOGO");
+create_data_type!(Member, "MEMBER");
+create_data_type!(Name, "N");
+create_data_type!(NickName, "NICKNAME");
+create_data_type!(Note, "NOTE");
+create_data_type!(Organization, "ORG");
+create_data_type!(PhoneNumber);
+create_data_type!(Photo, "PHOTO");
+create_data_type!(Proid, "PRODID");
+create_data_type!(Related, "RELATED");
+create_data_type!(Rev, "REV");
+create_data_type!(Sound, "SOUND");
+create_data_type!(Tel, "TEL");
+create_data_type!(Title, "TITLE");
+create_data_type!(Tz, "TZ");
+create_data_type!(Uid, "UID");
+create_data_type!(Url, "URL");
+create_data_type!(Version, "VERSION");
 
-    #[test]
-    fn test_vcard_basic() {
-        let item = Vcard::build(
-            "BEGIN:VCARD\n\
-            VERSION:2.1\n\
-            N:Mustermann;Erika\n\
-            FN:Erika Mustermann\n\
-            ORG:Wikipedia\n\
-            TITLE:Oberleutnant\n\
-            PHOTO;JPEG:http://commons.wikimedia.org/wiki/File:Erika_Mustermann_2010.jpg\n\
-            TEL;WORK;VOICE:(0221) 9999123\n\n\n\
-            TEL;HOME;VOICE:(0221) 1234567\n\
-            ADR;HOME:;;Heidestrasse 17;Koeln;;51147;Deutschland\n\
-            EMAIL;PREF;INTERNET:erika@mustermann.de\n\
+make_chrono_conversions!(Anniversary);
+make_chrono_conversions!(BDay);
+make_chrono_conversions!(Rev);
+
+make_timestamp_conversions!(Rev);
+
+make_utc_offset_conversions!(Tz, parse_vcard_utc_offset);
+
+impl Tz {
+    /// The timezone name, such as `America/New_York`, if the value is text rather than a UTC
+    /// offset.
+    pub fn as_timezone_name(&self) -> Option<&str> {
+        let is_text = self.1.get("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("text"));
+        if is_text || self.utc_offset().is_none() {
+            Some(&self.0)
+        } else {
+            None
+        }
+    }
+}
+
+/// The vCard versions, which differ in how some values are serialized.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum VcardVersion {
+    V2_1,
+    V3_0,
+    V4_0,
+}
+
+impl VcardVersion {
+    /// Parse the value of a `VERSION` property, e.g. `"4.0"`.
+    pub fn from_version_str(s: &str) -> Option<VcardVersion> {
+        match s.trim() {
+            "2.1" => Some(VcardVersion::V2_1),
+            "3.0" => Some(VcardVersion::V3_0),
+            "4.0" => Some(VcardVersion::V4_0),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            VcardVersion::V2_1 => "2.1",
+            VcardVersion::V3_0 => "3.0",
+            VcardVersion::V4_0 => "4.0",
+        }
+    }
+}
+
+create_value_enum!(
+    /// The kind of object a card represents, from the `KIND` property.
+    Kind (from_kind_str, as_str) {
+        Individual => "individual",
+        Group      => "group",
+        Org        => "org",
+        Location   => "location",
+    });
+
+impl Member {
+    /// The UUID of a `urn:uuid:` member, or `None` for other URIs.
+    pub fn uuid(&self) -> Option<&str> {
+        urn_uuid(&self.0)
+    }
+}
+
+create_value_enum!(
+    /// A relationship from the `TYPE` parameter of a `RELATED` property.
+    RelationType (from_type_str, as_str) {
+        Spouse    => "spouse",
+        Child     => "child",
+        Parent    => "parent",
+        Friend    => "friend",
+        Colleague => "colleague",
+        Emergency => "emergency",
+    });
+
+/// What a `RELATED` property points to.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum RelatedTarget {
+    /// Another card, by the UUID of a `urn:uuid:` reference.
+    Uid(String),
+
+    /// Any other URI, such as `mailto:` or `https:`.
+    Uri(String),
+
+    /// Unescaped free text, with `VALUE=text`.
+    Text(String),
+}
+
+impl Related {
+    /// The relationships in the `TYPE` parameter, e.g. `[Friend, Colleague]` for
+    /// `RELATED;TYPE=friend,colleague:...`.
+    pub fn relation_types(&self) -> Vec<RelationType> {
+        self.1.get_values("TYPE").into_iter().map(RelationType::from_type_str).collect()
+    }
+
+    /// The target of the relationship.
+    pub fn target(&self) -> RelatedTarget {
+        if self.1.get("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("text")) {
+            return RelatedTarget::Text(unescape_chars(&self.0));
+        }
+        match urn_uuid(&self.0) {
+            Some(uuid) => RelatedTarget::Uid(String::from(uuid)),
+            None => RelatedTarget::Uri(self.0.clone()),
+        }
+    }
+}
+
+/// The messaging protocol of an `IMPP` property, derived from the URI scheme.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ImppProtocol {
+    Xmpp,
+    Sip,
+    Irc,
+    Skype,
+    Other(String),
+}
+
+impl IMPP {
+    /// The URI scheme, e.g. `xmpp` for `xmpp:alice@jabber.org`. `None` if the value is not a URI.
+    pub fn scheme(&self) -> Option<&str> {
+        split_uri_scheme(&self.0).map(|(scheme, _)| scheme)
+    }
+
+    /// The part after the scheme, e.g. `alice@jabber.org` for `xmpp:alice@jabber.org`.
+    pub fn target(&self) -> Option<&str> {
+        split_uri_scheme(&self.0).map(|(_, target)| target)
+    }
+
+    /// The protocol for known schemes, matched case-insensitively. Unknown schemes become
+    /// `Other` with the lowercased scheme.
+    pub fn protocol(&self) -> Option<ImppProtocol> {
+        let scheme = self.scheme()?.to_ascii_lowercase();
+        Some(match &scheme[..] {
+            "xmpp" => ImppProtocol::Xmpp,
+            "sip" | "sips" => ImppProtocol::Sip,
+            "irc" | "ircs" => ImppProtocol::Irc,
+            "skype" => ImppProtocol::Skype,
+            _ => ImppProtocol::Other(scheme),
+        })
+    }
+}
+
+impl Url {
+    /// The URI scheme, e.g. `https`. `None` if the value is not a URI.
+    pub fn scheme(&self) -> Option<&str> {
+        split_uri_scheme(&self.0).map(|(scheme, _)| scheme)
+    }
+
+    /// The part after the scheme, e.g. `//example.com/` for `https://example.com/`.
+    pub fn target(&self) -> Option<&str> {
+        split_uri_scheme(&self.0).map(|(_, target)| target)
+    }
+}
+
+impl Key {
+    /// The URI scheme, e.g. `https` or `data`. `None` if the value is not a URI, such as an
+    /// inline key of vCard 3.0.
+    pub fn scheme(&self) -> Option<&str> {
+        split_uri_scheme(&self.0).map(|(scheme, _)| scheme)
+    }
+
+    /// The part after the scheme.
+    pub fn target(&self) -> Option<&str> {
+        split_uri_scheme(&self.0).map(|(_, target)| target)
+    }
+
+    /// Decode a base64 `data:` URI such as `data:application/pgp-keys;base64,...`.
+    pub fn as_data_uri_bytes(&self) -> VObjectResult<Vec<u8>> {
+        decode_data_uri(&self.0)
+            .map(|(_, data)| data)
+            .ok_or_else(|| VObjectError::InvalidValue("KEY", self.0.clone()))
+    }
+}
+
+impl ClientPidMap {
+    /// The source ID which `PID` parameters refer to, before the first `;`.
+    pub fn pid(&self) -> Option<u32> {
+        self.0.split(';').next()?.trim().parse().ok()
+    }
+
+    /// The URI identifying the client, after the first `;`.
+    pub fn uri(&self) -> Option<&str> {
+        self.0.split_once(';').map(|(_, uri)| uri).filter(|uri| !uri.is_empty())
+    }
+}
+
+/// Decoded image data of a `PHOTO` or `LOGO`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImageData {
+    /// The media type, such as `image/jpeg`, if it is given or the format was recognized.
+    pub mime: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+/// The image of a `PHOTO` or `LOGO`, either embedded or referenced by URL.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ImageRef {
+    Data(ImageData),
+    Url(String),
+}
+
+/// Add `data` and `from_bytes` to an image data type.
+macro_rules! make_image_functions {
+    ($name:ident) => {
+        impl $name {
+            /// The embedded image, either from a `data:` URI as in vCard 4.0 or from base64 data
+            /// marked by an `ENCODING` parameter as in earlier versions. Other values are returned
+            /// as `ImageRef::Url`.
+            ///
+            /// The media type is taken from the `data:` URI or the `TYPE` parameter, with `JPEG`
+            /// meaning `image/jpeg`. If neither gives one, it is guessed from the data. Corrupted
+            /// base64 yields an error.
+            pub fn data(&self) -> VObjectResult<ImageRef> {
+                image_data(&self.0, &self.1)
+            }
+
+            /// Embed an image in the form appropriate for `version`, see
+            /// `VcardBuilder::with_photo_binary`.
+            pub fn from_bytes(bytes: &[u8], mime: &str, version: VcardVersion) -> $name {
+                let prop = binary_property(<$name as ::property::TypedProperty>::NAME,
+                                           Parameters::new(), bytes, mime, version);
+                $name::from(prop)
+            }
+        }
+    }
+}
+
+make_image_functions!(Logo);
+make_image_functions!(Photo);
+
+fn image_data(raw: &str, params: &Parameters) -> VObjectResult<ImageRef> {
+    let invalid = || VObjectError::InvalidValue("BINARY", String::from(raw));
+    let (mime, bytes) = if split_uri_scheme(raw).is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case("data")) {
+        let (mime, bytes) = decode_data_uri(raw).ok_or_else(invalid)?;
+        (mime.map(|m| m.to_ascii_lowercase()), bytes)
+    } else if is_inline_binary(params) {
+        let mime = params.types().into_iter().next().map(|t| {
+            let t = t.to_ascii_lowercase();
+            if t.contains('/') { t } else { format!("image/{}", t) }
+        });
+        (mime, base64_decode(raw).ok_or_else(invalid)?)
+    } else {
+        return Ok(ImageRef::Url(String::from(raw.trim())));
+    };
+
+    let mime = mime.or_else(|| sniff_image_mime(&bytes).map(String::from));
+    Ok(ImageRef::Data(ImageData { mime, bytes }))
+}
+
+impl NickName {
+    /// The unescaped nicknames, as the property may hold several separated by commas.
+    pub fn values(&self) -> Vec<String> {
+        split_text_list(&self.0)
+    }
+}
+
+impl Category {
+    /// Create a comma-separated list, escaping commas within the entries.
+    pub fn from_values(values: Vec<String>) -> Category {
+        Category::from_raw(join_text_list(&values))
+    }
+
+    /// The unescaped entries of the list.
+    pub fn values(&self) -> Vec<String> {
+        split_text_list(&self.0)
+    }
+}
+
+impl Organization {
+    /// The unescaped units of the organization, starting with its name, followed by the
+    /// department and further sub-units: `ACME Inc.;Research;Widgets`.
+    pub fn units(&self) -> Vec<String> {
+        split_unescaped(&self.0, ';').into_iter().map(unescape_chars).collect()
+    }
+
+    /// The organization name, which is the first unit.
+    pub fn name(&self) -> String {
+        self.units().into_iter().next().unwrap_or_default()
+    }
+
+    /// The entries of the `SORT-AS` parameter, such as `["ABC"]` for `ORG;SORT-AS=ABC:The ABC Inc.`.
+    pub fn sort_as(&self) -> Option<Vec<String>> {
+        self.1.get("SORT-AS")?;
+        Some(self.1.get_values("SORT-AS").into_iter().map(String::from).collect())
+    }
+}
+
+impl Gender {
+    /// The sex component, such as `M`, `F`, `O`, `N` or `U`. `None` if it is empty.
+    pub fn sex(&self) -> Option<&str> {
+        split_unescaped(&self.0, ';').into_iter().next().filter(|s| !s.is_empty())
+    }
+
+    /// The unescaped free-text gender identity following the semicolon, if any.
+    pub fn identity(&self) -> Option<String> {
+        let (_, identity) = self.0.split_at(self.sex().map_or(0, str::len));
+        identity.strip_prefix(';').filter(|s| !s.is_empty()).map(unescape_chars)
+    }
+}
+
+/// Escape each unit of a structured value and join them with semicolons.
+fn join_units(units: &[String]) -> String {
+    units.iter().map(|u| escape_chars(u)).collect::<Vec<_>>().join(";")
+}
+
+/// A date which may lack the year or the day, as allowed for `BDAY` and `ANNIVERSARY` in
+/// vCard 4.0.
+#[cfg(feature = "timeconversions")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PartialDate {
+    Full(NaiveDate),
+    MonthDay { month: u8, day: u8 },
+    YearMonth { year: i32, month: u8 },
+    YearOnly(i32),
+}
+
+#[cfg(feature = "timeconversions")]
+impl PartialDate {
+    /// Parse `19850412`, `1985-04-12`, `--0412`, `--04-12`, `1985-04` or `1985`.
+    ///
+    /// A time following the date, as in `19850412T120000Z`, is ignored.
+    pub fn parse(raw: &str) -> VObjectResult<PartialDate> {
+        let invalid = || VObjectError::InvalidValue("DATE", String::from(raw));
+        let date = raw.trim().split('T').next().unwrap_or("");
+        if date.is_empty() || !date.bytes().all(|b| b.is_ascii_digit() || b == b'-') {
+            return Err(invalid());
+        }
+
+        let month_day = |month: &str, day: &str| -> VObjectResult<PartialDate> {
+            let month = month.parse().map_err(|_| invalid())?;
+            let day = day.parse().map_err(|_| invalid())?;
+            // Validate against a leap year, so that February 29th is accepted
+            NaiveDate::from_ymd_opt(2000, u32::from(month), u32::from(day)).ok_or_else(invalid)?;
+            Ok(PartialDate::MonthDay { month, day })
+        };
+
+        match date.len() {
+            8 if !date.contains('-') => NaiveDate::parse_from_str(date, DATE_FMT)
+                .map(PartialDate::Full)
+                .map_err(|_| invalid()),
+            10 if !date.starts_with('-') => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(PartialDate::Full)
+                .map_err(|_| invalid()),
+            6 if date.starts_with("--") => month_day(&date[2..4], &date[4..6]),
+            7 if date.starts_with("--") && &date[4..5] == "-" => month_day(&date[2..4], &date[5..7]),
+            7 if &date[4..5] == "-" => {
+                let year = date[..4].parse().map_err(|_| invalid())?;
+                let month = date[5..7].parse().map_err(|_| invalid())?;
+                if !(1..=12).contains(&month) {
+                    return Err(invalid());
+                }
+                Ok(PartialDate::YearMonth { year, month })
+            },
+            4 => date.parse().map(PartialDate::YearOnly).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Whether `date` is an anniversary of this date, i.e. has the same month and day.
+    ///
+    /// Always `false` if the day is unknown.
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        let (month, day) = match *self {
+            PartialDate::Full(d) => (d.month(), d.day()),
+            PartialDate::MonthDay { month, day } => (u32::from(month), u32::from(day)),
+            _ => return false,
+        };
+        date.month() == month && date.day() == day
+    }
+}
+
+#[cfg(feature = "timeconversions")]
+impl BDay {
+    pub fn as_date(&self) -> VObjectResult<PartialDate> {
+        PartialDate::parse(&self.0)
+    }
+}
+
+#[cfg(feature = "timeconversions")]
+impl Anniversary {
+    pub fn as_date(&self) -> VObjectResult<PartialDate> {
+        PartialDate::parse(&self.0)
+    }
+}
+
+impl Tel {
+    /// The uppercased types, e.g. `["CELL", "VOICE"]`.
+    pub fn types(&self) -> Vec<String> {
+        self.1.types()
+    }
+
+    fn has_type(&self, t: &str) -> bool {
+        self.types().iter().any(|x| x == t)
+    }
+
+    pub fn is_cell(&self) -> bool {
+        self.has_type("CELL")
+    }
+
+    pub fn is_work(&self) -> bool {
+        self.has_type("WORK")
+    }
+
+    pub fn is_home(&self) -> bool {
+        self.has_type("HOME")
+    }
+
+    pub fn is_fax(&self) -> bool {
+        self.has_type("FAX")
+    }
+
+    /// Whether the number is preferred, either by `TYPE=PREF` (vCard 3.0) or by a `PREF`
+    /// parameter (vCard 4.0, where `PREF=1` is the most preferred).
+    pub fn preferred(&self) -> bool {
+        self.has_type("PREF") || self.1.contains_key("PREF")
+    }
+
+    /// The number without a `tel:` scheme, spaces, dashes, parentheses and dots.
+    pub fn normalized_number(&self) -> String {
+        let raw = self.0.trim();
+        let raw = if raw.len() >= 4 && raw[..4].eq_ignore_ascii_case("tel:") { &raw[4..] } else { raw };
+        raw.chars().filter(|c| !matches!(c, ' ' | '-' | '(' | ')' | '.')).collect()
+    }
+}
+
+impl Email {
+    /// The address, without surrounding whitespace and `mailto:` scheme.
+    pub fn address(&self) -> &str {
+        let raw = self.0.trim();
+        if raw.len() >= 7 && raw[..7].eq_ignore_ascii_case("mailto:") {
+            &raw[7..]
+        } else {
+            raw
+        }
+    }
+
+    /// The uppercased types, e.g. `["INTERNET", "WORK"]`.
+    pub fn types(&self) -> Vec<String> {
+        self.1.types()
+    }
+
+    /// Whether the address is preferred, either by `TYPE=PREF` or by a `PREF` parameter.
+    pub fn is_preferred(&self) -> bool {
+        self.types().iter().any(|t| t == "PREF") || self.1.contains_key("PREF")
+    }
+
+    /// A loose syntax check: exactly one `@`, with something on both sides.
+    pub fn is_valid(&self) -> bool {
+        let mut parts = self.address().split('@');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(local), Some(domain), None) => !local.is_empty() && !domain.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+impl Geo {
+    /// Create a value in the format of the given vCard version: a `geo:` URI for 4.0,
+    /// `LAT;LON` otherwise.
+    pub fn from_coords(lat: f64, lon: f64, version: VcardVersion) -> Geo {
+        match version {
+            VcardVersion::V4_0 => Geo::from_raw(format!("geo:{},{}", lat, lon)),
+            _ => Geo::from_raw(format!("{};{}", lat, lon)),
+        }
+    }
+
+    /// Latitude and longitude, from either the vCard 4.0 `geo:` URI or the older `LAT;LON` form.
+    pub fn coordinates(&self) -> VObjectResult<(f64, f64)> {
+        parse_geo(&self.0)
+    }
+}
+
+/// A Name type
+///
+/// offers functionality to get firstname, middlenames and lastname.
+///
+/// The parsing behaviour is implemented in a way that splits at whitespace, following these rules:
+///
+/// * If there is only one element after splitting, this is considered the lastname
+/// * If there are two elements, this is firstname and lastname
+/// * If there are more than two elements, firstname and lastname are the first and last elements
+///   respectively, all others are middlenames.
+///
+impl Name {
+
+    pub fn plain(&self) -> String {
+        self.0.clone()
+    }
+
+    pub fn surname(&self) -> Option<String> {
+        self.0.split(";").nth(0).map(String::from)
+    }
+
+    pub fn given_name(&self) -> Option<String> {
+        self.0.split(";").nth(1).map(String::from)
+    }
+
+    pub fn additional_names(&self) -> Option<String> {
+        self.0.split(";").nth(2).map(String::from)
+    }
+
+    pub fn honorific_prefixes(&self) -> Option<String> {
+        self.0.split(";").nth(3).map(String::from)
+    }
+
+    pub fn honorific_suffixes(&self) -> Option<String> {
+        self.0.split(";").nth(4).map(String::from)
+    }
+
+    /// Alias for Name::surname()
+    #[inline]
+    pub fn family_name(&self) -> Option<String> {
+        self.surname()
+    }
+
+    /// The entries of the `SORT-AS` parameter, e.g. `["Mustermann", "Erika"]` for
+    /// `N;SORT-AS="Mustermann,Erika":...`.
+    pub fn sort_as(&self) -> Option<Vec<String>> {
+        self.1.get("SORT-AS")?;
+        Some(self.1.get_values("SORT-AS").into_iter().map(String::from).collect())
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))] use std::prelude::*;
+    use super::Vcard;
+    use super::ValidationError;
+    use error::VObjectError;
+
+    #[test]
+    fn test_vcard_basic() {
+        let item = Vcard::build(
+            "BEGIN:VCARD\n\
+            VERSION:2.1\n\
+            N:Mustermann;Erika\n\
+            FN:Erika Mustermann\n\
+            ORG:Wikipedia\n\
+            TITLE:Oberleutnant\n\
+            PHOTO;JPEG:http://commons.wikimedia.org/wiki/File:Erika_Mustermann_2010.jpg\n\
+            TEL;WORK;VOICE:(0221) 9999123\n\n\n\
+            TEL;HOME;VOICE:(0221) 1234567\n\
+            ADR;HOME:;;Heidestrasse 17;Koeln;;51147;Deutschland\n\
+            EMAIL;PREF;INTERNET:erika@mustermann.de\n\
             REV:20140301T221110Z\n\
             END:VCARD\n\r\n\n").unwrap();
 
@@ -321,27 +1684,22 @@ mod test {
         use component::write_component;
 
         let build = Vcard::builder()
-            .with_name(parameters!(),
-                       None,
-                       Some("Mustermann".into()),
-                       None,
-                       Some("Erika".into()),
-                       None)
-            .with_fullname("Erika Mustermann".into())
-            .with_org(vec!["Wikipedia".into()])
-            .with_title("Oberleutnant".into())
-            .with_tel(parameters!("TYPE" => "WORK"), "(0221) 9999123".into())
-            .with_tel(parameters!("TYPE" => "HOME"), "(0221) 1234567".into())
-            .with_adr(parameters!("TYPE" => "HOME"),
-                      None,
-                      None,
-                      Some("Heidestrasse 17".into()),
-                      Some("Koeln".into()),
-                      None,
-                      Some("51147".into()),
-                      Some("Deutschland".into()))
-            .with_email("erika@mustermann.de".into())
-            .with_rev("20140301T221110Z".into())
+            .with_name(None, Some("Mustermann".into()), None, Some("Erika".into()), None)
+            .with_fullname("Erika Mustermann")
+            .with_org(["Wikipedia"])
+            .with_title("Oberleutnant")
+            .with_tel_with_params(parameters!("TYPE" => "WORK"), "(0221) 9999123")
+            .with_tel_with_params(parameters!("TYPE" => "HOME"), "(0221) 1234567")
+            .with_adr_with_params(parameters!("TYPE" => "HOME"),
+                                  None,
+                                  None,
+                                  Some("Heidestrasse 17".into()),
+                                  Some("Koeln".into()),
+                                  None,
+                                  Some("51147".into()),
+                                  Some("Deutschland".into()))
+            .with_email("erika@mustermann.de")
+            .with_rev("20140301T221110Z")
             .build()
             .unwrap();
 
@@ -364,5 +1722,894 @@ mod test {
         assert_eq!(expected, build_string);
     }
 
-}
+    #[test]
+    fn test_vcard_builder_conversions() {
+        use property::Property;
 
+        let email = String::from("erika@example.com");
+        let card = Vcard::builder()
+            .with_version("4.0")
+            .with_fullname("Erika Mustermann")
+            .with_fullname_with_params(parameters!("LANGUAGE" => "de"), "Erika Mustermann")
+            .with_email(email)
+            .with_tel_with_params(parameters!("TYPE" => "cell"), "+49 170 1234567")
+            .with_note(format!("Added on {}", "2024-01-15"))
+            .with_property(Property::new("X-ABShowAs", "COMPANY"))
+            .build()
+            .unwrap();
+
+        assert_eq!(card.fullname().len(), 2);
+        assert_eq!(card.fullname_for_lang("de").unwrap().language(), Some("de"));
+        assert_eq!(card.email()[0].raw(), "erika@example.com");
+        assert!(card.tel()[0].is_cell());
+        assert_eq!(card.note()[0].raw(), "Added on 2024-01-15");
+        assert!(card.note()[0].params().is_empty());
+        assert_eq!(card.get_only("X-ABSHOWAS").unwrap().raw_value, "COMPANY");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_vcard_builder_uid_random() {
+        let build = Vcard::builder()
+            .with_fullname("Erika Mustermann")
+            .with_uid_random()
+            .build()
+            .unwrap();
+        let uid = build.uid().unwrap().into_raw();
+        assert!(uid.starts_with("urn:uuid:"));
+        assert_eq!(uid.len(), 45);
+        assert_eq!(&uid[23..24], "4");
+        assert_ne!(Vcard::builder().with_uid_random().build().unwrap().uid().unwrap().into_raw(), uid);
+    }
+
+    #[test]
+    fn test_vcard_builder_binary() {
+        use component::write_component;
+
+        let build = Vcard::builder()
+            .with_fullname("Erika Mustermann")
+            .with_photo_binary(parameters!(), b"foobar", "image/jpeg")
+            .build()
+            .unwrap();
+        let written = write_component(&build);
+        assert!(written.contains("\r\nPHOTO:data:image/jpeg;base64,Zm9vYmFy\r\n"));
+
+        let build = Vcard::builder()
+            .with_version("3.0")
+            .with_photo_binary(parameters!(), b"fooba", "image/png")
+            .with_sound_binary(parameters!(), b"f", "audio/ogg")
+            .with_proid("-//Example//EN")
+            .build()
+            .unwrap();
+        let written = write_component(&build);
+        assert!(written.contains("\r\nPHOTO;ENCODING=b;TYPE=PNG:Zm9vYmE=\r\n"));
+        assert!(written.contains("\r\nSOUND;ENCODING=b;TYPE=OGG:Zg==\r\n"));
+        assert_eq!(build.proid().unwrap().raw(), "-//Example//EN");
+    }
+
+    #[test]
+    fn test_vcard_validate() {
+        let item = Vcard::build(
+            "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            N:Mustermann;Erika\n\
+            N:Musterfrau;Erika\n\
+            UID:urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1\n\
+            END:VCARD\n").unwrap();
+
+        assert!(item.name().is_none());
+        assert_eq!(item.validate(), vec![
+            ValidationError::MissingProperty("FN".into()),
+            ValidationError::DuplicateProperty("N".into(), 2),
+        ]);
+    }
+
+    #[test]
+    fn test_vcard_build_strict() {
+        let valid = "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            N:Mustermann;Erika\n\
+            FN:Erika Mustermann\n\
+            END:VCARD\n";
+        assert!(Vcard::build_strict(valid).is_ok());
+
+        let duplicate_n = "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            N:Mustermann;Erika\n\
+            N:Musterfrau;Erika\n\
+            FN:Erika Mustermann\n\
+            END:VCARD\n";
+        match Vcard::build_strict(duplicate_n) {
+            Err(VObjectError::InvalidVcard(errors)) => {
+                assert_eq!(errors, vec![ValidationError::DuplicateProperty("N".into(), 2)]);
+            },
+            x => panic!("unexpected result: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_vcard_geo() {
+        use super::{Geo, VcardVersion};
+
+        let v4 = Geo::from_coords(37.386013, -122.082932, VcardVersion::V4_0);
+        assert_eq!(v4.raw(), "geo:37.386013,-122.082932");
+        assert_eq!(v4.coordinates().unwrap(), (37.386013, -122.082932));
+
+        let v3 = Geo::from_coords(37.386013, -122.082932, VcardVersion::V3_0);
+        assert_eq!(v3.raw(), "37.386013;-122.082932");
+        assert_eq!(v3.coordinates().unwrap(), (37.386013, -122.082932));
+
+        assert_eq!(Geo::from_raw("geo:37.786971,-122.399677;u=35".into()).coordinates().unwrap(),
+                   (37.786971, -122.399677));
+        assert!(Geo::from_raw("37.386013".into()).coordinates().is_err());
+        assert!(Geo::from_raw("geo:north,west".into()).coordinates().is_err());
+    }
+
+    #[test]
+    fn test_vcard_tel() {
+        let item = Vcard::build(
+            "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            FN:Erika Mustermann\n\
+            TEL;TYPE=CELL,VOICE;PREF=1:+1 (555) 010-9999\n\
+            TEL;TYPE=\"work,fax\";VALUE=uri:tel:+49.221.555\n\
+            TEL;HOME;ENCODING=QUOTED-PRINTABLE:0221 555\n\
+            END:VCARD\n").unwrap();
+        let tels = item.tel();
+
+        assert_eq!(tels[0].types(), vec!["CELL", "VOICE"]);
+        assert!(tels[0].is_cell() && tels[0].preferred());
+        assert!(!tels[0].is_work() && !tels[0].is_home() && !tels[0].is_fax());
+        assert_eq!(tels[0].normalized_number(), "+15550109999");
+
+        assert_eq!(tels[1].types(), vec!["WORK", "FAX"]);
+        assert!(tels[1].is_work() && tels[1].is_fax() && !tels[1].preferred());
+        assert_eq!(tels[1].normalized_number(), "+49221555");
+
+        assert_eq!(tels[2].types(), vec!["HOME"]);
+        assert_eq!(tels[2].normalized_number(), "0221555");
+    }
+
+    #[test]
+    fn test_vcard_email() {
+        use super::Email;
+
+        let item = Vcard::build(
+            "BEGIN:VCARD\n\
+            VERSION:2.1\n\
+            N:Mustermann;Erika\n\
+            FN:Erika Mustermann\n\
+            EMAIL;INTERNET: mailto:erika@example.com \n\
+            EMAIL;PREF;INTERNET:erika@mustermann.de\n\
+            EMAIL;TYPE=work:erika@@example.com\n\
+            END:VCARD\n").unwrap();
+        let emails = item.email();
+
+        assert_eq!(emails[0].address(), "erika@example.com");
+        assert_eq!(emails[0].types(), vec!["INTERNET"]);
+        assert!(!emails[0].is_preferred() && emails[0].is_valid());
+
+        assert_eq!(emails[1].types(), vec!["INTERNET", "PREF"]);
+        assert!(emails[1].is_preferred() && emails[1].is_valid());
+
+        assert_eq!(emails[2].types(), vec!["WORK"]);
+        assert!(!emails[2].is_valid());
+        assert!(!Email::from_raw("@example.com".into()).is_valid());
+
+        assert_eq!(item.primary_email().unwrap().address(), "erika@mustermann.de");
+        let first = Vcard::build("BEGIN:VCARD\nEMAIL:a@example.com\nEMAIL:b@example.com\nEND:VCARD\n").unwrap();
+        assert_eq!(first.primary_email().unwrap().address(), "a@example.com");
+        let none = Vcard::build("BEGIN:VCARD\nFN:Nobody\nEND:VCARD\n").unwrap();
+        assert!(none.primary_email().is_none());
+    }
+
+    #[cfg(feature = "timeconversions")]
+    #[test]
+    fn test_vcard_rev() {
+        use chrono::{TimeZone, Utc};
+        use super::Rev;
+
+        let expected = Utc.with_ymd_and_hms(2014, 3, 1, 22, 11, 10).unwrap();
+        assert_eq!(Rev::from_raw("20140301T221110Z".into()).as_datetime().unwrap(), expected);
+        assert_eq!(Rev::from_raw("2014-03-01T22:11:10Z".into()).as_datetime().unwrap(), expected);
+        assert_eq!(Rev::from_raw("2014-03-01T23:11:10+01:00".into()).as_datetime().unwrap(), expected);
+        assert_eq!(Rev::from_raw("20140302T031110+0500".into()).as_datetime().unwrap(), expected);
+        assert!(Rev::from_raw("20140301".into()).as_datetime().is_err());
+
+        let mut card = Vcard::build("BEGIN:VCARD\nFN:Erika\nREV:20140301T221110Z\nEND:VCARD\n").unwrap();
+        let before = Utc::now();
+        card.touch_rev();
+        let rev = card.rev().unwrap();
+        assert!(rev.raw().ends_with('Z') && !rev.raw().contains('-'));
+        assert!(rev.as_datetime().unwrap() >= before - chrono::Duration::seconds(1));
+        assert_eq!(card.get_all("REV").len(), 1);
+    }
+
+    #[cfg(feature = "timeconversions")]
+    #[test]
+    fn test_vcard_partial_dates() {
+        use chrono::NaiveDate;
+        use super::{BDay, PartialDate};
+
+        let date = |raw: &str| BDay::from_raw(raw.into()).as_date();
+        let april_12 = NaiveDate::from_ymd_opt(1985, 4, 12).unwrap();
+
+        assert_eq!(date("19850412").unwrap(), PartialDate::Full(april_12));
+        assert_eq!(date("1985-04-12").unwrap(), PartialDate::Full(april_12));
+        assert_eq!(date("19850412T120000Z").unwrap(), PartialDate::Full(april_12));
+        assert_eq!(date("--0412").unwrap(), PartialDate::MonthDay { month: 4, day: 12 });
+        assert_eq!(date("--04-12").unwrap(), PartialDate::MonthDay { month: 4, day: 12 });
+        assert_eq!(date("--0229").unwrap(), PartialDate::MonthDay { month: 2, day: 29 });
+        assert_eq!(date("1985-04").unwrap(), PartialDate::YearMonth { year: 1985, month: 4 });
+        assert_eq!(date("1985").unwrap(), PartialDate::YearOnly(1985));
+
+        for invalid in &["", "circa 1800", "--1332", "19851332", "1985-13", "---12", "85"] {
+            assert!(date(invalid).is_err(), "{} should be invalid", invalid);
+        }
+
+        let today = NaiveDate::from_ymd_opt(2024, 4, 12).unwrap();
+        assert!(date("--0412").unwrap().matches(today));
+        assert!(date("19850412").unwrap().matches(today));
+        assert!(!date("--0413").unwrap().matches(today));
+        assert!(!date("1985-04").unwrap().matches(today));
+        assert!(!date("1985").unwrap().matches(today));
+
+        let wedding = super::Anniversary::from_raw("20100612".into());
+        assert_eq!(wedding.as_date().unwrap(), PartialDate::Full(NaiveDate::from_ymd_opt(2010, 6, 12).unwrap()));
+    }
+
+    #[test]
+    fn test_vcard_setters() {
+        use component::write_component;
+
+        let mut item = Vcard::build(
+            "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            FN:Erika Mustermann\n\
+            N:Mustermann;Erika;;;\n\
+            NOTE:unchanged\n\
+            END:VCARD\n").unwrap();
+
+        item.set_fullname("Mustermann, Erika");
+        item.set_name(parameters!(), "Mustermann-Gabler", "Erika", "", "Dr.", "");
+        item.add_email("erika@example.com", parameters!("TYPE" => "work"));
+        item.add_tel("+49 221 9999123", parameters!());
+        item.add_tel("+49 170 1234567", parameters!("TYPE" => "cell"));
+        assert_eq!(item.remove_property("note").len(), 1);
+        assert!(item.remove_property("NOTE").is_empty());
+
+        let written = write_component(&item);
+        assert!(written.contains("FN:Mustermann\\, Erika\r\n"));
+
+        let reparsed = Vcard::build(&written).unwrap();
+        assert_eq!(reparsed.get_only("FN").unwrap().value_as_string(), "Mustermann, Erika");
+        assert_eq!(reparsed.fullname().len(), 1);
+        assert_eq!(reparsed.name().unwrap().raw(), "Mustermann-Gabler;Erika;;Dr.;");
+        assert_eq!(reparsed.email()[0].params().get("TYPE").unwrap(), "work");
+        assert_eq!(reparsed.tel().len(), 2);
+        assert!(reparsed.get_only("NOTE").is_none());
+    }
+
+    #[test]
+    fn test_vcard_unescaped_value() {
+        let item = Vcard::build("BEGIN:VCARD\n\
+            FN:Erika Mustermann\n\
+            NOTE:Met at the conference in Koeln\\, 2024\n\
+            END:VCARD\n").unwrap();
+        let note = item.note().pop().unwrap();
+
+        assert_eq!(note.raw(), "Met at the conference in Koeln\\, 2024");
+        assert_eq!(note.value(), "Met at the conference in Koeln, 2024");
+        assert_eq!(item.fullname()[0].value(), "Erika Mustermann");
+    }
+
+    #[test]
+    fn test_vcard_categories() {
+        use super::Category;
+
+        let vcard = Vcard::builder()
+            .with_fullname("Erika Mustermann")
+            .with_categories(vec!["Food, Drink", "Friends"])
+            .build()
+            .unwrap();
+        assert_eq!(vcard.categories()[0].raw(), "Food\\, Drink,Friends");
+
+        let vcard = Vcard::build(&::component::write_component(&vcard)).unwrap();
+        assert_eq!(vcard.categories()[0].values(), vec!["Food, Drink", "Friends"]);
+        assert_eq!(Category::from_values(vec!["a,b".into()]).raw(), "a\\,b");
+    }
+
+    #[test]
+    fn test_vcard_write_versioned() {
+        use super::VcardVersion;
+
+        let item = Vcard::build(
+            "BEGIN:VCARD\n\
+            VERSION:3.0\n\
+            FN:Erika Mustermann\n\
+            TEL;TYPE=CELL,PREF:+49 221 9999123\n\
+            PHOTO;ENCODING=b;TYPE=JPEG:MIICajCCAdOgAwIBAgICBEUwDQYJKoZIhvcN\n\
+            ADR;TYPE=HOME:;;Heidestrasse 17;Koeln;;51147;Deutschland\n\
+            LABEL;TYPE=HOME:Heidestrasse 17\\n51147 Koeln\n\
+            X-CUSTOM;TYPE=WHATEVER:unchanged\n\
+            END:VCARD\n").unwrap();
+
+        assert_eq!(item.version_enum(), Some(VcardVersion::V3_0));
+
+        assert_eq!(item.write_versioned(VcardVersion::V4_0).unwrap(),
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            ADR;LABEL=Heidestrasse 17^n51147 Koeln;TYPE=home:;;Heidestrasse 17;Koeln;;5\r\n \
+            1147;Deutschland\r\n\
+            FN:Erika Mustermann\r\n\
+            PHOTO:data:image/jpeg;base64,MIICajCCAdOgAwIBAgICBEUwDQYJKoZIhvcN\r\n\
+            TEL;PREF=1;TYPE=cell;VALUE=uri:tel:+49-221-9999123\r\n\
+            X-CUSTOM;TYPE=WHATEVER:unchanged\r\n\
+            END:VCARD\r\n");
+
+        let v4 = Vcard::build(&item.write_versioned(VcardVersion::V4_0).unwrap()).unwrap();
+        assert_eq!(v4.write_versioned(VcardVersion::V3_0).unwrap(),
+            "BEGIN:VCARD\r\n\
+            VERSION:3.0\r\n\
+            ADR;TYPE=HOME:;;Heidestrasse 17;Koeln;;51147;Deutschland\r\n\
+            FN:Erika Mustermann\r\n\
+            LABEL;TYPE=HOME:Heidestrasse 17\\n51147 Koeln\r\n\
+            PHOTO;ENCODING=b;TYPE=JPEG:MIICajCCAdOgAwIBAgICBEUwDQYJKoZIhvcN\r\n\
+            TEL;TYPE=CELL,PREF:+49-221-9999123\r\n\
+            X-CUSTOM;TYPE=WHATEVER:unchanged\r\n\
+            END:VCARD\r\n");
+
+        let v21 = item.write_versioned(VcardVersion::V2_1).unwrap();
+        assert!(v21.contains("TEL;CELL;PREF:+49 221 9999123\r\n"));
+        assert!(v21.contains("PHOTO;ENCODING=BASE64;TYPE=JPEG:"));
+        let v30 = Vcard::build(&v21).unwrap().write_versioned(VcardVersion::V3_0).unwrap();
+        assert!(v30.contains("PHOTO;ENCODING=b;TYPE=JPEG:"));
+
+        // The in-memory card is untouched
+        assert_eq!(item.get_only("TEL").unwrap().raw_value, "+49 221 9999123");
+        assert_eq!(item.version().unwrap().raw(), "3.0");
+    }
+
+    #[test]
+    fn test_vcard_string_conversions() {
+        use std::convert::TryFrom;
+        use component::Component;
+
+        let card: Vcard = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Erika Mustermann\r\nEND:VCARD\r\n".parse().unwrap();
+        let reparsed: Vcard = card.to_string().parse().unwrap();
+        assert_eq!(*reparsed, *card);
+        assert!("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n".parse::<Vcard>().is_err());
+
+        let component = Component::from(card);
+        assert_eq!(component.name, "VCARD");
+        assert!(Vcard::try_from(component).is_ok());
+        assert_eq!(Vcard::try_from(Component::new("VEVENT")).unwrap_err().name, "VEVENT");
+    }
+
+    #[test]
+    fn test_not_a_vcard() {
+        let input = format!("BEGIN:VCALENDAR\nX-DATA:{}\nEND:VCALENDAR\n", "x".repeat(10_000));
+        match Vcard::build(&input) {
+            Err(VObjectError::NotAVCard { found, prefix }) => {
+                assert_eq!(found, "VCALENDAR");
+                assert!(input.starts_with(prefix.trim_end_matches("...")));
+                assert!(prefix.len() < 100);
+            },
+            x => panic!("unexpected result: {:?}", x),
+        }
+        assert_eq!(Vcard::build(&input).unwrap_err().to_string(), "Not a Vcard, found VCALENDAR");
+    }
+
+    #[test]
+    fn test_vcard_agent() {
+        let input = "BEGIN:VCARD\r\n\
+                     VERSION:2.1\r\n\
+                     FN:Erika Mustermann\r\n\
+                     AGENT:\r\n\
+                     BEGIN:VCARD\r\n\
+                     VERSION:2.1\r\n\
+                     FN:Max Mustermann\r\n\
+                     TEL:+49 221 1234567\r\n\
+                     END:VCARD\r\n\
+                     END:VCARD\r\n";
+
+        let card = Vcard::build(input).unwrap();
+        let agents = card.agents();
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].fullname()[0].raw(), "Max Mustermann");
+        assert_eq!(agents[0].tel()[0].raw(), "+49 221 1234567");
+
+        let written = card.to_string();
+        assert_eq!(Vcard::build(&written).unwrap().agents()[0].fullname()[0].raw(), "Max Mustermann");
+    }
+
+    #[test]
+    fn test_vcard_without_binary_blobs() {
+        let card = Vcard::build("BEGIN:VCARD\r\n\
+                                 VERSION:4.0\r\n\
+                                 FN:Erika Mustermann\r\n\
+                                 PHOTO:data:image/jpeg;base64,AAAA\r\n\
+                                 item1.LOGO:data:image/png;base64,BBBB\r\n\
+                                 KEY;MEDIATYPE=application/pgp-keys:data:;base64,CCCC\r\n\
+                                 SOUND:data:audio/ogg;base64,DDDD\r\n\
+                                 item1.X-ABLABEL:Work\r\n\
+                                 END:VCARD\r\n").unwrap();
+
+        let stripped = card.without_binary_blobs();
+        assert!(stripped.photo().is_empty() && stripped.logo().is_empty());
+        assert!(stripped.key().is_empty() && stripped.sound().is_empty());
+        assert_eq!(stripped.fullname()[0].raw(), "Erika Mustermann");
+        assert_eq!(stripped.group("item1").len(), 1);
+        assert_eq!(card.photo().len(), 1);
+    }
+
+    #[test]
+    fn test_vcard_tz() {
+        use super::Tz;
+
+        let tz = |raw: &str, params: ::param::Parameters| Tz::new(String::from(raw), params);
+
+        assert_eq!(tz("-0500", parameters!()).utc_offset(), Some(-5 * 3600));
+        assert_eq!(tz("+05:30", parameters!()).utc_offset(), Some(5 * 3600 + 1800));
+        assert_eq!(tz("+01", parameters!("VALUE" => "utc-offset")).utc_offset(), Some(3600));
+        assert_eq!(tz("-0500", parameters!()).as_timezone_name(), None);
+
+        let named = tz("America/New_York", parameters!("VALUE" => "text"));
+        assert_eq!(named.utc_offset(), None);
+        assert_eq!(named.as_timezone_name(), Some("America/New_York"));
+        assert_eq!(tz("Europe/Berlin", parameters!()).as_timezone_name(), Some("Europe/Berlin"));
+
+        #[cfg(feature = "timeconversions")]
+        {
+            use chrono::FixedOffset;
+            assert_eq!(tz("-05:00", parameters!()).as_offset().unwrap(), FixedOffset::west_opt(5 * 3600).unwrap());
+            assert!(named.as_offset().is_err());
+        }
+    }
+
+    #[test]
+    fn test_builder_grouped_properties() {
+        use property::Property;
+
+        let card = Vcard::builder()
+            .with_version("3.0")
+            .with_fullname("Erika Mustermann")
+            .with_grouped_property("item1", Property::new("URL", "https://example.com/erika"))
+            .with_grouped_property("item1", Property::new("X-ABLabel", "_$!<HomePage>!$_"))
+            .build()
+            .unwrap();
+
+        let written = card.to_string();
+        assert!(written.contains("item1.URL:https://example.com/erika\r\n"));
+        assert!(written.contains("item1.X-ABLABEL:_$!<HomePage>!$_\r\n"));
+
+        let reparsed = Vcard::build(&written).unwrap();
+        assert_eq!(reparsed.url()[0].raw(), "https://example.com/erika");
+        let labelled = reparsed.labelled_properties();
+        assert_eq!(labelled.len(), 1);
+        assert_eq!(labelled[0].0.name, "URL");
+        assert_eq!(labelled[0].1.unwrap().raw_value, "_$!<HomePage>!$_");
+        assert_eq!(reparsed.group("ITEM1").len(), 2);
+    }
+
+    #[test]
+    fn test_vcard_languages() {
+        let card = Vcard::build("BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN;LANGUAGE=de:Jürgen Müller\r\n\
+            FN;LANGUAGE=en-US:Jurgen Muller\r\n\
+            TITLE;LANGUAGE=de:Geschäftsführer\r\n\
+            TITLE:CEO\r\n\
+            END:VCARD\r\n").unwrap();
+
+        let fullname = card.fullname_for_lang("en").unwrap();
+        assert_eq!(fullname.value(), "Jurgen Muller");
+        assert_eq!(fullname.language(), Some("en-US"));
+        assert_eq!(card.fullname_for_lang("EN-us").unwrap().value(), "Jurgen Muller");
+        assert_eq!(card.fullname_for_lang("de").unwrap().value(), "Jürgen Müller");
+        assert!(card.fullname_for_lang("e").is_none());
+        assert!(card.fullname_for_lang("fr").is_none());
+        assert_eq!(card.fullname_for_lang("*").unwrap().value(), "Jürgen Müller");
+
+        assert_eq!(card.title_for_lang("fr").unwrap().value(), "CEO");
+        assert_eq!(card.title_for_lang("de-AT").unwrap().value(), "CEO");
+        assert_eq!(card.title_for_lang("de").unwrap().language(), Some("de"));
+        assert!(card.org_for_lang("de").is_none());
+    }
+
+    #[test]
+    fn test_vcard_structured_org_and_gender() {
+        let card = Vcard::build("BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            FN:Erika Mustermann\n\
+            ORG:ACME\\, Inc.;Research;Widgets\n\
+            GENDER:F;grrrl\n\
+            END:VCARD\n").unwrap();
+        let org = &card.org()[0];
+        assert_eq!(org.units(), vec!["ACME, Inc.", "Research", "Widgets"]);
+        assert_eq!(org.name(), "ACME, Inc.");
+        let gender = card.gender().unwrap();
+        assert_eq!(gender.sex(), Some("F"));
+        assert_eq!(gender.identity(), Some("grrrl".into()));
+
+        let card = Vcard::builder()
+            .with_fullname("Erika Mustermann")
+            .with_org(vec![String::from("ACME"), String::from("R;D")])
+            .with_gender(";complicated\\, really")
+            .build()
+            .unwrap();
+        assert_eq!(card.org()[0].raw(), "ACME;R\\;D");
+        assert_eq!(card.org()[0].units(), vec!["ACME", "R;D"]);
+        let gender = card.gender().unwrap();
+        assert_eq!(gender.sex(), None);
+        assert_eq!(gender.identity(), Some("complicated, really".into()));
+
+        assert_eq!(super::Gender::from_raw("M".into()).identity(), None);
+    }
+
+    #[test]
+    fn test_vcard_uri_schemes() {
+        use super::{ImppProtocol, IMPP, Key, Url};
+
+        let impp = IMPP::from_raw("xmpp:alice@jabber.org".into());
+        assert_eq!(impp.scheme(), Some("xmpp"));
+        assert_eq!(impp.target(), Some("alice@jabber.org"));
+        assert_eq!(impp.protocol(), Some(ImppProtocol::Xmpp));
+        assert_eq!(IMPP::from_raw("SIP:bob@example.com".into()).protocol(), Some(ImppProtocol::Sip));
+        assert_eq!(IMPP::from_raw("ircs://irc.libera.chat/rust".into()).protocol(), Some(ImppProtocol::Irc));
+        assert_eq!(IMPP::from_raw("skype:echo123".into()).protocol(), Some(ImppProtocol::Skype));
+        assert_eq!(IMPP::from_raw("aim:goim?screenname=bob".into()).protocol(),
+                   Some(ImppProtocol::Other("aim".into())));
+        assert_eq!(IMPP::from_raw("alice@jabber.org".into()).protocol(), None);
+        assert_eq!(IMPP::from_raw("1x:foo".into()).scheme(), None);
+
+        let url = Url::from_raw("https://example.com/~erika".into());
+        assert_eq!(url.scheme(), Some("https"));
+        assert_eq!(url.target(), Some("//example.com/~erika"));
+        assert_eq!(Url::from_raw("example.com".into()).target(), None);
+
+        let key = Key::from_raw("data:application/pgp-keys;base64,Zm9vYmFy".into());
+        assert_eq!(key.scheme(), Some("data"));
+        assert_eq!(key.as_data_uri_bytes().unwrap(), b"foobar");
+        let key = Key::from_raw("https://example.com/key.asc".into());
+        assert_eq!(key.target(), Some("//example.com/key.asc"));
+        assert!(key.as_data_uri_bytes().is_err());
+        assert!(Key::from_raw("data:application/pgp-keys;base64,!!".into()).as_data_uri_bytes().is_err());
+        assert!(Key::from_raw("data:text/plain,foobar".into()).as_data_uri_bytes().is_err());
+        assert!(Key::from_raw("data:aéééé,AAAA".into()).as_data_uri_bytes().is_err());
+        assert_eq!(Key::from_raw("data:text/é;base64,Zm9v".into()).as_data_uri_bytes().unwrap(), b"foo");
+    }
+
+    #[test]
+    fn test_vcard_pids() {
+        let card = Vcard::build("BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN:Erika Mustermann\r\n\
+            EMAIL;PID=1.1:erika@example.com\r\n\
+            EMAIL;PID=2.1,2.3:erika@example.org\r\n\
+            EMAIL;PID=x.1,4:mustermann@example.net\r\n\
+            CLIENTPIDMAP:1;urn:uuid:3df403f4-5924-4bb7-b077-3c711d9eb34b\r\n\
+            CLIENTPIDMAP:2;urn:uuid:d89c9c7a-2e1b-4832-82de-7e992d95faa5\r\n\
+            END:VCARD\r\n").unwrap();
+
+        let maps = card.clientpidmap();
+        assert_eq!(maps.len(), 2);
+        assert_eq!(maps[0].pid(), Some(1));
+        assert_eq!(maps[1].uri(), Some("urn:uuid:d89c9c7a-2e1b-4832-82de-7e992d95faa5"));
+
+        let emails = card.get_all("EMAIL");
+        assert_eq!(emails[0].pids(), vec![(1, Some(1))]);
+        assert_eq!(emails[1].pids(), vec![(2, Some(1)), (2, Some(3))]);
+        assert_eq!(emails[2].pids(), vec![(4, None)]);
+        assert_eq!(card.max_pid_source(), Some(3));
+
+        let map = super::ClientPidMap::from_raw("foo".into());
+        assert_eq!((map.pid(), map.uri()), (None, None));
+        assert_eq!(Vcard::builder().with_fullname("Erika").build().unwrap().max_pid_source(), None);
+    }
+
+    #[test]
+    fn test_vcard_photo_data() {
+        use super::{ImageData, ImageRef};
+
+        let card = Vcard::build("BEGIN:VCARD\r\n\
+            VERSION:2.1\r\n\
+            FN:Erika Mustermann\r\n\
+            PHOTO;JPEG;ENCODING=BASE64:\r\n\
+            \x20/9j/4AAQ\r\n\
+            \x20SkZJRg==\r\n\
+            PHOTO;TYPE=image/png;ENCODING=b:iVBORw0KGgo=\r\n\
+            PHOTO;VALUE=uri:https://example.com/erika.jpg\r\n\
+            LOGO;ENCODING=b:!!!\r\n\
+            LOGO:data:image/png;base64,%%%\r\n\
+            LOGO:data:aéééé,AAAA\r\n\
+            END:VCARD\r\n").unwrap();
+
+        let photos = card.photo();
+        let jpeg = b"\xff\xd8\xff\xe0\x00\x10JFIF".to_vec();
+        assert_eq!(photos[0].data().unwrap(), ImageRef::Data(ImageData { mime: Some("image/jpeg".into()), bytes: jpeg }));
+        match photos[1].data().unwrap() {
+            ImageRef::Data(data) => assert_eq!(data.mime.as_deref(), Some("image/png")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(photos[2].data().unwrap(), ImageRef::Url("https://example.com/erika.jpg".into()));
+        assert_eq!(card.logo().len(), 3);
+        assert!(card.logo().iter().all(|logo| logo.data().is_err()));
+    }
+
+    #[test]
+    fn test_vcard_nicknames() {
+        use property::Property;
+
+        let card = Vcard::build("BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN:Erika Mustermann\r\n\
+            NICKNAME:Eri\\, the Great,Mausi\r\n\
+            NICKNAME:\r\n\
+            NICKNAME:Eri,\r\n\
+            END:VCARD\r\n").unwrap();
+        let nicknames = card.nickname();
+        assert_eq!(nicknames[0].values(), vec!["Eri, the Great", "Mausi"]);
+        assert!(nicknames[1].values().is_empty());
+        assert_eq!(nicknames[2].values(), vec!["Eri", ""]);
+        assert_eq!(card.get_all("NICKNAME")[0].values(), nicknames[0].values());
+
+        let prop = Property::from_values("NICKNAME", &["Eri, the Great", "Mausi"]);
+        assert_eq!(prop.raw_value, "Eri\\, the Great,Mausi");
+        assert_eq!(prop.values(), nicknames[0].values());
+    }
+
+    #[test]
+    fn test_sort_key() {
+        let card = |props: &str| Vcard::build(&format!("BEGIN:VCARD\nVERSION:4.0\n{}END:VCARD\n", props)).unwrap();
+
+        let sorted = card("N;SORT-AS=\"Mustermann,Erika\":von Mustermann;Erika;;;\nFN:Erika von Mustermann\n");
+        assert_eq!(sorted.name().unwrap().sort_as().unwrap(), vec!["Mustermann", "Erika"]);
+        assert_eq!(sorted.sort_key(), "mustermann erika");
+
+        let named = card("N:Müller;Jürgen;;;\nFN:Jürgen Müller\n");
+        assert_eq!(named.name().unwrap().sort_as(), None);
+        assert_eq!(named.sort_key(), "müller jürgen");
+
+        let org = card("FN:The ABC Inc.\nORG;SORT-AS=ABC:The ABC Inc.\n");
+        assert_eq!(org.org()[0].sort_as().unwrap(), vec!["ABC"]);
+        assert_eq!(org.sort_key(), "abc");
+
+        let only_fn = card("N:;;;;\nFN:  Zoë\n");
+        assert_eq!(only_fn.sort_key(), "zoë");
+
+        let mut cards = [only_fn, org, named, sorted];
+        cards.sort_by(Vcard::cmp_by_name);
+        let keys = cards.iter().map(Vcard::sort_key).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["abc", "mustermann erika", "müller jürgen", "zoë"]);
+    }
+
+    #[test]
+    fn test_related() {
+        use super::{RelatedTarget, RelationType};
+
+        let card = Vcard::build("BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            FN:Erika Mustermann\n\
+            RELATED;TYPE=spouse:urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6\n\
+            RELATED;TYPE=friend,co-worker:mailto:juergen@example.com\n\
+            RELATED;VALUE=text;TYPE=emergency:Jürgen Müller\\, Nachbar\n\
+            RELATED;TYPE=muse:https://example.com/muse\n\
+            END:VCARD\n").unwrap();
+
+        let related = card.related();
+        assert_eq!(related[0].relation_types(), vec![RelationType::Spouse]);
+        assert_eq!(related[0].target(), RelatedTarget::Uid("f81d4fae-7dec-11d0-a765-00a0c91e6bf6".into()));
+        assert_eq!(related[1].relation_types(), vec![RelationType::Friend, RelationType::Other("co-worker".into())]);
+        assert_eq!(related[1].target(), RelatedTarget::Uri("mailto:juergen@example.com".into()));
+        assert_eq!(related[2].relation_types(), vec![RelationType::Emergency]);
+        assert_eq!(related[2].target(), RelatedTarget::Text("Jürgen Müller, Nachbar".into()));
+        assert_eq!(related[3].relation_types(), vec![RelationType::Other("muse".into())]);
+        assert_eq!(RelationType::from_type_str("co-worker").to_string(), "co-worker");
+        assert_eq!(card.related_uids(), vec!["f81d4fae-7dec-11d0-a765-00a0c91e6bf6"]);
+
+        let built = Vcard::builder()
+            .with_version("4.0")
+            .with_fullname("Erika Mustermann")
+            .with_related_uid("f81d4fae-7dec-11d0-a765-00a0c91e6bf6", RelationType::Child)
+            .with_related_uid("urn:uuid:0e7602cc-443e-4b82-b4b1-90f62f99a199", RelationType::Parent)
+            .with_related_text("Jürgen Müller, Nachbar", RelationType::Emergency)
+            .build()
+            .unwrap();
+        let related = built.related();
+        assert_eq!(related[0].raw(), "urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6");
+        assert_eq!(related[0].relation_types(), vec![RelationType::Child]);
+        assert_eq!(related[2].raw(), "Jürgen Müller\\, Nachbar");
+        assert_eq!(related[2].target(), RelatedTarget::Text("Jürgen Müller, Nachbar".into()));
+        assert_eq!(built.related_uids(), vec![
+            "f81d4fae-7dec-11d0-a765-00a0c91e6bf6",
+            "0e7602cc-443e-4b82-b4b1-90f62f99a199",
+        ]);
+    }
+
+    #[test]
+    fn test_vcard_group() {
+        use super::Kind;
+
+        let icloud = "BEGIN:VCARD\r\n\
+            VERSION:3.0\r\n\
+            PRODID:-//Apple Inc.//iOS 17.2//EN\r\n\
+            N:Book Club\r\n\
+            FN:Book Club\r\n\
+            UID:5B2A4E8C-1D3F-4C7A-9E21-6F0B8D3C2A11\r\n\
+            X-ADDRESSBOOKSERVER-KIND:group\r\n\
+            X-ADDRESSBOOKSERVER-MEMBER:urn:uuid:0F1E2D3C-4B5A-6978-8796-A5B4C3D2E1F0\r\n\
+            X-ADDRESSBOOKSERVER-MEMBER:urn:uuid:9A8B7C6D-5E4F-3A2B-1C0D-E9F8A7B6C5D4\r\n\
+            REV:2024-01-15T10:00:00Z\r\n\
+            END:VCARD\r\n";
+
+        let card = Vcard::build(icloud).unwrap();
+        assert_eq!(card.kind(), Some(Kind::Group));
+        assert_eq!(card.members(), vec!["0F1E2D3C-4B5A-6978-8796-A5B4C3D2E1F0",
+                                        "9A8B7C6D-5E4F-3A2B-1C0D-E9F8A7B6C5D4"]);
+        let reparsed = Vcard::build(&card.to_string()).unwrap();
+        assert_eq!(reparsed.kind(), Some(Kind::Group));
+        assert_eq!(reparsed.members(), card.members());
+
+        let built = Vcard::builder()
+            .with_version("4.0")
+            .with_fullname("Office")
+            .with_kind(Kind::Location)
+            .build()
+            .unwrap();
+        assert_eq!(built.kind(), Some(Kind::Location));
+        assert_eq!(Kind::from_kind_str("ORG"), Kind::Org);
+        assert_eq!(Kind::from_kind_str("x-team").to_string(), "x-team");
+        assert_eq!(Vcard::default().kind(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_vcard_new_group() {
+        use super::Kind;
+
+        let group = Vcard::new_group("Book Club, Berlin",
+                                     &["0F1E2D3C", "mailto:erika@example.com", "https://example.com/?ids=1,2"]);
+        assert_eq!(group.kind(), Some(Kind::Group));
+        assert_eq!(group.fullname()[0].value(), "Book Club, Berlin");
+        assert!(group.uid().unwrap().raw().starts_with("urn:uuid:"));
+        assert_eq!(group.member()[1].raw(), "mailto:erika@example.com");
+        assert_eq!(group.member()[2].raw(), "https://example.com/?ids=1,2");
+        assert_eq!(group.members(), vec!["0F1E2D3C"]);
+        assert!(group.validate().is_empty());
+
+        let reparsed = Vcard::build(&group.to_string()).unwrap();
+        assert_eq!(reparsed.members(), group.members());
+        assert_eq!(reparsed.uid().unwrap().raw(), group.uid().unwrap().raw());
+    }
+
+    #[test]
+    fn test_vcard_merge() {
+        use super::MergeStrategy;
+
+        let older = Vcard::build(
+            "BEGIN:VCARD\r\n\
+            VERSION:3.0\r\n\
+            FN:Erika Mustermann\r\n\
+            N:Mustermann;Erika;;;\r\n\
+            REV:20230101T000000Z\r\n\
+            EMAIL:Erika@Example.com\r\n\
+            TEL:+49 170 1234567\r\n\
+            item1.TEL:+49 30 555\r\n\
+            item1.X-ABLabel:Werkstatt\r\n\
+            X-SOCIALPROFILE:https://twitter.com/erika\r\n\
+            END:VCARD\r\n").unwrap();
+        let newer = Vcard::build(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN:Erika M.\r\n\
+            REV:2024-01-01T00:00:00Z\r\n\
+            EMAIL:erika@example.com\r\n\
+            EMAIL:erika@work.example\r\n\
+            TEL;VALUE=uri:tel:+49-170-1234567\r\n\
+            item1.TEL:+49 40 777\r\n\
+            item1.X-ABLabel:Büro\r\n\
+            X-ABDATE:2000-01-01\r\n\
+            END:VCARD\r\n").unwrap();
+
+        let merged = older.merge(&newer, MergeStrategy::PreferNewerRev);
+        assert!(merged.validate().is_empty());
+        assert_eq!(merged.version().unwrap().raw(), "4.0");
+        assert_eq!(merged.fullname().iter().map(|f| f.raw()).collect::<Vec<_>>(), vec!["Erika M."]);
+        assert_eq!(merged.name().unwrap().raw(), "Mustermann;Erika;;;");
+        assert_eq!(merged.email().iter().map(|e| e.raw()).collect::<Vec<_>>(),
+                   vec!["erika@example.com", "erika@work.example"]);
+        assert_eq!(merged.tel().len(), 3);
+        assert_eq!(merged.get_all("X-SOCIALPROFILE").len(), 1);
+        assert_eq!(merged.get_all("X-ABDATE").len(), 1);
+
+        let labelled = merged.labelled_properties()
+            .into_iter()
+            .map(|(p, label)| (&p.raw_value[..], label.map(|l| &l.raw_value[..])))
+            .collect::<Vec<_>>();
+        assert_eq!(labelled, vec![("+49 40 777", Some("Büro")), ("+49 30 555", Some("Werkstatt"))]);
+
+        let merged = older.merge(&newer, MergeStrategy::PreferSelf);
+        assert_eq!(merged.version().unwrap().raw(), "3.0");
+        assert_eq!(merged.email()[0].raw(), "Erika@Example.com");
+        assert_eq!(merged.email().len(), 2);
+        assert_eq!(newer.merge(&older, MergeStrategy::PreferOther).rev().unwrap().raw(), "20230101T000000Z");
+    }
+
+    #[cfg(feature = "timeconversions")]
+    #[test]
+    fn test_vcard_merge_rev_offset() {
+        use super::MergeStrategy;
+
+        // 05:00Z, an hour before the other card
+        let older = Vcard::build("BEGIN:VCARD\nVERSION:4.0\nFN:Older\nREV:20240101T100000+0500\nEND:VCARD\n").unwrap();
+        let newer = Vcard::build("BEGIN:VCARD\nVERSION:4.0\nFN:Newer\nREV:20240101T060000Z\nEND:VCARD\n").unwrap();
+
+        assert_eq!(older.merge(&newer, MergeStrategy::PreferNewerRev).fullname()[0].raw(), "Newer");
+        assert_eq!(newer.merge(&older, MergeStrategy::PreferNewerRev).fullname()[0].raw(), "Newer");
+    }
+
+    #[test]
+    fn test_vcard_groups() {
+        use component::write_component;
+
+        // As exported by Apple Contacts
+        let item = Vcard::build(
+            "BEGIN:VCARD\r\n\
+            VERSION:3.0\r\n\
+            PRODID:-//Apple Inc.//macOS 14.0//EN\r\n\
+            N:Mustermann;Erika;;;\r\n\
+            FN:Erika Mustermann\r\n\
+            item1.TEL;type=pref:+49 170 1234567\r\n\
+            item1.X-ABLabel:Werkstatt\r\n\
+            item2.EMAIL;type=INTERNET:erika@example.com\r\n\
+            item2.X-ABLabel:_$!<Other>!$_\r\n\
+            item3.URL:https://example.com\r\n\
+            X-ABDATE;type=pref:2000-01-01\r\n\
+            X-SOCIALPROFILE;type=twitter:https://twitter.com/erika\r\n\
+            END:VCARD\r\n").unwrap();
+
+        assert_eq!(item.group("item1").iter().map(|p| &p.name[..]).collect::<Vec<_>>(),
+                   vec!["TEL", "X-ABLABEL"]);
+        assert_eq!(item.group("ITEM2").len(), 2);
+        assert!(item.group("item4").is_empty());
+        assert_eq!(item.x_properties().map(|p| &p.name[..]).collect::<Vec<_>>(),
+                   vec!["X-ABDATE", "X-ABLABEL", "X-ABLABEL", "X-SOCIALPROFILE"]);
+
+        let labelled = item.labelled_properties()
+            .into_iter()
+            .map(|(p, label)| (&p.name[..], label.map(|l| &l.raw_value[..])))
+            .collect::<Vec<_>>();
+        assert_eq!(labelled, vec![
+            ("EMAIL", Some("_$!<Other>!$_")),
+            ("TEL", Some("Werkstatt")),
+            ("URL", None),
+        ]);
+
+        let reparsed = Vcard::build(&write_component(&item)).unwrap();
+        assert_eq!(*reparsed, *item);
+        assert_eq!(reparsed.group("item1"), item.group("item1"));
+    }
+
+    #[test]
+    fn test_vcard_lowercase() {
+        // As exported by an old Android contacts app
+        let item = Vcard::build(
+            "begin:vcard\n\
+            version:2.1\n\
+            n:Mustermann;Erika;;;\n\
+            fn:Erika Mustermann\n\
+            tel;cell:+49 170 1234567\n\
+            email;type=home:erika@example.com\n\
+            end:vcard\n").unwrap();
+
+        assert_eq!(item.name, "VCARD");
+        assert_eq!(item.version().unwrap().raw(), "2.1");
+        assert_eq!(item.name().unwrap().given_name().unwrap(), "Erika");
+        assert_eq!(item.fullname()[0].raw(), "Erika Mustermann");
+        assert!(item.tel()[0].params().contains_key("CELL"));
+        assert_eq!(item.email()[0].params().get("TYPE").unwrap(), "home");
+        assert!(item.validate().is_empty());
+    }
+
+}
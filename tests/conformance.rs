@@ -0,0 +1,178 @@
+//! Round-trip every file in `tests/fixtures/`: parse, write, parse again and compare.
+//!
+//! Fixtures which are known not to survive this are listed with a reason in
+//! `tests/fixtures/EXPECTED_FAILURES`. Such fixtures must keep failing, so the list is updated
+//! when a parser change fixes them.
+
+extern crate vobject;
+
+use std::fs;
+use std::path::Path;
+
+use vobject::{normalize_ical, normalize_vcard, parse_components, write_component, write_component_with};
+#[cfg(feature = "std")] use vobject::write_component_to;
+use vobject::{EquivalenceOptions, NormalizeOptions, WriteOptions};
+#[cfg(feature = "std")] use vobject::component::{FoldMode, LineEnding};
+
+struct Fixture {
+    name: String,
+    contents: String,
+    /// Why the fixture is expected to fail, if it is.
+    expected_failure: Option<String>,
+}
+
+fn load_fixtures() -> Vec<Fixture> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+
+    let expected_failures = fs::read_to_string(dir.join("EXPECTED_FAILURES")).unwrap_or_default();
+    let expected_failure = |name: &str| {
+        expected_failures
+            .lines()
+            .filter(|l| !l.trim_start().starts_with('#'))
+            .filter_map(|l| {
+                let mut parts = l.splitn(2, char::is_whitespace);
+                Some((parts.next()?, parts.next().unwrap_or("").trim()))
+            })
+            .find(|&(n, _)| n == name)
+            .map(|(_, reason)| reason.to_owned())
+    };
+
+    let mut rv = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ics" || ext == "vcf"))
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            Fixture {
+                contents: fs::read_to_string(&path).unwrap(),
+                expected_failure: expected_failure(&name),
+                name,
+            }
+        })
+        .collect::<Vec<_>>();
+    rv.sort_by(|a, b| a.name.cmp(&b.name));
+    rv
+}
+
+fn roundtrip(input: &str) -> Result<(), String> {
+    let components = parse_components(input).map_err(|e| format!("parsing failed: {}", e))?;
+    if components.is_empty() {
+        return Err("no components found".to_owned());
+    }
+
+    let written = components.iter().map(write_component).collect::<String>();
+    let reparsed = parse_components(&written)
+        .map_err(|e| format!("parsing the written data failed: {}\n{}", e, written))?;
+    if reparsed.len() != components.len() {
+        return Err(format!("{} components written, {} read back", components.len(), reparsed.len()));
+    }
+
+    for (a, b) in components.iter().zip(&reparsed) {
+        if !a.equivalent(b, &EquivalenceOptions::default()) {
+            return Err(format!("changed by the roundtrip: {:?}", a.diff(b)));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_fixtures() {
+    let fixtures = load_fixtures();
+    assert!(!fixtures.is_empty());
+
+    let mut problems = vec![];
+    for fixture in &fixtures {
+        match (roundtrip(&fixture.contents), &fixture.expected_failure) {
+            (Ok(()), None) | (Err(_), Some(_)) => (),
+            (Err(e), None) => problems.push(format!("{}: {}", fixture.name, e)),
+            (Ok(()), Some(reason)) => {
+                problems.push(format!("{}: expected to fail ({}), but passed", fixture.name, reason))
+            },
+        }
+    }
+    assert!(problems.is_empty(), "{}", problems.join("\n"));
+}
+
+fn normalize(fixture: &Fixture, input: &str) -> String {
+    let rv = if fixture.name.ends_with(".ics") { normalize_ical(input) } else { normalize_vcard(input) };
+    rv.unwrap_or_else(|e| panic!("{}: normalizing failed: {}", fixture.name, e))
+}
+
+/// Variants of the input which differ only in ways that normalization removes.
+fn variants(input: &str) -> Vec<String> {
+    let mut components = parse_components(input).unwrap();
+    let mut rv = vec![input.replace("\r\n", "\n")];
+    for fold_width in &[None, Some(40)] {
+        let opts = WriteOptions { fold_width: *fold_width, ..WriteOptions::default() };
+        rv.push(components.iter().map(|c| write_component_with(c, &opts)).collect());
+    }
+    components.reverse();
+    for c in &mut components {
+        c.subcomponents.reverse();
+    }
+    rv.push(components.iter().map(write_component).collect());
+    rv
+}
+
+#[test]
+fn test_normalize_fixtures() {
+    let ignore_order = EquivalenceOptions { ignore_order: true, ..EquivalenceOptions::default() };
+
+    // Fixtures which fail the roundtrip may not even parse after normalizing
+    for fixture in load_fixtures().iter().filter(|f| f.expected_failure.is_none()) {
+        let normalized = normalize(fixture, &fixture.contents);
+        assert_eq!(normalize(fixture, &normalized), normalized, "{}: not idempotent", fixture.name);
+
+        for variant in variants(&fixture.contents) {
+            assert_eq!(normalize(fixture, &variant), normalized, "{}: variant differs", fixture.name);
+        }
+
+        let before = parse_components(&fixture.contents).unwrap();
+        let after = parse_components(&normalized).unwrap();
+        assert_eq!(before.len(), after.len());
+        for (a, b) in before.iter().zip(&after) {
+            assert!(a.equivalent(b, &ignore_order), "{}: changed by normalizing", fixture.name);
+        }
+    }
+}
+
+#[test]
+fn test_normalize_options() {
+    let input = "BEGIN:VCARD\nUID:b\nFN:B\nEND:VCARD\nBEGIN:VCARD\nUID:a\nFN:A\nEND:VCARD\n";
+    assert!(normalize_vcard(input).unwrap().starts_with("BEGIN:VCARD\r\nFN:A\r\n"));
+
+    let opts = NormalizeOptions { sort_components: false, ..NormalizeOptions::default() };
+    assert!(vobject::normalize_vcard_with(input, &opts).unwrap().starts_with("BEGIN:VCARD\r\nFN:B\r\n"));
+
+    assert!(normalize_ical(input).is_err());
+    assert!(normalize_vcard("BEGIN:VCALENDAR\nEND:VCALENDAR\n").is_err());
+}
+
+/// Compare the streamed output with `tests/fixtures/written/`, which holds what
+/// `write_component_with` produced before it was built on `write_component_to`.
+#[cfg(feature = "std")]
+#[test]
+fn test_write_to_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("written");
+    let options = [
+        ("crlf", WriteOptions::default()),
+        ("lf", WriteOptions { line_ending: LineEnding::Lf, fold_width: None, ..WriteOptions::default() }),
+        ("chars20", WriteOptions { fold_width: Some(20), fold_mode: FoldMode::Chars, ..WriteOptions::default() }),
+    ];
+
+    for fixture in load_fixtures() {
+        let components = match parse_components(&fixture.contents) {
+            Ok(components) => components,
+            Err(_) => continue,
+        };
+
+        for &(suffix, ref opts) in &options {
+            let mut streamed = Vec::new();
+            for c in &components {
+                write_component_to(c, &mut streamed, opts).unwrap();
+            }
+            let expected = fs::read_to_string(dir.join(format!("{}.{}", fixture.name, suffix))).unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), expected, "{}: {} output differs", fixture.name, suffix);
+        }
+    }
+}
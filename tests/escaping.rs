@@ -0,0 +1,54 @@
+#[macro_use]
+extern crate proptest;
+extern crate vobject;
+
+use vobject::{escape_chars, unescape_chars, parse_component, write_component, Component, Property};
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape_chars("a;b,c\\d\ne"), "a\\;b\\,c\\\\d\\ne");
+    assert_eq!(escape_chars("\\N"), "\\\\N");
+    assert_eq!(escape_chars("\\n"), "\\\\n");
+    assert_eq!(escape_chars("a\r\nb"), "a\\nb");
+    assert_eq!(escape_chars("a\rb\r"), "a\rb\r");
+    assert_eq!(escape_chars(":\"^'"), ":\"^'");
+}
+
+#[test]
+fn test_unescape() {
+    assert_eq!(unescape_chars("a\\;b\\,c\\\\d\\ne"), "a;b,c\\d\ne");
+    assert_eq!(unescape_chars("a\\Nb"), "a\nb");
+    assert_eq!(unescape_chars("\\\\N"), "\\N");
+    assert_eq!(unescape_chars("\\\\n"), "\\n");
+    // Unknown escapes and a trailing backslash are kept
+    assert_eq!(unescape_chars("C:\\Users\\t"), "C:\\Users\\t");
+    assert_eq!(unescape_chars("a\\"), "a\\");
+}
+
+// `escape_chars` turns `\r\n` into `\n`, so only other strings are restored exactly
+proptest! {
+    #[test]
+    fn test_escape_roundtrip(s in ".*") {
+        prop_assume!(!s.contains("\r\n"));
+        prop_assert_eq!(unescape_chars(&escape_chars(&s)), s);
+    }
+
+    #[test]
+    fn test_escape_roundtrip_special(s in "[\\\\nN;,\\n\\r a]*") {
+        prop_assume!(!s.contains("\r\n"));
+        prop_assert_eq!(unescape_chars(&escape_chars(&s)), s);
+    }
+
+    #[test]
+    fn test_escape_crlf(s in "[\\\\nN;,\\n\\r a]*") {
+        prop_assert_eq!(unescape_chars(&escape_chars(&s)), s.replace("\r\n", "\n"));
+    }
+
+    #[test]
+    fn test_property_value_roundtrip(s in "[^\\r\\x00-\\x08\\x0b-\\x1f\\x7f]*") {
+        let mut c = Component::new("VEVENT");
+        c.push(Property::new("SUMMARY", &s));
+        let parsed = parse_component(&write_component(&c)).unwrap();
+        prop_assert_eq!(parsed.get_only("SUMMARY").unwrap().value_as_string(), s);
+    }
+}
@@ -1,4 +1,5 @@
-extern crate vobject;
+#[macro_use] extern crate vobject;
+extern crate rayon;
 use vobject::parse_component;
 use std::borrow::ToOwned;
 
@@ -33,6 +34,31 @@ fn test_vcard_basic() {
     assert!(tel_values.next().is_none());
 }
 
+#[test]
+fn test_vcard_bare_params_roundtrip() {
+    let input = "BEGIN:VCARD\r\n\
+        VERSION:2.1\r\n\
+        N:Mustermann;Erika\r\n\
+        FN:Erika Mustermann\r\n\
+        TEL;WORK;VOICE:(0221) 9999123\r\n\
+        TEL;TYPE=HOME,VOICE:(0221) 1234567\r\n\
+        EMAIL;PREF;INTERNET:erika@mustermann.de\r\n\
+        END:VCARD\r\n";
+    let item = parse_component(input).unwrap();
+
+    let tels = item.get_all("TEL");
+    assert_eq!(tels[0].types(), vec!["VOICE", "WORK"]);
+    assert_eq!(tels[1].types(), vec!["HOME", "VOICE"]);
+    assert_eq!(parse_component("BEGIN:VCARD\nTEL;TYPE=VOICE,WORK:1\nEND:VCARD").unwrap()
+                   .get_only("TEL").unwrap().types(),
+               tels[0].types());
+
+    let written = vobject::write_component(&item);
+    assert!(written.contains("TEL;VOICE;WORK:(0221) 9999123\r\n"));
+    assert!(!written.contains("=:") && !written.contains("=;"));
+    assert_eq!(parse_component(&written).unwrap(), item);
+}
+
 #[test]
 fn test_line_cont() {
     let item = parse_component(
@@ -135,3 +161,387 @@ fn test_property_groups() {
     assert_eq!(item.get_only("EMAIL").unwrap().prop_group, Some("foo".to_owned()));
 
 }
+
+#[test]
+fn test_icalendar_validate() {
+    use vobject::icalendar::{ICalendar, ProblemKind, Severity, ValidationProblem};
+
+    let cal = ICalendar::build(
+            "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            BEGIN:VEVENT\n\
+            UID:first@example.com\n\
+            DTSTAMP:20060812T125900Z\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            DTSTAMP:20060812T125900Z\n\
+            DTSTART:20060910T220000Z\n\
+            DTEND:20060919T215900Z\n\
+            DURATION:PT1H\n\
+            BEGIN:VALARM\n\
+            ACTION:DISPLAY\n\
+            END:VALARM\n\
+            END:VEVENT\n\
+            BEGIN:VALARM\n\
+            ACTION:AUDIO\n\
+            TRIGGER:-PT15M\n\
+            END:VALARM\n\
+            END:VCALENDAR\n").unwrap();
+
+    let problems = cal.validate();
+    let expected = vec![
+        ValidationProblem { path: s!("VCALENDAR"), property: Some(s!("PRODID")), kind: ProblemKind::MissingProperty },
+        ValidationProblem { path: s!("VCALENDAR > VEVENT[1]"), property: Some(s!("UID")), kind: ProblemKind::MissingProperty },
+        ValidationProblem { path: s!("VCALENDAR > VEVENT[1]"), property: Some(s!("DURATION")), kind: ProblemKind::ConflictingProperties },
+        ValidationProblem { path: s!("VCALENDAR > VEVENT[1] > VALARM[0]"), property: Some(s!("TRIGGER")), kind: ProblemKind::MissingProperty },
+        ValidationProblem { path: s!("VCALENDAR > VALARM[0]"), property: None, kind: ProblemKind::UnexpectedComponent },
+    ];
+    assert_eq!(problems, expected);
+
+    assert_eq!(problems.iter().filter(|p| p.severity() == Severity::Warning).count(), 1);
+    assert_eq!(problems[1].to_string(), "VCALENDAR > VEVENT[1]: missing required property UID");
+}
+
+create_data_type!(XMoz, "X-MOZ-LASTACK");
+
+#[test]
+fn test_typed_property() {
+    use vobject::icalendar::{ICalendar, Summary};
+
+    let cal = ICalendar::build(
+            "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            BEGIN:VEVENT\n\
+            UID:first@example.com\n\
+            SUMMARY:Lunch\n\
+            X-MOZ-LASTACK;X-FOO=bar:20240101T120000Z\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+    let event = &cal.subcomponents[0];
+
+    let ack: XMoz = event.get_typed().unwrap();
+    assert_eq!(ack.raw(), "20240101T120000Z");
+    assert_eq!(ack.params().get("X-FOO"), Some("bar"));
+    assert_eq!(event.get_typed_all::<XMoz>(), vec![ack]);
+
+    assert_eq!(event.get_typed::<Summary>().unwrap().raw(), "Lunch");
+    assert!(cal.get_typed::<XMoz>().is_none());
+}
+
+#[test]
+fn test_strict_parsing() {
+    use vobject::{parse_component_with, ParserOptions};
+
+    let sloppy = "BEGIN:VCARD\n\
+                  VERSION:2.1\n\n\
+                  TEL;HOME:+49 221 9999123\n\
+                  NOTE:ring the bell \u{7}\n\
+                  END:VCARD";
+    assert!(parse_component(sloppy).is_ok());
+    assert!(parse_component_with(sloppy, &ParserOptions::default()).is_ok());
+    assert!(parse_component_with(sloppy, &ParserOptions::strict()).is_err());
+
+    let clean = "BEGIN:VCARD\r\n\
+                 VERSION:4.0\r\n\
+                 TEL;TYPE=home:+49 221 9999123\r\n\
+                 NOTE:a folded\r\n  line\twith tab\r\n\
+                 END:VCARD\r\n";
+    let strict = parse_component_with(clean, &ParserOptions::strict()).unwrap();
+    assert_eq!(strict, parse_component(clean).unwrap());
+    assert_eq!(strict.get_only("NOTE").unwrap().raw_value, "a folded line\twith tab");
+
+    // Each check on its own
+    let check = |input: &str, opts: ParserOptions| {
+        assert!(parse_component(input).is_ok(), "lax parsing failed: {:?}", input);
+        assert!(parse_component_with(input, &opts).is_err(), "strict parsing succeeded: {:?}", input);
+    };
+    let opts = ParserOptions::default();
+
+    check("BEGIN:VCARD\r\nFN:x\nEND:VCARD\r\n", ParserOptions { require_crlf: true, ..opts });
+    check("BEGIN:VCARD\r\nFN:x\r\n\r\nEND:VCARD\r\n", ParserOptions { strict_line_endings: true, ..opts });
+    check("BEGIN:VCARD\r\nFN:x\r\nEND:VCARD", ParserOptions { strict_line_endings: true, ..opts });
+    check("BEGIN:VCARD\r\nFN:x\u{0}y\r\nEND:VCARD\r\n", ParserOptions { forbid_control_chars: true, ..opts });
+    check("BEGIN:VCARD\r\nFN:x\ry\r\nEND:VCARD\r\n", ParserOptions { forbid_control_chars: true, ..opts });
+    check("BEGIN:VCARD\r\nTEL;HOME:1\r\nEND:VCARD\r\n", ParserOptions { require_param_values: true, ..opts });
+
+    // Empty property names are always rejected
+    assert!(parse_component("BEGIN:VCARD\r\n:x\r\nEND:VCARD\r\n").is_err());
+}
+
+#[test]
+fn test_parse_bytes() {
+    let clean = "BEGIN:VCARD\r\n\
+                 VERSION:3.0\r\n\
+                 FN:Jürgen Müller\r\n\
+                 N:Müller;Jürgen;;;\r\n\
+                 END:VCARD\r\n";
+    let expected = parse_component(clean).unwrap();
+
+    let mut bom = b"\xef\xbb\xbf".to_vec();
+    bom.extend_from_slice(clean.as_bytes());
+    assert_eq!(vobject::parse_bytes(&bom).unwrap(), expected);
+    assert_eq!(parse_component(std::str::from_utf8(&bom).unwrap()).unwrap(), expected);
+
+    let mut utf16le = b"\xff\xfe".to_vec();
+    utf16le.extend(clean.encode_utf16().flat_map(u16::to_le_bytes));
+    assert_eq!(vobject::parse_bytes(&utf16le).unwrap(), expected);
+
+    let mut utf16be = b"\xfe\xff".to_vec();
+    utf16be.extend(clean.encode_utf16().flat_map(u16::to_be_bytes));
+    assert_eq!(vobject::parse_bytes(&utf16be).unwrap(), expected);
+
+    utf16le.pop();
+    match vobject::parse_bytes(&utf16le) {
+        Err(vobject::error::VObjectError::Encoding(_)) => (),
+        other => panic!("{:?}", other),
+    }
+
+    let latin1 = clean.chars().map(|c| c as u8).collect::<Vec<_>>();
+    match vobject::parse_bytes(&latin1) {
+        #[cfg(feature = "encoding")]
+        Ok(component) => assert_eq!(component, expected),
+        #[cfg(not(feature = "encoding"))]
+        Err(vobject::error::VObjectError::Encoding(_)) => (),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn test_event_stream() {
+    use vobject::{Component, EventParser, ParseEvent};
+
+    fn build(input: &str) -> Result<Component, vobject::error::VObjectError> {
+        let mut stack: Vec<Component> = Vec::new();
+        for event in EventParser::new(input) {
+            match event? {
+                ParseEvent::ComponentStart(name) => stack.push(Component::new(name)),
+                ParseEvent::Property(prop) => stack.last_mut().unwrap().push(prop.into_owned()),
+                ParseEvent::ComponentEnd(name) => {
+                    let component = stack.pop().unwrap();
+                    assert_eq!(component.name, name);
+                    match stack.last_mut() {
+                        Some(parent) => parent.subcomponents.push(component),
+                        None => return Ok(component),
+                    }
+                },
+            }
+        }
+        panic!("event stream ended without closing {:?}", stack);
+    }
+
+    let fixtures = [
+        "BEGIN:VCARD\n\
+        VERSION:2.1\n\
+        N:Mustermann;Erika\n\
+        PHOTO;JPEG:http://commons.wikimedia.org/wiki/File:Erika_Mustermann_2010.jpg\n\
+        TEL;WORK;VOICE:(0221) 9999123\n\n\n\
+        TEL;HOME;VOICE:(0221) 1234567\n\
+        END:VCARD\n\r\n\n",
+        "BEGIN:VCARD\n\
+        N;ENCODING=QUOTED-PRINTABLE:Nikdo;Nikdo=\n\t\
+        vic\n\
+        TEL;WORK:5555\n \
+        4444\n\
+        END:VCARD",
+        "BEGIN:VCALENDAR\n\
+        VERSION:2.0\n\
+        BEGIN:VEVENT\n\
+        UID:461092315540@example.com\n\
+        ORGANIZER;CN=\"Alice Balder, Example Inc.\":MAILTO:alice@example.com\n\
+        END:VEVENT\n\
+        BEGIN:vtodo\n\
+        END:VTODO\n\
+        END:VCALENDAR\n",
+        "BEGIN:VCARD\n\
+        foo.EMAIL;TYPE=INTERNET:foo@example.com\n\
+        foo.X-ACTUAL-TYPE:CUSTOM\n\
+        END:VCARD\n",
+    ];
+    for input in fixtures.iter() {
+        assert_eq!(build(input).unwrap(), parse_component(input).unwrap());
+    }
+
+    for input in ["BEGIN:VCARD\nEND:VEVENT\n", "BEGIN:VCARD\nFN:x\n", "FN:x\n"].iter() {
+        assert_eq!(build(input).unwrap_err().to_string(), parse_component(input).unwrap_err().to_string());
+    }
+
+    // Stop as soon as the first event is seen, leaving the rest unparsed.
+    let input = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:a\nEND:VEVENT\nEND:VCALENDAR\n";
+    let mut events = EventParser::new(input);
+    let uid = events.find_map(|e| match e.unwrap() {
+        ParseEvent::Property(ref p) if p.name == "UID" => Some(p.raw_value.clone()),
+        _ => None,
+    });
+    assert_eq!(uid.unwrap(), "a");
+    assert_eq!(events.remainder(), "END:VEVENT\nEND:VCALENDAR\n");
+}
+
+#[test]
+fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<vobject::Component>();
+    assert_send_sync::<vobject::Property>();
+    assert_send_sync::<vobject::Vcard>();
+    assert_send_sync::<vobject::ICalendar>();
+    assert_send_sync::<vobject::icalendar::OwnedEvent>();
+    assert_send_sync::<vobject::error::VObjectError>();
+}
+
+#[test]
+fn test_parallel_parsing() {
+    use rayon::prelude::*;
+    use vobject::ICalendar;
+    use vobject::icalendar::OwnedEvent;
+
+    let files = (0..64)
+        .map(|i| format!("BEGIN:VCALENDAR\n\
+                          VERSION:2.0\n\
+                          PRODID:-//test//EN\n\
+                          BEGIN:VEVENT\n\
+                          UID:event-{i}-a\n\
+                          SUMMARY:First {i}\n\
+                          END:VEVENT\n\
+                          BEGIN:VEVENT\n\
+                          UID:event-{i}-b\n\
+                          SUMMARY:Second {i}\n\
+                          END:VEVENT\n\
+                          END:VCALENDAR\n", i = i))
+        .collect::<Vec<_>>();
+
+    let calendars = files.par_iter()
+        .map(|f| ICalendar::build(f))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    // Only the events are sent back, not the calendars holding them.
+    let events = calendars.par_iter()
+        .flat_map_iter(|cal| cal.events().filter_map(Result::ok).map(|ev| ev.to_owned()).collect::<Vec<OwnedEvent>>())
+        .collect::<Vec<_>>();
+    assert_eq!(events.len(), 128);
+
+    let summaries = events.par_iter()
+        .map(|ev| ev.as_event().summary().unwrap().raw().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(summaries[0], "First 0");
+    assert_eq!(summaries[127], "Second 63");
+}
+
+#[test]
+fn test_photo_data() {
+    use vobject::param::Parameters;
+    use vobject::vcard::{ImageData, ImageRef, Logo, Photo, VcardVersion};
+    use vobject::Vcard;
+
+    let png: &[u8] = include_bytes!("fixtures/images/pixel.png");
+    let gif: &[u8] = include_bytes!("fixtures/images/pixel.gif");
+
+    for version in &[VcardVersion::V2_1, VcardVersion::V3_0, VcardVersion::V4_0] {
+        let card = Vcard::builder()
+            .with_version(version.as_str())
+            .with_fullname("Erika Mustermann")
+            .with_photo_binary(Parameters::new(), png, "image/png")
+            .build()
+            .unwrap();
+        let card = Vcard::build(&card.to_string()).unwrap();
+        let expected = ImageData { mime: Some("image/png".into()), bytes: png.to_vec() };
+        assert_eq!(card.photo()[0].data().unwrap(), ImageRef::Data(expected));
+
+        let logo = Logo::from_bytes(gif, "image/gif", *version);
+        match logo.data().unwrap() {
+            ImageRef::Data(data) => {
+                assert_eq!(data.mime.as_deref(), Some("image/gif"));
+                assert_eq!(data.bytes, gif);
+            },
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    // Without a media type, the format is recognized from the data
+    let photo = Photo::from_raw(format!("data:;base64,{}", "R0lGODlhAQABAIAAAP///wAAACH5BAEAAAAALAAAAAABAAEAAAICRAEAOw=="));
+    match photo.data().unwrap() {
+        ImageRef::Data(data) => assert_eq!((data.mime.as_deref(), &data.bytes[..]), (Some("image/gif"), gif)),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_write_to() {
+    use std::io;
+    use vobject::{write_component, write_component_to, WriteOptions};
+    use vobject::icalendar::ICalendar;
+    use vobject::vcard::Vcard;
+
+    /// Accepts a few bytes, then fails.
+    struct FailingWriter(usize);
+
+    impl io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::other("disk full"));
+            }
+            let n = buf.len().min(self.0);
+            self.0 -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let cal = ICalendar::build("BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//test//EN\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:a\r\n\
+        SUMMARY:Planning\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n").unwrap();
+    let mut buf = Vec::new();
+    cal.write_to(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), cal.to_string());
+
+    let card = Vcard::build("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Erika\r\nEND:VCARD\r\n").unwrap();
+    let mut buf = Vec::new();
+    card.write_to(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), write_component(&card));
+
+    for limit in [0, 10, 30] {
+        let err = cal.write_to(&mut FailingWriter(limit)).unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+        let err = write_component_to(&card, &mut FailingWriter(limit), &WriteOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+}
+
+#[test]
+fn test_trailing_version() {
+    use vobject::{normalize_vcard, parse_components};
+    use vobject::vcard::{ValidationError, Vcard, VcardVersion};
+
+    let input = include_str!("fixtures/trailing_version.vcf");
+
+    let cards = parse_components(input)
+        .unwrap()
+        .into_iter()
+        .map(|c| Vcard::from_component(c).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(cards.len(), 2);
+    for card in &cards {
+        assert_eq!(card.version_enum(), Some(VcardVersion::V3_0));
+    }
+
+    let problems = Vcard::validate_source(input).unwrap();
+    assert_eq!(problems, vec![vec![ValidationError::VersionNotFirst], vec![]]);
+    assert!(problems[0][0].is_warning());
+    let first_card = &input[..input.find("END:VCARD\r\n").unwrap() + "END:VCARD\r\n".len()];
+    assert!(Vcard::build_strict(first_card).is_ok());
+
+    let normalized = normalize_vcard(input).unwrap();
+    let versions = normalized.match_indices("BEGIN:VCARD\r\nVERSION:3.0\r\n").count();
+    assert_eq!(versions, 2);
+    assert_eq!(normalized.matches("VERSION").count(), 2);
+    assert!(Vcard::validate_source(&normalized).unwrap().iter().all(Vec::is_empty));
+}